@@ -0,0 +1,182 @@
+//! A headless take on the `stress_dwarves` example, timing a frame as
+//! thousands of dwarves stumble around a map of spawned chunks.
+//!
+//! The tilemap runs in an app with the `Tilemap2DPlugin` but without any
+//! rendering, so that a frame covers the tile edits and the chunk update
+//! system which writes the changed tiles into the chunk meshes.
+
+use bevy_app::App;
+use bevy_asset::{AddAsset, AssetPlugin, Handle};
+use bevy_core::CorePlugin;
+use bevy_ecs::Entity;
+use bevy_render::mesh::Mesh;
+use bevy_sprite::TextureAtlas;
+use bevy_tilemap::prelude::*;
+use bevy_window::WindowResized;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const DWARF_COUNT: usize = 10_000;
+
+/// A small xorshift generator, so that every run stumbles the same way.
+struct Stumbles(u32);
+
+impl Stumbles {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// A step of -1, 0 or 1.
+    fn next(&mut self) -> i32 {
+        (self.next_u32() % 3) as i32 - 1
+    }
+
+    /// A coordinate between `-bound` and `bound`, both included.
+    fn within(&mut self, bound: i32) -> i32 {
+        (self.next_u32() % (bound * 2 + 1) as u32) as i32 - bound
+    }
+}
+
+/// A spawned map in a headless app, with the dwarves and the bounds they stay
+/// within.
+struct Frame {
+    app: App,
+    map: Entity,
+    dwarves: Vec<(i32, i32)>,
+    bounds: (i32, i32),
+}
+
+fn build_map() -> Frame {
+    let mut map = Tilemap::builder()
+        .dimensions(3, 3)
+        .tile_dimensions(32, 32)
+        .chunk_dimensions(32, 32)
+        .add_layer(TilemapLayer::default(), 0)
+        .add_layer(
+            TilemapLayer {
+                kind: LayerKind::Sparse,
+                ..Default::default()
+            },
+            1,
+        )
+        .texture_atlas(Handle::default())
+        .finish()
+        .unwrap();
+
+    let width = (map.width().unwrap() * map.chunk_width()) as i32;
+    let height = (map.height().unwrap() * map.chunk_height()) as i32;
+    for y in -1..2 {
+        for x in -1..2 {
+            map.insert_chunk((x, y)).unwrap();
+        }
+    }
+
+    let mut tiles = Vec::new();
+    for y in (-height / 2)..(height / 2) {
+        for x in (-width / 2)..(width / 2) {
+            tiles.push(Tile {
+                point: (x, y),
+                ..Default::default()
+            });
+        }
+    }
+    let bounds = (width / 2 - 2, height / 2 - 2);
+    let mut stumbles = Stumbles(0x2545_f491);
+    let mut dwarves = Vec::with_capacity(DWARF_COUNT);
+    for _ in 0..DWARF_COUNT {
+        let point = (stumbles.within(bounds.0), stumbles.within(bounds.1));
+        tiles.push(Tile {
+            point,
+            sprite_index: 1,
+            z_order: 1,
+            ..Default::default()
+        });
+        dwarves.push(point);
+    }
+    map.insert_tiles(tiles).unwrap();
+    for y in -1..2 {
+        for x in -1..2 {
+            map.spawn_chunk((x, y)).unwrap();
+        }
+    }
+
+    let mut app = App::build();
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(AssetPlugin::default())
+        .add_asset::<Mesh>()
+        .add_asset::<TextureAtlas>()
+        .add_event::<WindowResized>()
+        .add_plugin(Tilemap2DPlugin::default());
+    let mut app = app.app;
+    let map = app.world.spawn(TilemapBundle {
+        tilemap: map,
+        transform: Default::default(),
+        global_transform: Default::default(),
+    });
+    // Spawns the chunks and builds their meshes.
+    app.update();
+
+    Frame {
+        app,
+        map,
+        dwarves,
+        bounds,
+    }
+}
+
+fn stumble(frame: &mut Frame, stumbles: &mut Stumbles) {
+    let (bound_x, bound_y) = frame.bounds;
+    let mut map = frame.app.world.get_mut::<Tilemap>(frame.map).unwrap();
+    for dwarf in frame.dwarves.iter_mut() {
+        let previous = *dwarf;
+        dwarf.0 = (dwarf.0 + stumbles.next()).max(-bound_x).min(bound_x);
+        dwarf.1 = (dwarf.1 + stumbles.next()).max(-bound_y).min(bound_y);
+        if previous == *dwarf {
+            continue;
+        }
+        map.clear_tile(previous, 1).unwrap();
+        map.insert_tile(Tile {
+            point: *dwarf,
+            sprite_index: 1,
+            z_order: 1,
+            ..Default::default()
+        })
+        .unwrap();
+    }
+}
+
+fn stress_dwarves(c: &mut Criterion) {
+    c.bench_function("stress_dwarves frame", |b| {
+        b.iter_batched(
+            build_map,
+            |mut frame| {
+                let mut stumbles = Stumbles(0x9e37_79b9);
+                stumble(&mut frame, &mut stumbles);
+                frame.app.update();
+                frame
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("stress_dwarves chunk update", |b| {
+        b.iter_batched(
+            || {
+                let mut frame = build_map();
+                let mut stumbles = Stumbles(0x9e37_79b9);
+                stumble(&mut frame, &mut stumbles);
+                frame
+            },
+            |mut frame| {
+                frame.app.update();
+                frame
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, stress_dwarves);
+criterion_main!(benches);
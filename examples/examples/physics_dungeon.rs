@@ -18,7 +18,7 @@ use bevy_rapier2d::{
     physics::{RapierConfiguration, RigidBodyHandleComponent},
     rapier::{dynamics::RigidBodySet, ncollide::math::Vector},
 };
-use bevy_tilemap::prelude::*;
+use bevy_tilemap::{collision::ColliderMerge, prelude::*};
 use rand::Rng;
 
 const CHUNK_WIDTH: u32 = 16;
@@ -97,6 +97,7 @@ fn load(
         let wall_layer = TilemapLayer {
             kind: LayerKind::Sparse,
             interaction_groups: wall_interactions,
+            collider_merge: ColliderMerge::Rectangles,
             ..Default::default()
         };
 
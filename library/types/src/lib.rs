@@ -35,6 +35,8 @@ pub mod dimension;
 pub mod point;
 #[no_implicit_prelude]
 pub mod prelude;
+#[no_implicit_prelude]
+pub mod topology;
 
 /// A custom prelude around all the types we need from `std`, `bevy`, and `serde`.
 #[no_implicit_prelude]
@@ -61,12 +63,15 @@ mod lib {
         default::Default,
         error::Error,
         fmt::{Debug, Display, Formatter, Result as FmtResult},
-        ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+        iter::{IntoIterator, Iterator},
+        ops::{Add, AddAssign, Div, DivAssign, Fn, Mul, MulAssign, Neg, Sub, SubAssign},
+        option::Option::{self, *},
         result::Result::{self, *},
+        vec::Vec,
     };
 
     // Macros
-    pub(crate) use std::write;
+    pub(crate) use std::{vec, write};
 
     #[cfg(debug_assertions)]
     #[allow(unused_imports)]
@@ -0,0 +1,550 @@
+//! Grid topologies and the algorithms which depend on them.
+//!
+//! Square grids use the points as they are. Isometric grids are square grids
+//! seen from an angle, so they share the square algorithms in diamond
+//! coordinates, into which [`GridTopology::StaggeredIsometric`] points are
+//! converted first. Hex grids are converted to cube coordinates first, so
+//! every algorithm works the same for all of the hex layouts, including the
+//! offset ones like [`GridTopology::HexEvenRows`].
+//!
+//! Directions and rotations are clockwise with the Y axis pointing up.
+//!
+//! # Examples
+//! ```
+//! use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+//!
+//! let topology = GridTopology::HexOddCols;
+//!
+//! assert_eq!(topology.distance((0, 0), (3, 1)), 3);
+//! assert_eq!(topology.ring((2, 2), 1).len(), 6);
+//! assert_eq!(topology.spiral((2, 2), 2).len(), 19);
+//! assert_eq!(topology.line((0, 0), (3, 1)).last(), Some(&Point2::new(3, 1)));
+//! ```
+
+use crate::{
+    lib::*,
+    point::{Point2, Point3},
+};
+
+/// The axial directions of the hex neighbours, clockwise.
+///
+/// For hexes with a pointy top these start north-east, for hexes with a flat
+/// top these start north.
+const HEX_DIRECTIONS: [(i32, i32); 6] = [(0, 1), (1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1)];
+
+/// The directions of the square neighbours, clockwise starting north.
+const SQUARE_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+/// Topology of the tilemap grid (square, isometric or hex)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GridTopology {
+    /// Square grid
+    Square,
+    /// Hex grid with rows offset (hexes with pointy top).
+    HexY,
+    /// Hex grid with columns offset (hexes with flat top).
+    HexX,
+    /// Hex grid with offset on even rows (hexes with pointy top).
+    HexEvenRows,
+    /// Hex grid with offset on odd rows (hexes with pointy top).
+    HexOddRows,
+    /// Hex grid with offset on even columns (hexes with flat top).
+    HexEvenCols,
+    /// Hex grid with offset on odd columns (hexes with flat top).
+    HexOddCols,
+    /// Isometric grid of diamonds, where the X axis points north-east and
+    /// the Y axis north-west.
+    Isometric,
+    /// Isometric grid of diamonds laid out in rows, where odd rows are
+    /// offset by half a tile to the east.
+    ///
+    /// Each row is half a tile high, so the chunk height must be even.
+    StaggeredIsometric,
+}
+
+impl GridTopology {
+    /// Returns `true` if the topology is one of the hex grids.
+    pub fn is_hex(&self) -> bool {
+        use GridTopology::*;
+        match self {
+            HexY | HexX | HexEvenRows | HexOddRows | HexEvenCols | HexOddCols => true,
+            Square | Isometric | StaggeredIsometric => false,
+        }
+    }
+
+    /// Returns `true` if the topology is one of the isometric grids.
+    pub fn is_isometric(&self) -> bool {
+        use GridTopology::*;
+        match self {
+            Isometric | StaggeredIsometric => true,
+            Square | HexY | HexX | HexEvenRows | HexOddRows | HexEvenCols | HexOddCols => false,
+        }
+    }
+
+    /// Returns `true` if the topology is a hex grid with pointy tops, which
+    /// are laid out in rows.
+    fn is_pointy(&self) -> bool {
+        use GridTopology::*;
+        match self {
+            HexY | HexEvenRows | HexOddRows => true,
+            Square | Isometric | StaggeredIsometric | HexX | HexEvenCols | HexOddCols => false,
+        }
+    }
+
+    /// Converts a point into axial coordinates.
+    fn point_to_axial(&self, point: Point2) -> (i32, i32) {
+        use GridTopology::*;
+        match self {
+            Square | Isometric | StaggeredIsometric | HexY | HexX => (point.x, point.y),
+            HexEvenRows => (point.x - ((point.y + 1) >> 1), point.y),
+            HexOddRows => (point.x - (point.y >> 1), point.y),
+            HexEvenCols => (point.x, point.y - ((point.x + 1) >> 1)),
+            HexOddCols => (point.x, point.y - (point.x >> 1)),
+        }
+    }
+
+    /// Converts axial coordinates into a point.
+    fn axial_to_point(&self, (q, r): (i32, i32)) -> Point2 {
+        use GridTopology::*;
+        match self {
+            Square | Isometric | StaggeredIsometric | HexY | HexX => Point2::new(q, r),
+            HexEvenRows => Point2::new(q + ((r + 1) >> 1), r),
+            HexOddRows => Point2::new(q + (r >> 1), r),
+            HexEvenCols => Point2::new(q, r + ((q + 1) >> 1)),
+            HexOddCols => Point2::new(q, r + (q >> 1)),
+        }
+    }
+
+    /// Converts a point into cube coordinates, where `x + y + z == 0`.
+    ///
+    /// For hexes with a pointy top `y` is the row, for hexes with a flat top
+    /// `x` is the column. Square grids keep `x` and `y` as they are.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{
+    ///     point::{Point2, Point3},
+    ///     topology::GridTopology,
+    /// };
+    ///
+    /// let topology = GridTopology::HexEvenRows;
+    ///
+    /// let cube = topology.point_to_cube((2, 3));
+    /// assert_eq!(cube, Point3::new(0, 3, -3));
+    /// assert_eq!(topology.cube_to_point(cube), Point2::new(2, 3));
+    /// ```
+    pub fn point_to_cube<P: Into<Point2>>(&self, point: P) -> Point3 {
+        let (q, r) = self.point_to_axial(point.into());
+        Point3::new(q, r, -q - r)
+    }
+
+    /// Converts cube coordinates back into a point.
+    pub fn cube_to_point(&self, cube: Point3) -> Point2 {
+        self.axial_to_point((cube.x, cube.y))
+    }
+
+    /// Converts a point into diamond coordinates, where the neighbours of a
+    /// tile are the square neighbours.
+    ///
+    /// Only [`GridTopology::StaggeredIsometric`] points are converted, its
+    /// diamond coordinates are those of [`GridTopology::Isometric`]. Every
+    /// other topology keeps the point as it is.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// let topology = GridTopology::StaggeredIsometric;
+    ///
+    /// let diamond = topology.point_to_diamond((0, 1));
+    /// assert_eq!(diamond, Point2::new(1, 0));
+    /// assert_eq!(topology.diamond_to_point(diamond), Point2::new(0, 1));
+    /// ```
+    pub fn point_to_diamond<P: Into<Point2>>(&self, point: P) -> Point2 {
+        let point: Point2 = point.into();
+        match self {
+            GridTopology::StaggeredIsometric => {
+                let odd = point.y & 1;
+                Point2::new(point.x + (point.y + odd) / 2, (point.y - odd) / 2 - point.x)
+            }
+            _ => point,
+        }
+    }
+
+    /// Converts diamond coordinates back into a point.
+    pub fn diamond_to_point(&self, diamond: Point2) -> Point2 {
+        match self {
+            GridTopology::StaggeredIsometric => {
+                let y = diamond.x + diamond.y;
+                Point2::new((diamond.x - diamond.y - (y & 1)) / 2, y)
+            }
+            _ => diamond,
+        }
+    }
+
+    /// Returns the points of the tiles which share an edge with the tile at a
+    /// point, in clockwise order.
+    ///
+    /// Square grids return 4 points starting north, isometric grids 4 points
+    /// starting north-west. Hex grids return 6 points, starting north-east
+    /// for hexes with a pointy top and north for hexes with a flat top.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// let neighbours = GridTopology::Square.neighbours((0, 0));
+    /// assert_eq!(
+    ///     neighbours,
+    ///     vec![Point2::new(0, 1), Point2::new(1, 0), Point2::new(0, -1), Point2::new(-1, 0)]
+    /// );
+    ///
+    /// assert_eq!(GridTopology::HexEvenRows.neighbours((0, 0)).len(), 6);
+    /// ```
+    pub fn neighbours<P: Into<Point2>>(&self, point: P) -> Vec<Point2> {
+        let point: Point2 = point.into();
+        if !self.is_hex() {
+            let diamond = self.point_to_diamond(point);
+            return SQUARE_DIRECTIONS
+                .iter()
+                .map(|(x, y)| self.diamond_to_point(Point2::new(diamond.x + x, diamond.y + y)))
+                .collect();
+        }
+
+        let (q, r) = self.point_to_axial(point);
+        HEX_DIRECTIONS
+            .iter()
+            .map(|(dq, dr)| self.axial_to_point((q + dq, r + dr)))
+            .collect()
+    }
+
+    /// Returns the amount of steps between two points, when only stepping to
+    /// neighbours.
+    ///
+    /// On square and isometric grids this is the Manhattan distance.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::topology::GridTopology;
+    ///
+    /// assert_eq!(GridTopology::Square.distance((0, 0), (2, -3)), 5);
+    /// assert_eq!(GridTopology::HexY.distance((0, 0), (2, -3)), 3);
+    /// ```
+    pub fn distance<P: Into<Point2>>(&self, from: P, to: P) -> u32 {
+        let from: Point2 = from.into();
+        let to: Point2 = to.into();
+        if !self.is_hex() {
+            let from = self.point_to_diamond(from);
+            let to = self.point_to_diamond(to);
+            return ((to.x - from.x).abs() + (to.y - from.y).abs()) as u32;
+        }
+
+        let from = self.point_to_cube(from);
+        let to = self.point_to_cube(to);
+        (((to.x - from.x).abs() + (to.y - from.y).abs() + (to.z - from.z).abs()) / 2) as u32
+    }
+
+    /// Returns all points at a distance from a center point, in clockwise
+    /// order.
+    ///
+    /// The ring starts at the first neighbour direction, see [`neighbours`].
+    /// A radius of zero returns only the center point.
+    ///
+    /// [`neighbours`]: GridTopology::neighbours
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::topology::GridTopology;
+    ///
+    /// assert_eq!(GridTopology::Square.ring((0, 0), 2).len(), 8);
+    /// assert_eq!(GridTopology::HexX.ring((0, 0), 2).len(), 12);
+    /// ```
+    pub fn ring<P: Into<Point2>>(&self, center: P, radius: u32) -> Vec<Point2> {
+        let center: Point2 = center.into();
+        if radius == 0 {
+            return vec![center];
+        }
+        let radius = radius as i32;
+
+        if !self.is_hex() {
+            let center = self.point_to_diamond(center);
+            let mut points = Vec::with_capacity(radius as usize * 4);
+            for (x, y) in SQUARE_DIRECTIONS.iter() {
+                // Walk from one direction towards the next one clockwise.
+                for step in 0..radius {
+                    points.push(self.diamond_to_point(Point2::new(
+                        center.x + x * (radius - step) + y * step,
+                        center.y + y * (radius - step) - x * step,
+                    )));
+                }
+            }
+            return points;
+        }
+
+        let (q, r) = self.point_to_axial(center);
+        let (first_q, first_r) = HEX_DIRECTIONS[0];
+        let mut axial = (q + first_q * radius, r + first_r * radius);
+        let mut points = Vec::with_capacity(radius as usize * 6);
+        for (dq, dr) in HEX_DIRECTIONS.iter().cycle().skip(2).take(6) {
+            for _ in 0..radius {
+                points.push(self.axial_to_point(axial));
+                axial = (axial.0 + dq, axial.1 + dr);
+            }
+        }
+        points
+    }
+
+    /// Returns all points within a distance from a center point, starting
+    /// with the center and then each ring outwards.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// let spiral = GridTopology::HexEvenCols.spiral((1, 1), 1);
+    ///
+    /// assert_eq!(spiral.len(), 7);
+    /// assert_eq!(spiral.first(), Some(&Point2::new(1, 1)));
+    /// ```
+    pub fn spiral<P: Into<Point2>>(&self, center: P, radius: u32) -> Vec<Point2> {
+        let center: Point2 = center.into();
+        (0..=radius)
+            .flat_map(|radius| self.ring(center, radius))
+            .collect()
+    }
+
+    /// Returns the points of a line between two points, including both.
+    ///
+    /// Square and isometric grids use Bresenham's line algorithm, hex grids
+    /// linearly interpolate cube coordinates.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// let line = GridTopology::Square.line((0, 0), (3, 1));
+    /// assert_eq!(
+    ///     line,
+    ///     vec![Point2::new(0, 0), Point2::new(1, 0), Point2::new(2, 1), Point2::new(3, 1)]
+    /// );
+    ///
+    /// let line = GridTopology::HexOddRows.line((0, 0), (4, 0));
+    /// assert_eq!(line.len(), 5);
+    /// ```
+    pub fn line<P: Into<Point2>>(&self, from: P, to: P) -> Vec<Point2> {
+        let from: Point2 = from.into();
+        let to: Point2 = to.into();
+        if !self.is_hex() {
+            return bresenham(self.point_to_diamond(from), self.point_to_diamond(to))
+                .into_iter()
+                .map(|diamond| self.diamond_to_point(diamond))
+                .collect();
+        }
+
+        let steps = self.distance(from, to);
+        if steps == 0 {
+            return vec![from];
+        }
+        let from = self.point_to_cube(from);
+        let to = self.point_to_cube(to);
+        // Nudges the line off the edges between hexes, so that points are
+        // rounded consistently.
+        let (from_x, from_y, from_z) = (
+            from.x as f32 + 1e-6,
+            from.y as f32 + 2e-6,
+            from.z as f32 - 3e-6,
+        );
+        (0..=steps)
+            .map(|step| {
+                let t = step as f32 / steps as f32;
+                let cube = cube_round(
+                    from_x + (to.x as f32 + 1e-6 - from_x) * t,
+                    from_y + (to.y as f32 + 2e-6 - from_y) * t,
+                    from_z + (to.z as f32 - 3e-6 - from_z) * t,
+                );
+                self.cube_to_point(cube)
+            })
+            .collect()
+    }
+
+    /// Rotates a point around a center point clockwise, in steps of 90
+    /// degrees on square and isometric grids and 60 degrees on hex grids.
+    ///
+    /// Negative steps rotate counterclockwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// assert_eq!(GridTopology::Square.rotate((0, 2), (0, 0), 1), Point2::new(2, 0));
+    ///
+    /// let topology = GridTopology::HexY;
+    /// let north_east = topology.neighbours((0, 0))[0];
+    /// let east = topology.neighbours((0, 0))[1];
+    /// assert_eq!(topology.rotate(north_east, Point2::new(0, 0), 1), east);
+    /// ```
+    pub fn rotate<P: Into<Point2>>(&self, point: P, center: P, steps: i32) -> Point2 {
+        let point: Point2 = point.into();
+        let center: Point2 = center.into();
+        if !self.is_hex() {
+            return self.reflect_diamond(point, center, |mut x, mut y| {
+                for _ in 0..steps.rem_euclid(4) {
+                    let rotated = (y, -x);
+                    x = rotated.0;
+                    y = rotated.1;
+                }
+                (x, y)
+            });
+        }
+
+        let cube = self.point_to_cube(point);
+        let center = self.point_to_cube(center);
+        let (mut x, mut y, mut z) = (cube.x - center.x, cube.y - center.y, cube.z - center.z);
+        for _ in 0..steps.rem_euclid(6) {
+            let rotated = (-z, -x, -y);
+            x = rotated.0;
+            y = rotated.1;
+            z = rotated.2;
+        }
+        self.cube_to_point(Point3::new(center.x + x, center.y + y, center.z + z))
+    }
+
+    /// Reflects a point horizontally around a center point, so that what is
+    /// east of the center ends up west of it.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// assert_eq!(GridTopology::Square.reflect_x((3, 1), (1, 0)), Point2::new(-1, 1));
+    ///
+    /// let topology = GridTopology::HexOddCols;
+    /// let north_east = topology.neighbours((0, 0))[1];
+    /// let north_west = topology.neighbours((0, 0))[5];
+    /// assert_eq!(topology.reflect_x(north_east, Point2::new(0, 0)), north_west);
+    /// ```
+    pub fn reflect_x<P: Into<Point2>>(&self, point: P, center: P) -> Point2 {
+        let point: Point2 = point.into();
+        let center: Point2 = center.into();
+        if !self.is_hex() {
+            // Isometric grids have east and west along the diagonal where
+            // both axes are equal.
+            return self.reflect_diamond(point, center, |x, y| {
+                if self.is_isometric() {
+                    (y, x)
+                } else {
+                    (-x, y)
+                }
+            });
+        }
+
+        self.reflect_cube(point, center, |x, y, z| {
+            if self.is_pointy() {
+                (z, y, x)
+            } else {
+                (-x, -z, -y)
+            }
+        })
+    }
+
+    /// Reflects a point vertically around a center point, so that what is
+    /// north of the center ends up south of it.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap_types::{point::Point2, topology::GridTopology};
+    ///
+    /// assert_eq!(GridTopology::Square.reflect_y((3, 1), (1, 0)), Point2::new(3, -1));
+    ///
+    /// let topology = GridTopology::HexEvenRows;
+    /// let north_east = topology.neighbours((0, 0))[0];
+    /// let south_east = topology.neighbours((0, 0))[2];
+    /// assert_eq!(topology.reflect_y(north_east, Point2::new(0, 0)), south_east);
+    /// ```
+    pub fn reflect_y<P: Into<Point2>>(&self, point: P, center: P) -> Point2 {
+        let point: Point2 = point.into();
+        let center: Point2 = center.into();
+        if !self.is_hex() {
+            return self.reflect_diamond(point, center, |x, y| {
+                if self.is_isometric() {
+                    (-y, -x)
+                } else {
+                    (x, -y)
+                }
+            });
+        }
+
+        self.reflect_cube(point, center, |x, y, z| {
+            if self.is_pointy() {
+                (-z, -y, -x)
+            } else {
+                (x, z, y)
+            }
+        })
+    }
+
+    /// Applies a rotation or reflection of diamond coordinates relative to a
+    /// center point.
+    fn reflect_diamond<F>(&self, point: Point2, center: Point2, reflect: F) -> Point2
+    where
+        F: Fn(i32, i32) -> (i32, i32),
+    {
+        let diamond = self.point_to_diamond(point);
+        let center = self.point_to_diamond(center);
+        let (x, y) = reflect(diamond.x - center.x, diamond.y - center.y);
+        self.diamond_to_point(Point2::new(center.x + x, center.y + y))
+    }
+
+    /// Applies a reflection of cube coordinates relative to a center point.
+    fn reflect_cube<F>(&self, point: Point2, center: Point2, reflect: F) -> Point2
+    where
+        F: Fn(i32, i32, i32) -> (i32, i32, i32),
+    {
+        let cube = self.point_to_cube(point);
+        let center = self.point_to_cube(center);
+        let (x, y, z) = reflect(cube.x - center.x, cube.y - center.y, cube.z - center.z);
+        self.cube_to_point(Point3::new(center.x + x, center.y + y, center.z + z))
+    }
+}
+
+/// Rounds fractional cube coordinates to the closest cube.
+fn cube_round(x: f32, y: f32, z: f32) -> Point3 {
+    let (mut rx, mut ry, mut rz) = (x.round(), y.round(), z.round());
+    let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+    if dx > dy && dx > dz {
+        rx = -ry - rz;
+    } else if dy > dz {
+        ry = -rx - rz;
+    } else {
+        rz = -rx - ry;
+    }
+    Point3::new(rx as i32, ry as i32, rz as i32)
+}
+
+/// Returns the points of a line on a square grid with Bresenham's algorithm.
+fn bresenham(from: Point2, to: Point2) -> Vec<Point2> {
+    let dx = (to.x - from.x).abs();
+    let dy = -(to.y - from.y).abs();
+    let step_x = if from.x < to.x { 1 } else { -1 };
+    let step_y = if from.y < to.y { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut point = from;
+    let mut points = Vec::with_capacity(dx.max(-dy) as usize + 1);
+    loop {
+        points.push(point);
+        if point == to {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            point.x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            point.y += step_y;
+        }
+    }
+    points
+}
@@ -0,0 +1,654 @@
+//! Animated tiles.
+//!
+//! A [`TileAnimation`] is a list of sprite indices with a duration for each
+//! frame. It can either be attached to a single tile with
+//! [`Tilemap::insert_tile_animation`] or to a sprite index with
+//! [`Tilemap::insert_sprite_animation`], in which case every tile with that
+//! sprite index is animated.
+//!
+//! Animations are advanced in the tilemap stage and every chunk which changed
+//! is updated once per frame, regardless of how many of its tiles changed.
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{animation::{AnimationMode, TileAnimation}, prelude::*};
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+//!
+//! // Every water tile, which is sprite 4, cycles through sprites 4 to 7.
+//! let water = TileAnimation::uniform(vec![4, 5, 6, 7], 0.25, AnimationMode::Loop);
+//! tilemap.insert_sprite_animation(4, water);
+//!
+//! // A single torch flickers back and forth.
+//! let torch = TileAnimation::new(vec![(10, 0.1), (11, 0.2), (12, 0.1)], AnimationMode::PingPong);
+//! tilemap.insert_tile_animation((3, 3), 1, torch);
+//! ```
+//!
+//! [`Tilemap::insert_tile_animation`]: crate::tilemap::Tilemap::insert_tile_animation
+//! [`Tilemap::insert_sprite_animation`]: crate::tilemap::Tilemap::insert_sprite_animation
+
+use crate::{lib::*, Tilemap};
+
+/// How an animation continues after its last frame.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AnimationMode {
+    /// Starts over from the first frame.
+    Loop,
+    /// Plays the frames backwards, then forwards again.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+impl Default for AnimationMode {
+    fn default() -> AnimationMode {
+        AnimationMode::Loop
+    }
+}
+
+/// A single frame of an animation.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AnimationFrame {
+    /// The sprite index of the frame in the texture atlas.
+    pub sprite_index: usize,
+    /// How long the frame is shown, in seconds.
+    pub duration: f32,
+}
+
+/// An animation made of sprite indices.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TileAnimation {
+    /// The frames of the animation in order.
+    frames: Vec<AnimationFrame>,
+    /// How the animation continues after the last frame.
+    mode: AnimationMode,
+}
+
+impl TileAnimation {
+    /// Constructs an animation from pairs of sprite indices and durations in
+    /// seconds.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::animation::{AnimationMode, TileAnimation};
+    ///
+    /// let animation = TileAnimation::new(vec![(0, 0.5), (1, 0.25)], AnimationMode::Loop);
+    ///
+    /// assert_eq!(animation.frames().len(), 2);
+    /// assert_eq!(animation.duration(), 0.75);
+    /// ```
+    pub fn new<I>(frames: I, mode: AnimationMode) -> TileAnimation
+    where
+        I: IntoIterator<Item = (usize, f32)>,
+    {
+        TileAnimation {
+            frames: frames
+                .into_iter()
+                .map(|(sprite_index, duration)| AnimationFrame {
+                    sprite_index,
+                    duration,
+                })
+                .collect(),
+            mode,
+        }
+    }
+
+    /// Constructs an animation where every frame has the same duration in
+    /// seconds.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::animation::{AnimationMode, TileAnimation};
+    ///
+    /// let animation = TileAnimation::uniform(vec![0, 1, 2, 3], 0.25, AnimationMode::Once);
+    ///
+    /// assert_eq!(animation.duration(), 1.0);
+    /// ```
+    pub fn uniform<I>(sprite_indices: I, duration: f32, mode: AnimationMode) -> TileAnimation
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        TileAnimation::new(
+            sprite_indices
+                .into_iter()
+                .map(|sprite_index| (sprite_index, duration)),
+            mode,
+        )
+    }
+
+    /// Returns the frames of the animation.
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    /// Returns the mode of the animation.
+    pub fn mode(&self) -> AnimationMode {
+        self.mode
+    }
+
+    /// Returns the duration of playing all frames once, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// Returns the duration of one full cycle, in seconds.
+    ///
+    /// For ping-pong animations this includes playing the frames backwards.
+    fn cycle_duration(&self) -> f32 {
+        self.duration() + self.backwards().map(|frame| frame.duration).sum::<f32>()
+    }
+
+    /// Returns the frames which are played backwards after the last frame.
+    ///
+    /// These are all frames except the first and the last one in reverse
+    /// order for ping-pong animations, and none otherwise.
+    fn backwards(&self) -> impl Iterator<Item = &AnimationFrame> {
+        let len = match self.mode {
+            AnimationMode::PingPong => self.frames.len().saturating_sub(2),
+            AnimationMode::Loop | AnimationMode::Once => 0,
+        };
+        self.frames.iter().rev().skip(1).take(len)
+    }
+
+    /// Returns the sprite index which is shown after the elapsed time in
+    /// seconds.
+    ///
+    /// Returns `None` if the animation has no frames.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::animation::{AnimationMode, TileAnimation};
+    ///
+    /// let animation = TileAnimation::uniform(vec![0, 1, 2], 1.0, AnimationMode::PingPong);
+    ///
+    /// assert_eq!(animation.sprite_index_at(0.5), Some(0));
+    /// assert_eq!(animation.sprite_index_at(2.5), Some(2));
+    /// assert_eq!(animation.sprite_index_at(3.5), Some(1));
+    /// assert_eq!(animation.sprite_index_at(4.5), Some(0));
+    /// ```
+    pub fn sprite_index_at(&self, elapsed: f32) -> Option<usize> {
+        let last = self.frames.last()?;
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return Some(last.sprite_index);
+        }
+
+        let mut time = match self.mode {
+            AnimationMode::Once if elapsed >= duration => return Some(last.sprite_index),
+            AnimationMode::Once => elapsed,
+            AnimationMode::Loop | AnimationMode::PingPong => elapsed % self.cycle_duration(),
+        };
+        for frame in self.frames.iter().chain(self.backwards()) {
+            if time < frame.duration {
+                return Some(frame.sprite_index);
+            }
+            time -= frame.duration;
+        }
+
+        Some(last.sprite_index)
+    }
+
+    /// Returns `true` if the animation does not change anymore after the
+    /// elapsed time in seconds.
+    pub fn is_finished(&self, elapsed: f32) -> bool {
+        self.mode == AnimationMode::Once && elapsed >= self.duration()
+    }
+}
+
+/// The playback state of an animation.
+#[derive(Clone, PartialEq, Debug)]
+struct AnimationState {
+    /// The animation being played.
+    animation: TileAnimation,
+    /// The time since the start of the current cycle in seconds.
+    elapsed: f32,
+    /// The sprite index which is currently shown, if any yet.
+    sprite_index: Option<usize>,
+}
+
+impl AnimationState {
+    /// Starts playing an animation.
+    fn new(animation: TileAnimation) -> AnimationState {
+        AnimationState {
+            animation,
+            elapsed: 0.0,
+            sprite_index: None,
+        }
+    }
+
+    /// Advances the animation and returns the new sprite index if it changed.
+    fn advance(&mut self, delta: f32) -> Option<usize> {
+        self.elapsed += delta;
+        if self.animation.mode() != AnimationMode::Once {
+            let cycle = self.animation.cycle_duration();
+            if cycle > 0.0 {
+                self.elapsed %= cycle;
+            }
+        }
+        let sprite_index = self.animation.sprite_index_at(self.elapsed);
+        if sprite_index != self.sprite_index {
+            self.sprite_index = sprite_index;
+            sprite_index
+        } else {
+            None
+        }
+    }
+}
+
+/// All animations of a tilemap.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub(crate) struct Animations {
+    /// Animations of single tiles by their point and z order.
+    tiles: HashMap<(Point2, usize), AnimationState>,
+    /// Animations of every tile with a sprite index.
+    sprites: HashMap<usize, AnimationState>,
+}
+
+impl Animations {
+    /// Adds an animation to a tile, returning the previous one.
+    pub(crate) fn insert_tile(
+        &mut self,
+        point: Point2,
+        z_order: usize,
+        animation: TileAnimation,
+    ) -> Option<TileAnimation> {
+        self.tiles
+            .insert((point, z_order), AnimationState::new(animation))
+            .map(|state| state.animation)
+    }
+
+    /// Removes the animation of a tile.
+    pub(crate) fn remove_tile(&mut self, point: Point2, z_order: usize) -> Option<TileAnimation> {
+        self.tiles
+            .remove(&(point, z_order))
+            .map(|state| state.animation)
+    }
+
+    /// Adds an animation to a sprite index, returning the previous one.
+    pub(crate) fn insert_sprite(
+        &mut self,
+        sprite_index: usize,
+        animation: TileAnimation,
+    ) -> Option<TileAnimation> {
+        self.sprites
+            .insert(sprite_index, AnimationState::new(animation))
+            .map(|state| state.animation)
+    }
+
+    /// Removes the animation of a sprite index.
+    pub(crate) fn remove_sprite(&mut self, sprite_index: usize) -> Option<TileAnimation> {
+        self.sprites
+            .remove(&sprite_index)
+            .map(|state| state.animation)
+    }
+
+    /// Advances all animations.
+    ///
+    /// Returns the tiles with their new sprite index and the animated sprite
+    /// indices which changed frames. Tile animations which finished are
+    /// removed.
+    pub(crate) fn advance(&mut self, delta: f32) -> (Vec<(Point2, usize, usize)>, HashSet<usize>) {
+        let mut tiles = Vec::new();
+        let mut finished = Vec::new();
+        for ((point, z_order), state) in self.tiles.iter_mut() {
+            if let Some(sprite_index) = state.advance(delta) {
+                tiles.push((*point, *z_order, sprite_index));
+            }
+            if state.animation.is_finished(state.elapsed) {
+                finished.push((*point, *z_order));
+            }
+        }
+        for key in finished {
+            self.tiles.remove(&key);
+        }
+
+        let mut sprites = HashSet::default();
+        for (sprite_index, state) in self.sprites.iter_mut() {
+            if state.advance(delta).is_some() {
+                sprites.insert(*sprite_index);
+            }
+        }
+
+        (tiles, sprites)
+    }
+
+    /// Returns the current sprite index of every animated sprite index.
+    pub(crate) fn sprite_frames(&self) -> HashMap<usize, usize> {
+        self.sprites
+            .iter()
+            .map(|(sprite_index, state)| {
+                (*sprite_index, state.sprite_index.unwrap_or(*sprite_index))
+            })
+            .collect()
+    }
+}
+
+impl Tilemap {
+    /// Animates a single tile, replacing any previous animation of the tile.
+    ///
+    /// The sprite index of the tile is changed to the current frame of the
+    /// animation, which is advanced every frame in the tilemap stage. The
+    /// animation is removed once the tile is cleared or, if it only plays
+    /// once, when it finished.
+    ///
+    /// Returns the previous animation of the tile, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{animation::{AnimationMode, TileAnimation}, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    ///
+    /// let animation = TileAnimation::uniform(vec![1, 2, 3], 0.1, AnimationMode::Loop);
+    /// assert!(tilemap.insert_tile_animation((1, 1), 0, animation.clone()).is_none());
+    /// assert_eq!(tilemap.remove_tile_animation((1, 1), 0), Some(animation));
+    /// ```
+    pub fn insert_tile_animation<P: Into<Point2>>(
+        &mut self,
+        point: P,
+        z_order: usize,
+        animation: TileAnimation,
+    ) -> Option<TileAnimation> {
+        self.animations_mut()
+            .insert_tile(point.into(), z_order, animation)
+    }
+
+    /// Removes the animation of a single tile, leaving the tile on its
+    /// current frame.
+    ///
+    /// Returns the animation, if the tile had one.
+    pub fn remove_tile_animation<P: Into<Point2>>(
+        &mut self,
+        point: P,
+        z_order: usize,
+    ) -> Option<TileAnimation> {
+        self.animations_mut().remove_tile(point.into(), z_order)
+    }
+
+    /// Animates every tile with the given sprite index, replacing any previous
+    /// animation of the sprite index.
+    ///
+    /// Unlike tile animations, the sprite index of the tiles themselves does
+    /// not change. Only the rendered sprite does, so [`get_tile`] keeps
+    /// returning the given sprite index.
+    ///
+    /// Returns the previous animation of the sprite index, if any.
+    ///
+    /// [`get_tile`]: Tilemap::get_tile
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{animation::{AnimationMode, TileAnimation}, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    ///
+    /// let water = TileAnimation::uniform(vec![4, 5, 6, 7], 0.25, AnimationMode::Loop);
+    /// assert!(tilemap.insert_sprite_animation(4, water.clone()).is_none());
+    /// assert_eq!(tilemap.remove_sprite_animation(4), Some(water));
+    /// ```
+    pub fn insert_sprite_animation(
+        &mut self,
+        sprite_index: usize,
+        animation: TileAnimation,
+    ) -> Option<TileAnimation> {
+        self.animations_mut().insert_sprite(sprite_index, animation)
+    }
+
+    /// Removes the animation of a sprite index.
+    ///
+    /// Returns the animation, if the sprite index had one.
+    pub fn remove_sprite_animation(&mut self, sprite_index: usize) -> Option<TileAnimation> {
+        let animation = self.animations_mut().remove_sprite(sprite_index)?;
+
+        // The chunks still render the last frame, so they need to be updated.
+        let mut sprites = HashSet::default();
+        sprites.insert(sprite_index);
+        let mut modified = HashMap::default();
+        self.collect_sprite_layers(&sprites, &mut modified);
+        self.send_modified(modified);
+
+        Some(animation)
+    }
+
+    /// Advances all animations and sends a single modified event per chunk
+    /// which changed.
+    pub(crate) fn update_animations(&mut self, delta: f32) {
+        let (tiles, sprites) = self.animations_mut().advance(delta);
+        if tiles.is_empty() && sprites.is_empty() {
+            return;
+        }
+
+        let mut modified: HashMap<Point2, HashMap<usize, Entity>> = HashMap::default();
+        for (point, z_order, sprite_index) in tiles {
+            let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+            let tile_point = self.point_to_tile_point(point);
+            let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+            let tile = self
+                .chunks_mut()
+                .get_mut(&chunk_point)
+                .and_then(|chunk| chunk.get_tile_mut(z_order, index));
+            if let Some(tile) = tile {
+                tile.index = sprite_index;
+            } else {
+                self.animations_mut().remove_tile(point, z_order);
+                continue;
+            }
+            if let Some(entity) = self
+                .chunks()
+                .get(&chunk_point)
+                .and_then(|chunk| chunk.get_entity(z_order))
+            {
+                modified
+                    .entry(chunk_point)
+                    .or_insert_with(HashMap::default)
+                    .insert(z_order, entity);
+            }
+        }
+
+        if !sprites.is_empty() {
+            self.collect_sprite_layers(&sprites, &mut modified);
+        }
+
+        self.send_modified(modified);
+    }
+
+    /// Collects the spawned layers of every chunk which have a tile with one
+    /// of the sprite indices, marking those tiles as changed.
+    fn collect_sprite_layers(
+        &mut self,
+        sprites: &HashSet<usize>,
+        modified: &mut HashMap<Point2, HashMap<usize, Entity>>,
+    ) {
+        for (chunk_point, chunk) in self.chunks_mut().iter_mut() {
+            for z_order in 0..chunk.z_layers() {
+                if let Some(entity) = chunk.get_entity(z_order) {
+                    if chunk.mark_sprites_dirty(z_order, sprites) {
+                        modified
+                            .entry(*chunk_point)
+                            .or_insert_with(HashMap::default)
+                            .insert(z_order, entity);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the sprite index currently shown for each animated sprite
+    /// index.
+    pub(crate) fn sprite_animation_frames(&self) -> HashMap<usize, usize> {
+        self.animations().sprite_frames()
+    }
+}
+
+/// Replaces the sprite indices of renderer attributes with their current
+/// animation frame.
+pub(crate) fn apply_sprite_frames(frames: &HashMap<usize, usize>, indexes: &mut [f32]) {
+    if frames.is_empty() {
+        return;
+    }
+    for index in indexes.iter_mut() {
+        if let Some(frame) = frames.get(&(*index as usize)) {
+            *index = *frame as f32;
+        }
+    }
+}
+
+/// Advances the animations of all tilemaps.
+pub(crate) fn tile_animations(time: Res<Time>, mut tilemap_query: Query<&mut Tilemap>) {
+    let delta = time.delta_seconds();
+    for mut tilemap in tilemap_query.iter_mut() {
+        tilemap.update_animations(delta);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tile;
+
+    /// Returns the sprite indices shown at each elapsed time.
+    fn sprite_indices(animation: &TileAnimation, times: &[f32]) -> Vec<Option<usize>> {
+        times
+            .iter()
+            .map(|elapsed| animation.sprite_index_at(*elapsed))
+            .collect()
+    }
+
+    #[test]
+    fn loop_repeats() {
+        let animation = TileAnimation::new(vec![(1, 0.5), (2, 1.0)], AnimationMode::Loop);
+        assert_eq!(
+            sprite_indices(&animation, &[0.0, 0.4, 0.5, 1.4, 1.6, 2.0, 3.1]),
+            vec![
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(2),
+                Some(1),
+                Some(2),
+                Some(1)
+            ]
+        );
+        assert!(!animation.is_finished(10.0));
+    }
+
+    #[test]
+    fn once_stops_on_last_frame() {
+        let animation = TileAnimation::uniform(vec![1, 2, 3], 0.5, AnimationMode::Once);
+        assert_eq!(
+            sprite_indices(&animation, &[0.2, 0.7, 1.2, 1.6, 10.0]),
+            vec![Some(1), Some(2), Some(3), Some(3), Some(3)]
+        );
+        assert!(!animation.is_finished(1.4));
+        assert!(animation.is_finished(1.5));
+    }
+
+    #[test]
+    fn ping_pong_plays_backwards() {
+        let animation = TileAnimation::uniform(vec![0, 1, 2, 3], 1.0, AnimationMode::PingPong);
+        assert_eq!(
+            sprite_indices(&animation, &[0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5]),
+            vec![
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(2),
+                Some(1),
+                Some(0),
+                Some(1)
+            ]
+        );
+        assert!(!animation.is_finished(100.0));
+
+        // Without frames in between, the ends do not repeat.
+        let animation = TileAnimation::uniform(vec![0, 1], 1.0, AnimationMode::PingPong);
+        assert_eq!(
+            sprite_indices(&animation, &[0.5, 1.5, 2.5, 3.5]),
+            vec![Some(0), Some(1), Some(0), Some(1)]
+        );
+    }
+
+    #[test]
+    fn empty_and_instant_animations() {
+        let animation = TileAnimation::new(vec![], AnimationMode::Loop);
+        assert_eq!(animation.sprite_index_at(1.0), None);
+        let animation = TileAnimation::new(vec![(4, 0.0), (5, 0.0)], AnimationMode::PingPong);
+        assert_eq!(animation.sprite_index_at(1.0), Some(5));
+    }
+
+    #[test]
+    fn advance_reports_changes() {
+        let mut state =
+            AnimationState::new(TileAnimation::uniform(vec![5, 6], 1.0, AnimationMode::Loop));
+        assert_eq!(state.advance(0.25), Some(5));
+        assert_eq!(state.advance(0.25), None);
+        assert_eq!(state.advance(1.0), Some(6));
+        assert_eq!(state.advance(1.0), Some(5));
+    }
+
+    #[test]
+    fn finished_tile_animations_are_removed() {
+        let mut animations = Animations::default();
+        let point = Point2::new(1, 2);
+        animations.insert_tile(
+            point,
+            0,
+            TileAnimation::uniform(vec![3], 0.5, AnimationMode::Once),
+        );
+        let (tiles, sprites) = animations.advance(0.25);
+        assert_eq!(tiles, vec![(point, 0, 3)]);
+        assert!(sprites.is_empty());
+        let (tiles, _) = animations.advance(0.5);
+        assert!(tiles.is_empty());
+        assert_eq!(animations.remove_tile(point, 0), None);
+    }
+
+    #[test]
+    fn cleared_tiles_lose_their_animation() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .tile_dimensions(32, 32)
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        tilemap
+            .insert_tile(Tile {
+                point: (1, 1),
+                ..Default::default()
+            })
+            .unwrap();
+        let animation = TileAnimation::uniform(vec![1, 2], 0.5, AnimationMode::Loop);
+        tilemap.insert_tile_animation((1, 1), 0, animation);
+
+        tilemap.clear_tile((1, 1), 0).unwrap();
+        tilemap
+            .insert_tile(Tile {
+                point: (1, 1),
+                sprite_index: 7,
+                ..Default::default()
+            })
+            .unwrap();
+        tilemap.update_animations(0.25);
+        assert_eq!(tilemap.get_tile((1, 1), 0).unwrap().index, 7);
+        assert_eq!(tilemap.remove_tile_animation((1, 1), 0), None);
+    }
+}
@@ -0,0 +1,443 @@
+//! Auto-tiling which picks sprites from the neighbours of a tile.
+//!
+//! A [`TerrainSet`] maps neighbour masks to sprite indices. Once it is added
+//! to a tilemap with [`Tilemap::add_terrain`], every tile which is inserted or
+//! cleared with a sprite index of the set is re-resolved together with its
+//! neighbours, including those in neighbouring chunks.
+//!
+//! A neighbour counts towards the mask if it is on the same z layer and has a
+//! sprite index of the same terrain set. How the mask is built is decided by
+//! the [`TerrainMask`] of the set and the [`GridTopology`] of the tilemap.
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_render::prelude::*;
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{
+//!     auto_tile::{TerrainMask, TerrainSet},
+//!     chunk::RawTile,
+//!     prelude::*,
+//! };
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+//!
+//! tilemap.insert_chunk((0, 0)).unwrap();
+//!
+//! // Sprites 0 to 15 are the walls for each 4-bit mask.
+//! let walls = TerrainSet::sequential(TerrainMask::Edges, GridTopology::Square, 0);
+//! tilemap.add_terrain(walls);
+//!
+//! tilemap.insert_tile(Tile { point: (0, 0), sprite_index: 0, ..Default::default() }).unwrap();
+//! tilemap.insert_tile(Tile { point: (1, 0), sprite_index: 0, ..Default::default() }).unwrap();
+//!
+//! // The left wall has a neighbour to the east, the right one to the west.
+//! assert_eq!(tilemap.get_tile((0, 0), 0), Some(&RawTile { index: 2, color: Color::WHITE, ..Default::default() }));
+//! assert_eq!(tilemap.get_tile((1, 0), 0), Some(&RawTile { index: 8, color: Color::WHITE, ..Default::default() }));
+//! ```
+//!
+//! [`Tilemap::add_terrain`]: crate::tilemap::Tilemap::add_terrain
+
+use crate::{lib::*, GridTopology};
+
+/// How the neighbours of a tile are turned into a mask.
+///
+/// Bits are assigned clockwise, in the order of [`GridTopology::neighbours`].
+/// On hex grids the corners of a tile lie between two neighbours, so the
+/// first corner is the one between the first and the second neighbour.
+/// Isometric grids are resolved like square grids turned by 45 degrees, where
+/// north is north-west.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum TerrainMask {
+    /// A bit for each neighbour which shares an edge.
+    ///
+    /// On square grids these are the 16 masks of 4 bits with north as `1`,
+    /// east as `2`, south as `4` and west as `8`. This is also how Wang edge
+    /// sets are resolved. Hex grids have 64 masks of 6 bits.
+    Edges,
+    /// A bit for each neighbour, where corners only count if both adjacent
+    /// edges do too.
+    ///
+    /// On square grids bits go from north as `1` clockwise to north-west as
+    /// `128`, which leaves the 47 masks of blob tilesets. Hex grids have no
+    /// corner neighbours and are resolved like [`TerrainMask::Edges`].
+    Blob,
+    /// A bit for each corner of the tile, which is set if every tile sharing
+    /// the corner is of the terrain, as in Wang corner sets.
+    ///
+    /// On square grids these are the 16 masks of 4 bits with north-east as
+    /// `1`, south-east as `2`, south-west as `4` and north-west as `8`. Hex
+    /// grids have 64 masks of 6 bits.
+    WangCorners,
+}
+
+impl TerrainMask {
+    /// Returns every mask that can occur on a grid topology, in ascending
+    /// order.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::{auto_tile::TerrainMask, prelude::*};
+    ///
+    /// assert_eq!(TerrainMask::Edges.masks(GridTopology::Square).len(), 16);
+    /// assert_eq!(TerrainMask::Blob.masks(GridTopology::Square).len(), 47);
+    /// assert_eq!(TerrainMask::Blob.masks(GridTopology::HexY).len(), 64);
+    /// ```
+    pub fn masks(&self, topology: GridTopology) -> Vec<u8> {
+        if topology.is_hex() {
+            return (0..64).collect();
+        }
+        match self {
+            TerrainMask::Edges | TerrainMask::WangCorners => (0..16).collect(),
+            TerrainMask::Blob => (0..=255).filter(|mask| is_blob_mask(*mask)).collect(),
+        }
+    }
+
+    /// Computes the mask of a tile at a point from which points are of the
+    /// same terrain.
+    pub(crate) fn compute<F>(&self, topology: GridTopology, point: Point2, is_terrain: F) -> u8
+    where
+        F: Fn(Point2) -> bool,
+    {
+        let edges: Vec<bool> = topology
+            .neighbours(point)
+            .into_iter()
+            .map(&is_terrain)
+            .collect();
+
+        if topology.is_hex() {
+            return match self {
+                TerrainMask::Edges | TerrainMask::Blob => to_mask(edges),
+                TerrainMask::WangCorners => {
+                    let corners = edges
+                        .iter()
+                        .zip(edges.iter().cycle().skip(1))
+                        .map(|(a, b)| *a && *b);
+                    to_mask(corners)
+                }
+            };
+        }
+
+        // Square neighbours are north, east, south and west, the corners are
+        // north-east, south-east, south-west and north-west. Isometric grids
+        // are the same turned by 45 degrees.
+        let corners = square_corners(topology, point).into_iter().map(&is_terrain);
+        let corners: Vec<bool> = edges
+            .iter()
+            .zip(edges.iter().cycle().skip(1))
+            .zip(corners)
+            .map(|((a, b), corner)| *a && *b && corner)
+            .collect();
+        match self {
+            TerrainMask::Edges => to_mask(edges),
+            TerrainMask::Blob => to_mask(
+                edges
+                    .into_iter()
+                    .zip(corners.into_iter())
+                    .flat_map(|(edge, corner)| vec![edge, corner]),
+            ),
+            TerrainMask::WangCorners => to_mask(corners),
+        }
+    }
+}
+
+/// Returns `true` if the 8-bit mask only has corners where both adjacent edges
+/// are set.
+fn is_blob_mask(mask: u8) -> bool {
+    (0..4).all(|corner| {
+        let bit = 2 * corner + 1;
+        let before = 1 << (bit - 1);
+        let after = 1 << ((bit + 1) % 8);
+        mask & (1 << bit) == 0 || (mask & before != 0 && mask & after != 0)
+    })
+}
+
+/// Turns bits into a mask, where the first is the lowest bit.
+fn to_mask<I: IntoIterator<Item = bool>>(bits: I) -> u8 {
+    bits.into_iter()
+        .enumerate()
+        .fold(0, |mask, (n, bit)| if bit { mask | 1 << n } else { mask })
+}
+
+/// Returns the corner neighbours of a square tile, clockwise from north-east.
+fn square_corners(topology: GridTopology, point: Point2) -> Vec<Point2> {
+    let diamond = topology.point_to_diamond(point);
+    [(1, 1), (1, -1), (-1, -1), (-1, 1)]
+        .iter()
+        .map(|(x, y)| topology.diamond_to_point(Point2::new(diamond.x + x, diamond.y + y)))
+        .collect()
+}
+
+/// Returns the points whose mask can depend on the tile at a point.
+pub(crate) fn surrounding(topology: GridTopology, point: Point2) -> Vec<Point2> {
+    let mut points = topology.neighbours(point);
+    if !topology.is_hex() {
+        points.extend(square_corners(topology, point));
+    }
+    points
+}
+
+/// A set of sprites of one terrain, mapped by their neighbour masks.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Debug)]
+pub struct TerrainSet {
+    /// How the masks are computed.
+    mask: TerrainMask,
+    /// The sprite used for masks which have none set.
+    default_sprite: usize,
+    /// The sprite index for each mask.
+    sprites: HashMap<u8, usize>,
+}
+
+impl TerrainSet {
+    /// Constructs a terrain set with a sprite index which is used for every
+    /// mask without a sprite of its own.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::auto_tile::{TerrainMask, TerrainSet};
+    ///
+    /// let water = TerrainSet::new(TerrainMask::Edges, 20)
+    ///     .with_sprite(0b0101, 21)
+    ///     .with_sprite(0b1010, 22);
+    ///
+    /// assert_eq!(water.sprite_index(0b0101), 21);
+    /// assert_eq!(water.sprite_index(0b1111), 20);
+    /// ```
+    pub fn new(mask: TerrainMask, default_sprite: usize) -> TerrainSet {
+        TerrainSet {
+            mask,
+            default_sprite,
+            sprites: HashMap::default(),
+        }
+    }
+
+    /// Constructs a terrain set where the sprites of all possible masks are
+    /// laid out in ascending mask order, starting at a sprite index.
+    ///
+    /// The first sprite, which is the one of the mask without neighbours, is
+    /// also the default sprite.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::{auto_tile::{TerrainMask, TerrainSet}, prelude::*};
+    ///
+    /// // The 47 blob sprites start at sprite index 100.
+    /// let grass = TerrainSet::sequential(TerrainMask::Blob, GridTopology::Square, 100);
+    ///
+    /// assert_eq!(grass.sprite_index(0), 100);
+    /// assert_eq!(grass.sprite_index(255), 146);
+    /// ```
+    pub fn sequential(
+        mask: TerrainMask,
+        topology: GridTopology,
+        first_sprite: usize,
+    ) -> TerrainSet {
+        let sprites = mask
+            .masks(topology)
+            .into_iter()
+            .enumerate()
+            .map(|(n, mask)| (mask, first_sprite + n))
+            .collect();
+        TerrainSet {
+            mask,
+            default_sprite: first_sprite,
+            sprites,
+        }
+    }
+
+    /// Sets the sprite index of a mask.
+    pub fn with_sprite(mut self, mask: u8, sprite_index: usize) -> TerrainSet {
+        self.sprites.insert(mask, sprite_index);
+        self
+    }
+
+    /// Returns how the masks of the terrain set are computed.
+    pub fn mask(&self) -> TerrainMask {
+        self.mask
+    }
+
+    /// Returns the sprite index used for masks without a sprite of their own.
+    pub fn default_sprite(&self) -> usize {
+        self.default_sprite
+    }
+
+    /// Returns the sprite index of a mask.
+    pub fn sprite_index(&self, mask: u8) -> usize {
+        self.sprites
+            .get(&mask)
+            .copied()
+            .unwrap_or(self.default_sprite)
+    }
+
+    /// Returns `true` if the sprite index is one of the terrain set.
+    pub fn contains(&self, sprite_index: usize) -> bool {
+        self.default_sprite == sprite_index
+            || self.sprites.values().any(|index| *index == sprite_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tile::Tile, tilemap::test_builder, Tilemap};
+
+    /// Returns a predicate which is `true` for the points.
+    fn is_in(points: &[(i32, i32)]) -> impl Fn(Point2) -> bool + '_ {
+        move |point| points.iter().any(|p| Point2::from(*p) == point)
+    }
+
+    /// Inserts tiles of a terrain set and returns the tilemap.
+    fn terrain_tilemap(
+        topology: GridTopology,
+        terrain: TerrainSet,
+        points: &[(i32, i32)],
+    ) -> Tilemap {
+        let mut tilemap = test_builder().topology(topology).finish().unwrap();
+        let sprite_index = terrain.default_sprite();
+        tilemap.add_terrain(terrain);
+        let tiles = points.iter().map(|point| Tile {
+            point: *point,
+            sprite_index,
+            ..Default::default()
+        });
+        tilemap.insert_tiles(tiles).unwrap();
+        tilemap
+    }
+
+    fn sprite_index(tilemap: &Tilemap, point: (i32, i32)) -> usize {
+        tilemap.raw_tile(point.into(), 0).unwrap().index
+    }
+
+    #[test]
+    fn square_masks() {
+        let origin = Point2::new(0, 0);
+        let square = GridTopology::Square;
+        // North, north-east and east, with a lone tile to the north-west.
+        let terrain = [(0, 1), (1, 1), (1, 0), (-1, 1)];
+        assert_eq!(
+            TerrainMask::Edges.compute(square, origin, is_in(&terrain)),
+            0b0011
+        );
+        assert_eq!(
+            TerrainMask::Blob.compute(square, origin, is_in(&terrain)),
+            0b0000_0111
+        );
+        assert_eq!(
+            TerrainMask::WangCorners.compute(square, origin, is_in(&terrain)),
+            0b0001
+        );
+
+        let all = [
+            (0, 1),
+            (1, 1),
+            (1, 0),
+            (1, -1),
+            (0, -1),
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+        ];
+        assert_eq!(
+            TerrainMask::Edges.compute(square, origin, is_in(&all)),
+            0b1111
+        );
+        assert_eq!(
+            TerrainMask::Blob.compute(square, origin, is_in(&all)),
+            0b1111_1111
+        );
+        assert_eq!(
+            TerrainMask::WangCorners.compute(square, origin, is_in(&all)),
+            0b1111
+        );
+    }
+
+    #[test]
+    fn hex_masks() {
+        // Odd rows are shifted right, so the north-east of (0, 1) is (1, 2).
+        let topology = GridTopology::HexOddRows;
+        let point = Point2::new(0, 1);
+        let terrain = [(1, 2), (1, 1)];
+        assert_eq!(
+            TerrainMask::Edges.compute(topology, point, is_in(&terrain)),
+            0b00_0011
+        );
+        assert_eq!(
+            TerrainMask::Blob.compute(topology, point, is_in(&terrain)),
+            0b00_0011
+        );
+        assert_eq!(
+            TerrainMask::WangCorners.compute(topology, point, is_in(&terrain)),
+            0b00_0001
+        );
+
+        let terrain = [(1, 2), (1, 1), (0, 2)];
+        assert_eq!(
+            TerrainMask::WangCorners.compute(topology, point, is_in(&terrain)),
+            0b10_0001
+        );
+    }
+
+    #[test]
+    fn blob_masks_need_both_edges() {
+        let masks = TerrainMask::Blob.masks(GridTopology::Square);
+        assert!(masks.contains(&0b0000_0111));
+        assert!(!masks.contains(&0b0000_0010));
+        assert!(!masks.contains(&0b0000_0110));
+    }
+
+    #[test]
+    fn tiles_resolve_across_chunks() {
+        let edges = TerrainSet::sequential(TerrainMask::Edges, GridTopology::Square, 0);
+        // Chunks are 8 wide, so the row crosses from chunk (0, 0) into (1, 0).
+        let mut tilemap = terrain_tilemap(GridTopology::Square, edges, &[(3, 0), (4, 0), (5, 0)]);
+        assert_eq!(sprite_index(&tilemap, (3, 0)), 0b0010);
+        assert_eq!(sprite_index(&tilemap, (4, 0)), 0b1010);
+        assert_eq!(sprite_index(&tilemap, (5, 0)), 0b1000);
+
+        tilemap.clear_tile((5, 0), 0).unwrap();
+        assert_eq!(sprite_index(&tilemap, (4, 0)), 0b1000);
+        tilemap.clear_tile((3, 0), 0).unwrap();
+        assert_eq!(sprite_index(&tilemap, (4, 0)), 0);
+    }
+
+    #[test]
+    fn blob_tiles_resolve() {
+        let blob = TerrainSet::sequential(TerrainMask::Blob, GridTopology::Square, 100);
+        let block = [(0, 0), (1, 0), (0, 1), (1, 1)];
+        let tilemap = terrain_tilemap(GridTopology::Square, blob.clone(), &block);
+        assert_eq!(
+            sprite_index(&tilemap, (0, 0)),
+            blob.sprite_index(0b0000_0111)
+        );
+        assert_eq!(
+            sprite_index(&tilemap, (1, 0)),
+            blob.sprite_index(0b1100_0001)
+        );
+        assert_eq!(
+            sprite_index(&tilemap, (0, 1)),
+            blob.sprite_index(0b0001_1100)
+        );
+        assert_eq!(
+            sprite_index(&tilemap, (1, 1)),
+            blob.sprite_index(0b0111_0000)
+        );
+    }
+
+    #[test]
+    fn hex_tiles_resolve() {
+        let topology = GridTopology::HexOddRows;
+        let edges = TerrainSet::sequential(TerrainMask::Edges, topology, 0);
+        let tilemap = terrain_tilemap(topology, edges, &[(0, 1), (1, 2), (1, 1)]);
+        // North-east and east.
+        assert_eq!(sprite_index(&tilemap, (0, 1)), 0b00_0011);
+        // South-east and south-west.
+        assert_eq!(sprite_index(&tilemap, (1, 2)), 0b00_1100);
+        // West and north-west.
+        assert_eq!(sprite_index(&tilemap, (1, 1)), 0b11_0000);
+    }
+}
@@ -1,19 +1,48 @@
+//! Components of chunk layer entities.
+//!
+//! Every layer of a spawned chunk is its own entity, a child of the tilemap
+//! entity, with the components in [`ChunkBundle`]. These can be queried to
+//! add components of your own, such as for lighting or custom materials.
+//!
+//! ```
+//! use bevy_ecs::prelude::*;
+//! use bevy_tilemap::chunk::entity::{ChunkPoint, ChunkTilemap, ZOrder};
+//!
+//! fn chunk_layers(query: Query<(Entity, &ChunkPoint, &ZOrder, &ChunkTilemap)>) {
+//!     for (entity, point, z_order, tilemap) in query.iter() {
+//!         // The chunk layer `entity` of `tilemap` at `point.0`, `z_order.0`.
+//!     }
+//! }
+//! ```
+
 use crate::lib::*;
 
-/// A component that is used as a flag for dirty chunks that need updating.
-#[derive(Default)]
-pub(crate) struct ModifiedLayer(pub usize);
+/// The point of the chunk a layer entity belongs to.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkPoint(pub Point2);
 
 /// The Z Order of a layer in a chunk.
-pub(crate) struct ZOrder(pub usize);
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ZOrder(pub usize);
+
+/// The tilemap entity which owns a chunk layer entity.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkTilemap(pub Entity);
 
 /// A component bundle for `Chunk` entities.
+///
+/// These are spawned and despawned by the tilemap system only, a
+/// [`TilemapChunkLayerEvent`] is sent each time it does.
+///
+/// [`TilemapChunkLayerEvent`]: crate::event::TilemapChunkLayerEvent
 #[derive(Bundle)]
-pub(crate) struct ChunkBundle {
+pub struct ChunkBundle {
     /// The point of the chunk.
-    pub point: Point2,
+    pub point: ChunkPoint,
     /// The z order of the layer.
     pub z_order: ZOrder,
+    /// The tilemap entity which owns the chunk.
+    pub tilemap: ChunkTilemap,
     /// The handle for a TextureAtlas which contains multiple textures.
     pub texture_atlas: Handle<TextureAtlas>,
     /// A component that indicates how to draw a component.
@@ -31,6 +60,4 @@ pub(crate) struct ChunkBundle {
     pub transform: Transform,
     /// The global transform location in a space for a component.
     pub global_transform: GlobalTransform,
-    /// If a layer has been modified, all are set here.
-    pub modified_layer: ModifiedLayer,
 }
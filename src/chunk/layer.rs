@@ -1,4 +1,8 @@
-use crate::{chunk::raw_tile::RawTile, lib::*};
+use crate::{
+    chunk::raw_tile::RawTile,
+    fog::{FogState, FogStyle},
+    lib::*,
+};
 
 /// Common methods for layers in a chunk.
 pub(super) trait Layer: 'static {
@@ -24,7 +28,7 @@ pub(super) trait Layer: 'static {
     fn get_tile_indices(&self) -> Vec<usize>;
 
     /// Takes all the tiles in the layer and returns attributes for the renderer.
-    fn tiles_to_attributes(&self, area: usize) -> (Vec<f32>, Vec<[f32; 4]>);
+    fn tiles_to_attributes(&self, area: usize) -> (Vec<f32>, Vec<[f32; 4]>, Vec<f32>);
 }
 
 /// A layer with dense sprite tiles.
@@ -98,7 +102,7 @@ impl Layer for DenseLayer {
         indices
     }
 
-    fn tiles_to_attributes(&self, _area: usize) -> (Vec<f32>, Vec<[f32; 4]>) {
+    fn tiles_to_attributes(&self, _area: usize) -> (Vec<f32>, Vec<[f32; 4]>, Vec<f32>) {
         crate::chunk::raw_tile::dense_tiles_to_attributes(&self.tiles)
     }
 }
@@ -160,7 +164,7 @@ impl Layer for SparseLayer {
         indices
     }
 
-    fn tiles_to_attributes(&self, area: usize) -> (Vec<f32>, Vec<[f32; 4]>) {
+    fn tiles_to_attributes(&self, area: usize) -> (Vec<f32>, Vec<[f32; 4]>, Vec<f32>) {
         crate::chunk::raw_tile::sparse_tiles_to_attributes(area, &self.tiles)
     }
 }
@@ -175,8 +179,100 @@ impl SparseLayer {
     }
 }
 
+/// A layer with the fog of war state of every tile.
+///
+/// The layer has no tiles of its own, the overlay of each state is set by the
+/// fog style of the tilemap when the layer is rendered.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Debug)]
+pub(super) struct FogLayer {
+    /// A mesh handle.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mesh: Handle<Mesh>,
+    /// The fog state of every tile in the chunk.
+    states: Vec<FogState>,
+}
+
+impl Layer for FogLayer {
+    fn mesh(&self) -> &Handle<Mesh> {
+        &self.mesh
+    }
+
+    fn set_mesh(&mut self, mesh: Handle<Mesh>) {
+        self.mesh = mesh;
+    }
+
+    fn set_tile(&mut self, index: usize, _tile: RawTile) {
+        warn!(
+            "tile at index {} can not be set on a fog layer, set its fog state instead",
+            index
+        );
+    }
+
+    fn remove_tile(&mut self, index: usize) {
+        warn!(
+            "tile at index {} can not be removed from a fog layer, set its fog state instead",
+            index
+        );
+    }
+
+    fn get_tile(&self, _index: usize) -> Option<&RawTile> {
+        None
+    }
+
+    fn get_tile_mut(&mut self, _index: usize) -> Option<&mut RawTile> {
+        None
+    }
+
+    fn get_tile_indices(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    fn tiles_to_attributes(&self, _area: usize) -> (Vec<f32>, Vec<[f32; 4]>, Vec<f32>) {
+        self.tiles_to_fog_attributes(&FogStyle::default())
+    }
+}
+
+impl FogLayer {
+    /// Constructs a new fog layer where every tile is unexplored.
+    pub fn new(area: usize) -> FogLayer {
+        FogLayer {
+            mesh: Default::default(),
+            states: vec![FogState::Unexplored; area],
+        }
+    }
+
+    /// Gets the fog state of a tile by an index.
+    pub fn state(&self, index: usize) -> Option<FogState> {
+        self.states.get(index).copied()
+    }
+
+    /// Sets the fog state of a tile at an index, returning `true` if it
+    /// changed.
+    pub fn set_state(&mut self, index: usize, state: FogState) -> bool {
+        if let Some(inner_state) = self.states.get_mut(index) {
+            let changed = *inner_state != state;
+            *inner_state = state;
+            changed
+        } else {
+            warn!(
+                "fog state is out of bounds at index {} and can not be set",
+                index
+            );
+            false
+        }
+    }
+
+    /// Takes the fog state of every tile and returns the attributes of the
+    /// overlay for the renderer.
+    pub fn tiles_to_fog_attributes(&self, style: &FogStyle) -> (Vec<f32>, Vec<[f32; 4]>, Vec<f32>) {
+        let tiles: Vec<RawTile> = self.states.iter().map(|state| style.tile(*state)).collect();
+        crate::chunk::raw_tile::dense_tiles_to_attributes(&tiles)
+    }
+}
+
 /// Specifies which kind of layer to construct, either a dense or a sparse
-/// sprite layer.
+/// sprite layer, or a fog of war layer.
 ///
 /// The difference between a dense and sparse layer is namely the storage kind.
 /// A dense layer uses a vector and must fully contain tiles. This is ideal for
@@ -192,6 +288,11 @@ pub enum LayerKind {
     Dense,
     /// Specifies the tilemap to add a sparse sprite layer.
     Sparse,
+    /// Specifies the tilemap to add a fog of war layer, which stores a
+    /// [`FogState`] per tile instead of sprites.
+    ///
+    /// [`FogState`]: crate::fog::FogState
+    Fog,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -202,6 +303,8 @@ pub(super) enum LayerKindInner {
     Dense(DenseLayer),
     /// Inner sparse layer storage.
     Sparse(SparseLayer),
+    /// Inner fog layer storage.
+    Fog(FogLayer),
 }
 
 impl AsRef<dyn Layer> for LayerKindInner {
@@ -209,6 +312,7 @@ impl AsRef<dyn Layer> for LayerKindInner {
         match self {
             LayerKindInner::Dense(s) => s,
             LayerKindInner::Sparse(s) => s,
+            LayerKindInner::Fog(s) => s,
         }
     }
 }
@@ -218,6 +322,7 @@ impl AsMut<dyn Layer> for LayerKindInner {
         match self {
             LayerKindInner::Dense(s) => s,
             LayerKindInner::Sparse(s) => s,
+            LayerKindInner::Fog(s) => s,
         }
     }
 }
@@ -231,4 +336,51 @@ pub(super) struct SpriteLayer {
     #[cfg_attr(feature = "serde", serde(skip))]
     /// Contains an entity if the layer had been spawned.
     pub entity: Option<Entity>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    /// The tiles which changed since the mesh was last updated.
+    pub dirty: DirtyTiles,
+}
+
+/// The tiles of a layer which changed since its mesh was last updated.
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub(super) struct DirtyTiles {
+    /// Whether every tile of the layer changed.
+    all: bool,
+    /// The indexes of the tiles which changed.
+    tiles: HashSet<usize>,
+}
+
+impl DirtyTiles {
+    /// Marks a tile as changed.
+    pub fn insert(&mut self, index: usize) {
+        if !self.all {
+            self.tiles.insert(index);
+        }
+    }
+
+    /// Marks every tile as changed.
+    pub fn insert_all(&mut self) {
+        self.all = true;
+        self.tiles.clear();
+    }
+
+    /// Takes the changed tiles as ranges of indexes in ascending order, where
+    /// neighbouring tiles are merged into a single range.
+    pub fn take_ranges(&mut self, area: usize) -> Vec<Range<usize>> {
+        if mem::take(&mut self.all) {
+            self.tiles.clear();
+            return vec![0..area];
+        }
+
+        let mut indexes: Vec<usize> = self.tiles.drain().filter(|index| *index < area).collect();
+        indexes.sort_unstable();
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for index in indexes {
+            match ranges.last_mut() {
+                Some(range) if range.end == index => range.end += 1,
+                _ => ranges.push(index..index + 1),
+            }
+        }
+        ranges
+    }
 }
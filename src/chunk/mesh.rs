@@ -1,10 +1,46 @@
-use crate::lib::*;
+use crate::{
+    chunk::render::GridTopologyExt,
+    lib::*,
+    tile::{TileAnchor, TileFlip},
+    GridTopology,
+};
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+/// The corners of a tile for each of its vertices, with the Y axis pointing
+/// down as in the texture atlas.
+const CORNERS: [[f32; 2]; 4] = [[0.0, 1.0], [0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+
+/// The geometry of a chunk layer as it is drawn, computed without a GPU.
+///
+/// Every tile of the chunk has 4 vertices, in the same order as its index
+/// within the chunk, and two triangles. Tiles without a sprite keep their
+/// vertices but are fully transparent.
+///
+/// This mirrors what the vertex shaders do, which makes it useful to test the
+/// rendered output, export tilemaps to other engines, or for picking.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct ChunkGeometry {
+    /// The positions of the vertices in pixels, relative to the tilemap.
+    pub positions: Vec<[f32; 3]>,
+    /// The texture coordinates of the vertices, from 0 to 1 over the texture
+    /// atlas.
+    pub uvs: Vec<[f32; 2]>,
+    /// The colors of the vertices.
+    pub colors: Vec<[f32; 4]>,
+    /// The vertex indices of the triangles, in the order they are drawn.
+    pub indices: Vec<u32>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 /// The mesh of a chunk layer.
 pub struct ChunkMesh {
-    /// The dimensions of the chunk in pixels.
+    /// The dimensions of the chunk in tiles.
     dimensions: Dimension2,
+    /// The dimensions of a grid cell in pixels.
+    tile_dimensions: Dimension2,
+    /// Where sprites are anchored to their grid cells.
+    anchor: TileAnchor,
+    /// The topology of the grid, which decides the drawing order.
+    topology: GridTopology,
 }
 
 impl ChunkMesh {
@@ -12,17 +48,33 @@ impl ChunkMesh {
     pub(crate) const ATTRIBUTE_TILE_INDEX: &'static str = "Vertex_Tile_Index";
     /// Vertex attribute of the tile's color.
     pub(crate) const ATTRIBUTE_TILE_COLOR: &'static str = "Vertex_Tile_Color";
+    /// Vertex attribute of the tile's flip flags.
+    pub(crate) const ATTRIBUTE_TILE_FLIP: &'static str = "Vertex_Tile_Flip";
+    /// Vertex attribute of the dimensions of a grid cell.
+    pub(crate) const ATTRIBUTE_CELL_DIMENSIONS: &'static str = "Vertex_Cell_Dimensions";
+    /// Vertex attribute of the tile's anchor.
+    pub(crate) const ATTRIBUTE_TILE_ANCHOR: &'static str = "Vertex_Tile_Anchor";
 
     /// Constructs a new chunk mesh.
-    pub(crate) fn new(dimensions: Dimension2) -> ChunkMesh {
-        ChunkMesh { dimensions }
+    pub(crate) fn new(
+        dimensions: Dimension2,
+        tile_dimensions: Dimension2,
+        anchor: TileAnchor,
+        topology: GridTopology,
+    ) -> ChunkMesh {
+        ChunkMesh {
+            dimensions,
+            tile_dimensions,
+            anchor,
+            topology,
+        }
     }
-}
 
-impl From<&ChunkMesh> for Mesh {
-    fn from(chunk_mesh: &ChunkMesh) -> Mesh {
-        let chunk_width = chunk_mesh.dimensions.width as i32;
-        let chunk_height = chunk_mesh.dimensions.height as i32;
+    /// Returns the positions of the vertices of every tile in grid cells,
+    /// centered on the chunk.
+    fn vertices(&self) -> Vec<[f32; 3]> {
+        let chunk_width = self.dimensions.width as i32;
+        let chunk_height = self.dimensions.height as i32;
 
         let mut vertices = Vec::with_capacity((chunk_width * chunk_height) as usize * 4);
         for y in 0..chunk_height {
@@ -38,24 +90,138 @@ impl From<&ChunkMesh> for Mesh {
                 vertices.push([x1, y0, 0.0]);
             }
         }
+        vertices
+    }
+
+    /// Returns the vertex indices of the triangles of every tile.
+    fn indices(&self) -> Vec<u32> {
+        let chunk_width = self.dimensions.width;
+        let chunk_height = self.dimensions.height;
+
+        // The rows are drawn from the top down, so sprites which are taller
+        // than a cell are drawn over the rows behind them. On isometric grids
+        // a row on the screen is a diagonal of the chunk.
+        let mut tiles: Vec<u32> = (0..chunk_width * chunk_height).collect();
+        tiles.sort_by_key(|i| {
+            let x = *i % chunk_width;
+            let y = *i / chunk_width;
+            match self.topology {
+                GridTopology::Isometric => Reverse(x + y),
+                _ => Reverse(y),
+            }
+        });
+        tiles
+            .into_iter()
+            .flat_map(|i| {
+                let i = i * 4;
+                vec![i, i + 2, i + 1, i, i + 3, i + 2]
+            })
+            .collect()
+    }
 
-        let indices = Indices::U32(
-            (0..(chunk_width * chunk_height) as u32)
-                .flat_map(|i| {
-                    let i = i * 4;
-                    vec![i, i + 2, i + 1, i, i + 3, i + 2]
-                })
-                .collect(),
+    /// Computes the geometry of the chunk layer from the attributes of its
+    /// tiles, the same way the vertex shaders do.
+    ///
+    /// The translation is the one of the chunk from the tilemap in pixels.
+    pub(crate) fn geometry(
+        &self,
+        translation: Vec2,
+        indexes: &[f32],
+        colors: &[[f32; 4]],
+        flips: &[f32],
+        texture_atlas: &TextureAtlas,
+    ) -> ChunkGeometry {
+        let cell = Vec2::new(
+            self.tile_dimensions.width as f32,
+            self.tile_dimensions.height as f32,
         );
+        let [anchor_x, anchor_y] = self.anchor.to_fraction();
+        let anchor = Vec2::new(anchor_x, anchor_y);
+        let area = self.dimensions.area() as usize;
+
+        let mut positions = Vec::with_capacity(area * 4);
+        let mut uvs = Vec::with_capacity(area * 4);
+        for index in 0..area {
+            let tile_point = Point2::new(
+                (index % self.dimensions.width as usize) as i32,
+                (index / self.dimensions.width as usize) as i32,
+            );
+            let center =
+                self.topology
+                    .tile_center(tile_point, self.dimensions, self.tile_dimensions);
+            let sprite_index = indexes.get(index * 4).copied().unwrap_or_default() as usize;
+            let (begin, end) = texture_atlas
+                .textures
+                .get(sprite_index)
+                .map(|rect| (rect.min, rect.max))
+                .unwrap_or_default();
+            let sprite_dimensions = end - begin;
+            let flip = flips.get(index * 4).copied().unwrap_or_default().round() as u8;
+            let flip = TileFlip::from_bits_truncate(flip);
+
+            for [x, y] in CORNERS.iter() {
+                let quad_corner = Vec2::new(*x, 1.0 - *y);
+                let position = center
+                    + (quad_corner - Vec2::new(0.5, 0.5)) * cell
+                    + (quad_corner - anchor) * (sprite_dimensions - cell);
+                positions.push([
+                    position.x.ceil() + translation.x,
+                    position.y.ceil() + translation.y,
+                    0.0,
+                ]);
+
+                let mut corner = Vec2::new(*x, *y);
+                if flip.contains(TileFlip::VERTICAL) {
+                    corner.y = 1.0 - corner.y;
+                }
+                if flip.contains(TileFlip::HORIZONTAL) {
+                    corner.x = 1.0 - corner.x;
+                }
+                if flip.contains(TileFlip::ANTI_DIAGONAL) {
+                    corner = Vec2::new(corner.y, corner.x);
+                }
+                let atlas_position = begin + (end - begin) * corner;
+                uvs.push([
+                    (atlas_position.x + 0.01).floor() / texture_atlas.size.x,
+                    (atlas_position.y + 0.01).floor() / texture_atlas.size.y,
+                ]);
+            }
+        }
+
+        ChunkGeometry {
+            positions,
+            uvs,
+            colors: colors.to_vec(),
+            indices: self.indices(),
+        }
+    }
+}
+
+impl From<&ChunkMesh> for Mesh {
+    fn from(chunk_mesh: &ChunkMesh) -> Mesh {
+        let vertices = chunk_mesh.vertices();
+        let indices = Indices::U32(chunk_mesh.indices());
 
         let tile_indexes = vec![0.; vertices.len()];
         let tile_colors: Vec<[f32; 4]> = vec![Color::WHITE.into(); vertices.len()];
+        let tile_flips = vec![0.; vertices.len()];
+        let cell_dimensions: Vec<[f32; 2]> = vec![
+            [
+                chunk_mesh.tile_dimensions.width as f32,
+                chunk_mesh.tile_dimensions.height as f32,
+            ];
+            vertices.len()
+        ];
+        let tile_anchors: Vec<[f32; 2]> = vec![chunk_mesh.anchor.to_fraction(); vertices.len()];
 
         let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
         mesh.set_indices(Some(indices));
         mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
         mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_INDEX, tile_indexes);
         mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_COLOR, tile_colors);
+        mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_FLIP, tile_flips);
+        mesh.set_attribute(ChunkMesh::ATTRIBUTE_CELL_DIMENSIONS, cell_dimensions);
+        mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_ANCHOR, tile_anchors);
 
         mesh
     }
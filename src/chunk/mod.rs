@@ -56,7 +56,7 @@
 //! ```
 
 /// Chunk entity.
-pub(crate) mod entity;
+pub mod entity;
 /// Sparse and dense chunk layers.
 mod layer;
 /// Meshes for rendering to vertices.
@@ -68,10 +68,17 @@ pub(crate) mod render;
 /// Systems for chunks.
 pub(crate) mod system;
 
-use crate::{lib::*, tile::Tile};
+use crate::{
+    fog::{FogState, FogStyle},
+    lib::*,
+    tile::{Tile, TileFlip},
+    tile_data::TileDataMap,
+};
 pub use layer::LayerKind;
-use layer::{DenseLayer, LayerKindInner, SparseLayer, SpriteLayer};
+use layer::{DenseLayer, DirtyTiles, FogLayer, LayerKindInner, SparseLayer, SpriteLayer};
+pub use mesh::ChunkGeometry;
 pub use raw_tile::RawTile;
+pub use render::TilemapRenderGraphBuilder;
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, PartialEq, Debug)]
@@ -83,9 +90,17 @@ pub(crate) struct Chunk {
     sprite_layers: Vec<Option<SpriteLayer>>,
     /// Ephemeral user data that can be used for flags or other purposes.
     user_data: u128,
-    /// Contains a map of all collision entities.
+    /// The typed data of the tiles, which has no effect on rendering.
+    tile_data: TileDataMap,
+    /// The collider entities of the tiles of each layer, by z order and tile
+    /// index.
     #[cfg(feature = "bevy_rapier2d")]
-    pub collision_entities: HashMap<usize, Entity>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    collision_entities: HashMap<usize, HashMap<usize, Entity>>,
+    /// The collider entities of each layer with merged tiles, by z order.
+    #[cfg(feature = "bevy_rapier2d")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    merged_collision_entities: HashMap<usize, Vec<Entity>>,
 }
 
 impl Chunk {
@@ -99,8 +114,11 @@ impl Chunk {
             point,
             sprite_layers: vec![None; layers.len()],
             user_data: 0,
+            tile_data: TileDataMap::default(),
             #[cfg(feature = "bevy_rapier2d")]
             collision_entities: HashMap::default(),
+            #[cfg(feature = "bevy_rapier2d")]
+            merged_collision_entities: HashMap::default(),
         };
         for (z_order, kind) in layers.iter().enumerate() {
             if let Some(kind) = kind {
@@ -118,7 +136,8 @@ impl Chunk {
                 let tiles = vec![
                     RawTile {
                         index: 0,
-                        color: Color::rgba(0.0, 0.0, 0.0, 0.0)
+                        color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+                        flip: TileFlip::empty(),
                     };
                     dimensions.area() as usize
                 ];
@@ -126,6 +145,7 @@ impl Chunk {
                     *layer = Some(SpriteLayer {
                         inner: LayerKindInner::Dense(DenseLayer::new(tiles)),
                         entity: None,
+                        dirty: DirtyTiles::default(),
                     });
                 } else {
                     error!("sprite layer {} is out of bounds", z_order);
@@ -136,6 +156,18 @@ impl Chunk {
                     *layer = Some(SpriteLayer {
                         inner: LayerKindInner::Sparse(SparseLayer::new(HashMap::default())),
                         entity: None,
+                        dirty: DirtyTiles::default(),
+                    });
+                } else {
+                    error!("sprite layer {} is out of bounds", z_order);
+                }
+            }
+            LayerKind::Fog => {
+                if let Some(layer) = self.sprite_layers.get_mut(z_order) {
+                    *layer = Some(SpriteLayer {
+                        inner: LayerKindInner::Fog(FogLayer::new(dimensions.area() as usize)),
+                        entity: None,
+                        dirty: DirtyTiles::default(),
                     });
                 } else {
                     error!("sprite layer {} is out of bounds", z_order);
@@ -149,20 +181,46 @@ impl Chunk {
         self.point
     }
 
-    // /// Returns a copy of the user data.
-    // pub(crate) fn user_data(&self) -> u128 {
-    //     self.user_data
-    // }
-    //
-    // /// Returns a mutable reference to the user data.
-    // pub(crate) fn user_data_mut(&mut self) -> &mut u128 {
-    //     &mut self.user_data
-    // }
+    /// Returns a copy of the user data.
+    pub(crate) fn user_data(&self) -> u128 {
+        self.user_data
+    }
+
+    /// Returns a mutable reference to the user data.
+    pub(crate) fn user_data_mut(&mut self) -> &mut u128 {
+        &mut self.user_data
+    }
+
+    /// Returns a reference to the typed data of the tiles.
+    pub(crate) fn tile_data(&self) -> &TileDataMap {
+        &self.tile_data
+    }
+
+    /// Returns a mutable reference to the typed data of the tiles.
+    pub(crate) fn tile_data_mut(&mut self) -> &mut TileDataMap {
+        &mut self.tile_data
+    }
+
+    /// Returns the number of z layers in the chunk.
+    pub(crate) fn z_layers(&self) -> usize {
+        self.sprite_layers.len()
+    }
+
+    /// Returns the kind of the layer at a z order, if it exists.
+    pub(crate) fn layer_kind(&self, z_order: usize) -> Option<LayerKind> {
+        self.sprite_layers.get(z_order).and_then(|layer| {
+            layer.as_ref().map(|layer| match layer.inner {
+                LayerKindInner::Dense(_) => LayerKind::Dense,
+                LayerKindInner::Sparse(_) => LayerKind::Sparse,
+                LayerKindInner::Fog(_) => LayerKind::Fog,
+            })
+        })
+    }
 
     /// Moves a layer from a z layer to another.
     pub(crate) fn move_layer(&mut self, from_z: usize, to_z: usize) {
         // TODO: rename to swap and include it in the greater api
-        if self.sprite_layers.get(to_z).is_some() {
+        if let Some(Some(_)) = self.sprite_layers.get(to_z) {
             error!(
                 "sprite layer {} unexpectedly exists and can not be moved",
                 to_z
@@ -171,11 +229,24 @@ impl Chunk {
         }
 
         self.sprite_layers.swap(from_z, to_z);
+        self.tile_data.move_layer(from_z, to_z);
+        #[cfg(feature = "bevy_rapier2d")]
+        {
+            if let Some(entities) = self.collision_entities.remove(&from_z) {
+                self.collision_entities.insert(to_z, entities);
+            }
+            if let Some(entities) = self.merged_collision_entities.remove(&from_z) {
+                self.merged_collision_entities.insert(to_z, entities);
+            }
+        }
     }
 
     /// Removes a layer from the specified layer.
     pub(crate) fn remove_layer(&mut self, z_order: usize) {
-        self.sprite_layers.get_mut(z_order).take();
+        if let Some(layer) = self.sprite_layers.get_mut(z_order) {
+            layer.take();
+        }
+        self.tile_data.remove_layer(z_order);
     }
 
     /// Sets the mesh for the chunk layer to use.
@@ -198,8 +269,10 @@ impl Chunk {
                 let raw_tile = RawTile {
                     index: tile.sprite_index,
                     color: tile.tint,
+                    flip: tile.flip,
                 };
                 layer.inner.as_mut().set_tile(index, raw_tile);
+                layer.dirty.insert(index);
             } else {
                 error!("can not set tile to sprite layer {}", tile.z_order);
             }
@@ -208,11 +281,26 @@ impl Chunk {
         }
     }
 
+    /// Sets a raw tile directly to a z layer and index.
+    pub(crate) fn set_raw_tile(&mut self, z_order: usize, index: usize, raw_tile: RawTile) {
+        if let Some(layer) = self.sprite_layers.get_mut(z_order) {
+            if let Some(layer) = layer.as_mut() {
+                layer.inner.as_mut().set_tile(index, raw_tile);
+                layer.dirty.insert(index);
+            } else {
+                error!("can not set tile to sprite layer {}", z_order);
+            }
+        } else {
+            error!("sprite layer {} does not exist", z_order);
+        }
+    }
+
     /// Removes a tile from a sprite layer with a given index and z order.
     pub(crate) fn remove_tile(&mut self, index: usize, z_order: usize) {
         if let Some(layer) = self.sprite_layers.get_mut(z_order) {
             if let Some(layer) = layer.as_mut() {
                 layer.inner.as_mut().remove_tile(index);
+                layer.dirty.insert(index);
             } else {
                 error!("can not remove tile on sprite layer {}", z_order);
             }
@@ -234,14 +322,50 @@ impl Chunk {
         }
     }
 
-    /// Adds an entity to a tile index in a layer.
+    /// Adds a collider entity to a tile index in a layer.
     #[cfg(feature = "bevy_rapier2d")]
     pub(crate) fn insert_collision_entity(
         &mut self,
+        z_order: usize,
         index: usize,
         entity: Entity,
     ) -> Option<Entity> {
-        self.collision_entities.insert(index, entity)
+        self.collision_entities
+            .entry(z_order)
+            .or_insert_with(HashMap::default)
+            .insert(index, entity)
+    }
+
+    /// Removes the collider entity of a tile index in a layer, if any.
+    #[cfg(feature = "bevy_rapier2d")]
+    pub(crate) fn remove_collision_entity(
+        &mut self,
+        z_order: usize,
+        index: usize,
+    ) -> Option<Entity> {
+        self.collision_entities
+            .get_mut(&z_order)
+            .and_then(|entities| entities.remove(&index))
+    }
+
+    /// Sets the collider entities of a layer with merged tiles.
+    #[cfg(feature = "bevy_rapier2d")]
+    pub(crate) fn set_merged_collision_entities(&mut self, z_order: usize, entities: Vec<Entity>) {
+        self.merged_collision_entities.insert(z_order, entities);
+    }
+
+    /// Takes all the collider entities of a layer out of the chunk.
+    #[cfg(feature = "bevy_rapier2d")]
+    pub(crate) fn take_collision_entities(&mut self, z_order: usize) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = self
+            .collision_entities
+            .remove(&z_order)
+            .map(|entities| entities.into_iter().map(|(_, entity)| entity).collect())
+            .unwrap_or_default();
+        if let Some(merged) = self.merged_collision_entities.remove(&z_order) {
+            entities.extend(merged);
+        }
+        entities
     }
 
     /// Gets the layers entity, if any. Useful for despawning.
@@ -251,18 +375,22 @@ impl Chunk {
             .and_then(|o| o.as_ref().and_then(|layer| layer.entity))
     }
 
-    /// Gets the collision entity if any.
+    /// Gets the collider entity of a tile index in a layer, if any.
     #[cfg(feature = "bevy_rapier2d")]
-    pub(crate) fn get_collision_entity(&self, index: usize) -> Option<Entity> {
-        self.collision_entities.get(&index).cloned()
+    pub(crate) fn get_collision_entity(&self, z_order: usize, index: usize) -> Option<Entity> {
+        self.collision_entities
+            .get(&z_order)
+            .and_then(|entities| entities.get(&index))
+            .cloned()
     }
 
-    /// Gets all the layers entities for use with bulk despawning.
-    pub(crate) fn get_entities(&self) -> Vec<Entity> {
+    /// Takes all the layers entities out of the chunk for use with bulk
+    /// despawning, as the layers are no longer spawned.
+    pub(crate) fn take_entities(&mut self) -> Vec<Entity> {
         let mut entities = Vec::new();
-        for sprite_layer in &self.sprite_layers {
+        for sprite_layer in &mut self.sprite_layers {
             if let Some(layer) = sprite_layer {
-                if let Some(entity) = layer.entity {
+                if let Some(entity) = layer.entity.take() {
                     entities.push(entity);
                 }
             }
@@ -280,16 +408,64 @@ impl Chunk {
     }
 
     /// Gets a mutable reference to a tile from a provided z order and index.
+    ///
+    /// The tile is marked as changed, as it may be modified through the
+    /// reference.
     pub(crate) fn get_tile_mut(&mut self, z_order: usize, index: usize) -> Option<&mut RawTile> {
         self.sprite_layers.get_mut(z_order).and_then(|layer| {
-            layer
-                .as_mut()
-                .and_then(|layer| layer.inner.as_mut().get_tile_mut(index))
+            layer.as_mut().and_then(|layer| {
+                layer.dirty.insert(index);
+                layer.inner.as_mut().get_tile_mut(index)
+            })
         })
     }
 
+    /// Marks every tile of a layer as changed.
+    pub(crate) fn mark_layer_dirty(&mut self, z_order: usize) {
+        if let Some(Some(layer)) = self.sprite_layers.get_mut(z_order) {
+            layer.dirty.insert_all();
+        }
+    }
+
+    /// Marks the tiles of a layer which have one of the sprite indices as
+    /// changed, returning `true` if there were any.
+    pub(crate) fn mark_sprites_dirty(&mut self, z_order: usize, indices: &HashSet<usize>) -> bool {
+        let tiles: Vec<usize> = match self.get_tile_indices(z_order) {
+            Some(tiles) => tiles
+                .into_iter()
+                .filter(|index| {
+                    self.get_tile(z_order, *index)
+                        .map_or(false, |tile| indices.contains(&tile.index))
+                })
+                .collect(),
+            None => return false,
+        };
+        if let Some(Some(layer)) = self.sprite_layers.get_mut(z_order) {
+            for index in tiles.iter() {
+                layer.dirty.insert(*index);
+            }
+        }
+        !tiles.is_empty()
+    }
+
+    /// Takes the tiles of a layer which changed since the last time, as
+    /// ranges of tile indexes.
+    pub(crate) fn take_dirty_ranges(&mut self, z_order: usize, area: usize) -> Vec<Range<usize>> {
+        match self.sprite_layers.get_mut(z_order) {
+            Some(Some(layer)) => layer.dirty.take_ranges(area),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the mesh of a layer.
+    pub(crate) fn mesh(&self, z_order: usize) -> Option<&Handle<Mesh>> {
+        match self.sprite_layers.get(z_order) {
+            Some(Some(layer)) => Some(layer.inner.as_ref().mesh()),
+            _ => None,
+        }
+    }
+
     /// Gets a vec of all the tiles in the layer, if any.
-    #[cfg(feature = "bevy_rapier2d")]
     pub(crate) fn get_tile_indices(&self, z_order: usize) -> Option<Vec<usize>> {
         self.sprite_layers.get(z_order).and_then(|layer| {
             layer
@@ -298,19 +474,88 @@ impl Chunk {
         })
     }
 
+    /// Gets the fog state of a tile from a provided z order and index, if the
+    /// layer is a fog layer.
+    pub(crate) fn get_fog_state(&self, z_order: usize, index: usize) -> Option<FogState> {
+        match self.sprite_layers.get(z_order) {
+            Some(Some(SpriteLayer {
+                inner: LayerKindInner::Fog(layer),
+                ..
+            })) => layer.state(index),
+            _ => None,
+        }
+    }
+
+    /// Sets the fog state of a tile at a z order and index, returning `true`
+    /// if it changed.
+    pub(crate) fn set_fog_state(&mut self, z_order: usize, index: usize, state: FogState) -> bool {
+        match self.sprite_layers.get_mut(z_order) {
+            Some(Some(SpriteLayer {
+                inner: LayerKindInner::Fog(layer),
+                dirty,
+                ..
+            })) => {
+                let changed = layer.set_state(index, state);
+                if changed {
+                    dirty.insert(index);
+                }
+                changed
+            }
+            _ => {
+                error!("sprite layer {} is not a fog layer", z_order);
+                false
+            }
+        }
+    }
+
     /// At the given z layer, changes the tiles into attributes for use with
     /// the renderer using the given dimensions.
     ///
-    /// Easier to pass in the dimensions opposed to storing it everywhere.
+    /// Easier to pass in the dimensions opposed to storing it everywhere. Fog
+    /// layers are turned into overlays with the fog style.
     pub(crate) fn tiles_to_renderer_parts(
         &self,
         z: usize,
         dimensions: Dimension2,
-    ) -> Option<(Vec<f32>, Vec<[f32; 4]>)> {
+        fog_style: &FogStyle,
+    ) -> Option<(Vec<f32>, Vec<[f32; 4]>, Vec<f32>)> {
         let area = dimensions.area() as usize;
         self.sprite_layers.get(z).and_then(|o| {
-            o.as_ref()
-                .map(|layer| layer.inner.as_ref().tiles_to_attributes(area))
+            o.as_ref().map(|layer| match &layer.inner {
+                LayerKindInner::Fog(fog) => fog.tiles_to_fog_attributes(fog_style),
+                inner => inner.as_ref().tiles_to_attributes(area),
+            })
         })
     }
+
+    /// At the given z layer, changes a range of tiles into attributes for use
+    /// with the renderer, the same as [`Chunk::tiles_to_renderer_parts`] does
+    /// for the whole layer.
+    pub(crate) fn tiles_to_renderer_range(
+        &self,
+        z: usize,
+        range: Range<usize>,
+        fog_style: &FogStyle,
+    ) -> Option<(Vec<f32>, Vec<[f32; 4]>, Vec<f32>)> {
+        let layer = self.sprite_layers.get(z)?.as_ref()?;
+        let tiles: Vec<RawTile> = match &layer.inner {
+            LayerKindInner::Fog(fog) => range
+                .map(|index| fog_style.tile(fog.state(index).unwrap_or(FogState::Unexplored)))
+                .collect(),
+            inner => range
+                .map(|index| {
+                    inner
+                        .as_ref()
+                        .get_tile(index)
+                        .copied()
+                        .unwrap_or_else(|| RawTile {
+                            index: 0,
+                            color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+                            flip: TileFlip::empty(),
+                        })
+                })
+                .collect(),
+        };
+        Some(raw_tile::dense_tiles_to_attributes(&tiles))
+    }
 }
@@ -1,126 +1,123 @@
-use crate::lib::*;
+use crate::{lib::*, GridTopology};
 
-macro_rules! build_chunk_pipeline {
-    ($handle: ident, $id: expr, $name: ident, $file: expr) => {
+macro_rules! chunk_pipeline_handle {
+    ($handle: ident, $id: expr) => {
         /// The constant render pipeline for a chunk.
         pub(crate) const $handle: HandleUntyped =
             HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, $id);
-
-        /// Builds the chunk render pipeline.
-        fn $name(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
-            PipelineDescriptor {
-                rasterization_state: Some(RasterizationStateDescriptor {
-                    front_face: FrontFace::Ccw,
-                    cull_mode: CullMode::None,
-                    depth_bias: 0,
-                    depth_bias_slope_scale: 0.0,
-                    depth_bias_clamp: 0.0,
-                    clamp_depth: false,
-                }),
-                color_states: vec![ColorStateDescriptor {
-                    format: TextureFormat::default(),
-                    color_blend: BlendDescriptor {
-                        src_factor: BlendFactor::SrcAlpha,
-                        dst_factor: BlendFactor::OneMinusSrcAlpha,
-                        operation: BlendOperation::Add,
-                    },
-                    alpha_blend: BlendDescriptor {
-                        src_factor: BlendFactor::One,
-                        dst_factor: BlendFactor::One,
-                        operation: BlendOperation::Add,
-                    },
-                    write_mask: ColorWrite::ALL,
-                }],
-                depth_stencil_state: Some(DepthStencilStateDescriptor {
-                    format: TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: CompareFunction::LessEqual,
-                    stencil: StencilStateDescriptor {
-                        front: StencilStateFaceDescriptor::IGNORE,
-                        back: StencilStateFaceDescriptor::IGNORE,
-                        read_mask: 0,
-                        write_mask: 0,
-                    },
-                }),
-                ..PipelineDescriptor::new(ShaderStages {
-                    vertex: shaders
-                        .add(Shader::from_glsl(ShaderStage::Vertex, include_str!($file))),
-                    fragment: Some(shaders.add(Shader::from_glsl(
-                        ShaderStage::Fragment,
-                        include_str!("tilemap.frag"),
-                    ))),
-                })
-            }
-        }
     };
 }
 
-build_chunk_pipeline!(
-    CHUNK_SQUARE_PIPELINE,
-    2110840099625352487,
-    build_chunk_square_pipeline,
-    "tilemap-square.vert"
-);
-build_chunk_pipeline!(
-    CHUNK_HEX_X_PIPELINE,
-    7038597873061171051,
-    build_chunk_hex_x,
-    "tilemap-hex-x.vert"
-);
-build_chunk_pipeline!(
-    CHUNK_HEX_Y_PIPELINE,
-    4304966217182648108,
-    build_chunk_hex_y,
-    "tilemap-hex-y.vert"
-);
-build_chunk_pipeline!(
-    CHUNK_HEXCOLS_EVEN_PIPELINE,
-    7604280309043018950,
-    build_chunk_hexcols_even,
-    "tilemap-hexcols-even.vert"
-);
-build_chunk_pipeline!(
-    CHUNK_HEXCOLS_ODD_PIPELINE,
-    3111565682159860869,
-    build_chunk_hexcols_odd,
-    "tilemap-hexcols-odd.vert"
-);
-build_chunk_pipeline!(
-    CHUNK_HEXROWS_EVEN_PIPELINE,
-    1670470246078408352,
-    build_chunk_hexrows_even,
-    "tilemap-hexrows-even.vert"
-);
-build_chunk_pipeline!(
-    CHUNK_HEXROWS_ODD_PIPELINE,
-    8160067835497533408,
-    build_chunk_hexrows_odd,
-    "tilemap-hexrows-odd.vert"
-);
-
-/// Topology of the tilemap grid (square or hex)
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum GridTopology {
-    /// Square grid
-    Square,
-    /// Hex grid with rows offset (hexes with pointy top).
-    HexY,
-    /// Hex grid with columns offset (hexes with flat top).
-    HexX,
-    /// Hex grid with offset on even rows (hexes with pointy top).
-    HexEvenRows,
-    /// Hex grid with offset on odd rows (hexes with pointy top).
-    HexOddRows,
-    /// Hex grid with offset on even columns (hexes with flat top).
-    HexEvenCols,
-    /// Hex grid with offset on odd columns (hexes with flat top).
-    HexOddCols,
+chunk_pipeline_handle!(CHUNK_SQUARE_PIPELINE, 2110840099625352487);
+chunk_pipeline_handle!(CHUNK_HEX_X_PIPELINE, 7038597873061171051);
+chunk_pipeline_handle!(CHUNK_HEX_Y_PIPELINE, 4304966217182648108);
+chunk_pipeline_handle!(CHUNK_HEXCOLS_EVEN_PIPELINE, 7604280309043018950);
+chunk_pipeline_handle!(CHUNK_HEXCOLS_ODD_PIPELINE, 3111565682159860869);
+chunk_pipeline_handle!(CHUNK_HEXROWS_EVEN_PIPELINE, 1670470246078408352);
+chunk_pipeline_handle!(CHUNK_HEXROWS_ODD_PIPELINE, 8160067835497533408);
+chunk_pipeline_handle!(CHUNK_ISOMETRIC_PIPELINE, 7049910581942208829);
+chunk_pipeline_handle!(CHUNK_STAGGERED_ISOMETRIC_PIPELINE, 5742354528107326601);
+
+/// Every grid topology, which all have their own render pipeline.
+const TOPOLOGIES: [GridTopology; 9] = [
+    GridTopology::Square,
+    GridTopology::HexX,
+    GridTopology::HexY,
+    GridTopology::HexEvenCols,
+    GridTopology::HexOddCols,
+    GridTopology::HexEvenRows,
+    GridTopology::HexOddRows,
+    GridTopology::Isometric,
+    GridTopology::StaggeredIsometric,
+];
+
+/// Builds a chunk render pipeline from its shaders.
+fn build_chunk_pipeline(vertex: Handle<Shader>, fragment: Handle<Shader>) -> PipelineDescriptor {
+    PipelineDescriptor {
+        rasterization_state: Some(RasterizationStateDescriptor {
+            front_face: FrontFace::Ccw,
+            cull_mode: CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+            clamp_depth: false,
+        }),
+        color_states: vec![ColorStateDescriptor {
+            format: TextureFormat::default(),
+            color_blend: BlendDescriptor {
+                src_factor: BlendFactor::SrcAlpha,
+                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                operation: BlendOperation::Add,
+            },
+            alpha_blend: BlendDescriptor {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            write_mask: ColorWrite::ALL,
+        }],
+        depth_stencil_state: Some(DepthStencilStateDescriptor {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::LessEqual,
+            stencil: StencilStateDescriptor {
+                front: StencilStateFaceDescriptor::IGNORE,
+                back: StencilStateFaceDescriptor::IGNORE,
+                read_mask: 0,
+                write_mask: 0,
+            },
+        }),
+        ..PipelineDescriptor::new(ShaderStages {
+            vertex,
+            fragment: Some(fragment),
+        })
+    }
 }
 
-impl GridTopology {
+/// Rendering helpers of a grid topology.
+pub(crate) trait GridTopologyExt {
     /// Takes a grid topology and returns a handle.
-    pub(crate) fn to_pipeline_handle(&self) -> HandleUntyped {
+    fn to_pipeline_handle(&self) -> HandleUntyped;
+
+    /// Returns the source of the vertex shader of the grid topology.
+    fn vertex_shader(&self) -> &'static str;
+
+    /// Returns the translation of a chunk from the tilemap in pixels.
+    fn chunk_translation(
+        &self,
+        point: Point2,
+        chunk_dimensions: Dimension2,
+        tile_dimensions: Dimension2,
+    ) -> Vec2;
+
+    /// Returns the center of a tile from the center of its chunk in pixels.
+    ///
+    /// This mirrors the offsets and compaction done by the vertex shaders.
+    fn tile_center(
+        &self,
+        tile_point: Point2,
+        chunk_dimensions: Dimension2,
+        tile_dimensions: Dimension2,
+    ) -> Vec2;
+
+    /// Returns the corners of the cell of a tile from its center in pixels, in
+    /// counter clockwise order.
+    fn tile_polygon(&self, tile_dimensions: Dimension2) -> Vec<Vec2>;
+
+    /// Approximates how many tiles an offset in pixels spans, rounded to the
+    /// closest tile.
+    fn tile_offset(&self, offset: Vec2, tile_dimensions: Dimension2) -> Point2;
+
+    /// Returns the squared distance of an offset in pixels, scaled so that the
+    /// closest tile center is always the one of the tile containing it.
+    ///
+    /// Hexes are stretched to be regular, where the cells around the tile
+    /// centers are exactly the hexes.
+    fn scaled_distance(&self, offset: Vec2, tile_dimensions: Dimension2) -> f32;
+}
+
+impl GridTopologyExt for GridTopology {
+    fn to_pipeline_handle(&self) -> HandleUntyped {
         use GridTopology::*;
         match self {
             Square => CHUNK_SQUARE_PIPELINE,
@@ -130,14 +127,307 @@ impl GridTopology {
             HexOddRows => CHUNK_HEXROWS_ODD_PIPELINE,
             HexEvenCols => CHUNK_HEXCOLS_EVEN_PIPELINE,
             HexOddCols => CHUNK_HEXCOLS_ODD_PIPELINE,
+            Isometric => CHUNK_ISOMETRIC_PIPELINE,
+            StaggeredIsometric => CHUNK_STAGGERED_ISOMETRIC_PIPELINE,
+        }
+    }
+
+    fn vertex_shader(&self) -> &'static str {
+        use GridTopology::*;
+        match self {
+            Square => include_str!("tilemap-square.vert"),
+            HexY => include_str!("tilemap-hex-y.vert"),
+            HexX => include_str!("tilemap-hex-x.vert"),
+            HexEvenRows => include_str!("tilemap-hexrows-even.vert"),
+            HexOddRows => include_str!("tilemap-hexrows-odd.vert"),
+            HexEvenCols => include_str!("tilemap-hexcols-even.vert"),
+            HexOddCols => include_str!("tilemap-hexcols-odd.vert"),
+            Isometric => include_str!("tilemap-isometric.vert"),
+            StaggeredIsometric => include_str!("tilemap-staggered-isometric.vert"),
         }
     }
+
+    fn chunk_translation(
+        &self,
+        point: Point2,
+        chunk_dimensions: Dimension2,
+        tile_dimensions: Dimension2,
+    ) -> Vec2 {
+        use GridTopology::*;
+        let x = match self {
+            HexX | HexEvenCols | HexOddCols => {
+                (((point.x * tile_dimensions.width as i32) as f32 * 0.75) as i32
+                    * chunk_dimensions.width as i32) as f32
+            }
+            HexY => {
+                (point.x * tile_dimensions.width as i32 * chunk_dimensions.width as i32) as f32
+                    + (point.y as f32 * chunk_dimensions.height as f32 * 0.5)
+                        * tile_dimensions.width as f32
+            }
+            Square | HexEvenRows | HexOddRows | StaggeredIsometric => {
+                (point.x * tile_dimensions.width as i32 * chunk_dimensions.width as i32) as f32
+            }
+            Isometric => {
+                (point.x * chunk_dimensions.width as i32 - point.y * chunk_dimensions.height as i32)
+                    as f32
+                    * tile_dimensions.width as f32
+                    * 0.5
+            }
+        };
+        let y = match self {
+            HexX => {
+                (point.y * tile_dimensions.height as i32 * chunk_dimensions.height as i32) as f32
+                    + (point.x as f32 * chunk_dimensions.width as f32 * 0.5)
+                        * tile_dimensions.height as f32
+            }
+            HexY | HexEvenRows | HexOddRows => {
+                (((point.y * tile_dimensions.height as i32) as f32 * 0.75) as i32
+                    * chunk_dimensions.height as i32) as f32
+            }
+            Square | HexEvenCols | HexOddCols => {
+                (point.y * tile_dimensions.height as i32 * chunk_dimensions.height as i32) as f32
+            }
+            Isometric => {
+                (point.x * chunk_dimensions.width as i32 + point.y * chunk_dimensions.height as i32)
+                    as f32
+                    * tile_dimensions.height as f32
+                    * 0.5
+            }
+            StaggeredIsometric => {
+                (point.y * tile_dimensions.height as i32 * chunk_dimensions.height as i32) as f32
+                    * 0.5
+            }
+        };
+        Vec2::new(x, y)
+    }
+
+    fn tile_center(
+        &self,
+        tile_point: Point2,
+        chunk_dimensions: Dimension2,
+        tile_dimensions: Dimension2,
+    ) -> Vec2 {
+        use GridTopology::*;
+        let width = tile_dimensions.width as f32;
+        let height = tile_dimensions.height as f32;
+        let x0 = tile_point.x as f32 - chunk_dimensions.width as f32 / 2.0;
+        let y0 = tile_point.y as f32 - chunk_dimensions.height as f32 / 2.0;
+        // The shaders count rows and columns from the far edge of a tile.
+        let row = (y0 + 0.01).floor() as i32 + 1;
+        let col = (x0 + 0.01).floor() as i32 + 1;
+
+        let mut x = (x0 + 0.5) * width;
+        let mut y = (y0 + 0.5) * height;
+        match self {
+            Square => {}
+            HexY => {
+                x += (0.5 * width).floor() * row as f32;
+                y -= row as f32 * (0.25 * height).ceil();
+            }
+            HexX => {
+                y += (0.5 * height).floor() * col as f32;
+                x -= col as f32 * (0.25 * width).ceil();
+            }
+            HexEvenRows | HexOddRows => {
+                let offset = (0.25 * width).floor();
+                if (row % 2 == 0) == (*self == HexEvenRows) {
+                    x -= offset;
+                } else {
+                    x += offset;
+                }
+                y -= row as f32 * (0.25 * height).ceil();
+            }
+            HexEvenCols | HexOddCols => {
+                let offset = (0.25 * height).floor();
+                if (col % 2 == 0) == (*self == HexEvenCols) {
+                    y -= offset;
+                } else {
+                    y += offset;
+                }
+                x -= col as f32 * (0.25 * width).ceil();
+            }
+            Isometric => {
+                x = (x0 - y0) * 0.5 * width;
+                y = (x0 + y0 + 1.0) * 0.5 * height;
+            }
+            StaggeredIsometric => {
+                if ((y0 + 0.5).floor() as i32) & 1 != 0 {
+                    x += 0.5 * width;
+                }
+                y *= 0.5;
+            }
+        }
+        Vec2::new(x, y)
+    }
+
+    fn tile_polygon(&self, tile_dimensions: Dimension2) -> Vec<Vec2> {
+        use GridTopology::*;
+        let x = tile_dimensions.width as f32 / 2.0;
+        let y = tile_dimensions.height as f32 / 2.0;
+        let corners = match self {
+            Square => vec![(-x, -y), (x, -y), (x, y), (-x, y)],
+            HexY | HexEvenRows | HexOddRows => vec![
+                (0.0, -y),
+                (x, -y / 2.0),
+                (x, y / 2.0),
+                (0.0, y),
+                (-x, y / 2.0),
+                (-x, -y / 2.0),
+            ],
+            HexX | HexEvenCols | HexOddCols => vec![
+                (-x / 2.0, -y),
+                (x / 2.0, -y),
+                (x, 0.0),
+                (x / 2.0, y),
+                (-x / 2.0, y),
+                (-x, 0.0),
+            ],
+            Isometric | StaggeredIsometric => vec![(0.0, -y), (x, 0.0), (0.0, y), (-x, 0.0)],
+        };
+        corners.into_iter().map(|(x, y)| Vec2::new(x, y)).collect()
+    }
+
+    fn tile_offset(&self, offset: Vec2, tile_dimensions: Dimension2) -> Point2 {
+        use GridTopology::*;
+        let width = tile_dimensions.width as f32;
+        let height = tile_dimensions.height as f32;
+        let (x, y) = match self {
+            Square => (offset.x / width, offset.y / height),
+            HexY => {
+                let y = offset.y / (0.75 * height);
+                (offset.x / width - y / 2.0, y)
+            }
+            HexEvenRows | HexOddRows => (offset.x / width, offset.y / (0.75 * height)),
+            HexX => {
+                let x = offset.x / (0.75 * width);
+                (x, offset.y / height - x / 2.0)
+            }
+            HexEvenCols | HexOddCols => (offset.x / (0.75 * width), offset.y / height),
+            Isometric => (
+                offset.x / width + offset.y / height,
+                offset.y / height - offset.x / width,
+            ),
+            StaggeredIsometric => (offset.x / width, offset.y / (0.5 * height)),
+        };
+        Point2::new(x.round() as i32, y.round() as i32)
+    }
+
+    fn scaled_distance(&self, offset: Vec2, tile_dimensions: Dimension2) -> f32 {
+        let mut x = offset.x / tile_dimensions.width as f32;
+        let mut y = offset.y / tile_dimensions.height as f32;
+        match self {
+            GridTopology::Square | GridTopology::Isometric | GridTopology::StaggeredIsometric => {}
+            GridTopology::HexY | GridTopology::HexEvenRows | GridTopology::HexOddRows => {
+                y *= 2.0 / 3.0_f32.sqrt()
+            }
+            GridTopology::HexX | GridTopology::HexEvenCols | GridTopology::HexOddCols => {
+                x *= 2.0 / 3.0_f32.sqrt()
+            }
+        }
+        x * x + y * y
+    }
 }
 
 /// A trait which implements the tilemap graph to a render graph.
+///
+/// Besides the pipelines of every grid topology, which are added by the
+/// [`Tilemap2DPlugin`], layers can be drawn with custom pipelines and bound
+/// to uniforms of their own. This can be used for a water layer with
+/// scrolling texture coordinates, an emissive layer or palette swaps.
+///
+/// ```no_run
+/// use bevy_asset::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_reflect::TypeUuid;
+/// use bevy_render::{
+///     pipeline::PipelineDescriptor,
+///     prelude::*,
+///     render_graph::RenderGraph,
+///     renderer::RenderResources,
+///     shader::ShaderStage,
+/// };
+/// use bevy_tilemap::{chunk::TilemapRenderGraphBuilder, prelude::*};
+///
+/// #[derive(RenderResources, TypeUuid)]
+/// #[uuid = "3c6b4e5e-1d7a-4b9a-a0d4-2fb5e9c1b1a7"]
+/// struct WaterMaterial {
+///     time: f32,
+/// }
+///
+/// const WATER_SHADER: &str = r"
+/// #version 450
+///
+/// layout(location = 0) in vec2 v_Uv;
+/// layout(location = 1) in vec4 v_Color;
+///
+/// layout(location = 0) out vec4 o_Target;
+///
+/// layout(set = 1, binding = 2) uniform texture2D TextureAtlas_texture;
+/// layout(set = 1, binding = 3) uniform sampler TextureAtlas_texture_sampler;
+///
+/// layout(set = 3, binding = 0) uniform WaterMaterial_time {
+///     float Time;
+/// };
+///
+/// void main() {
+///     vec2 uv = v_Uv + vec2(sin(Time) * 0.002, 0.0);
+///     o_Target = v_Color * texture(
+///         sampler2D(TextureAtlas_texture, TextureAtlas_texture_sampler),
+///         uv
+///     );
+/// }
+/// ";
+///
+/// fn setup(resources: &mut Resources) -> Handle<PipelineDescriptor> {
+///     let mut render_graph = resources.get_mut::<RenderGraph>().unwrap();
+///     render_graph.add_tilemap_material::<WaterMaterial>("water_material");
+///     render_graph.add_tilemap_pipeline(
+///         resources,
+///         GridTopology::Square,
+///         Shader::from_glsl(ShaderStage::Fragment, WATER_SHADER),
+///     )
+/// }
+///
+/// fn water_layer(pipeline: Handle<PipelineDescriptor>) -> TilemapLayer {
+///     TilemapLayer {
+///         pipeline: Some(pipeline.id),
+///         ..Default::default()
+///     }
+/// }
+/// ```
+///
+/// The fragment shader receives the texture coordinates and the color of the
+/// tiles in the same way as the default one, which binds the texture atlas
+/// to `set = 1, binding = 2` and its sampler to `set = 1, binding = 3`.
+/// Uniforms of materials are bound from `set = 3`, such as
+/// `layout(set = 3, binding = 0) uniform WaterMaterial_time`, for the chunk
+/// layer entities with a handle of the material. Insert that handle when a
+/// [`TilemapChunkLayerEvent::Spawned`] is sent for the layer.
+///
+/// [`Tilemap2DPlugin`]: crate::Tilemap2DPlugin
+/// [`TilemapChunkLayerEvent::Spawned`]: crate::event::TilemapChunkLayerEvent::Spawned
 pub trait TilemapRenderGraphBuilder {
     /// Adds the tilemaps render graph.
     fn add_tilemap_graph(&mut self, resources: &Resources) -> &mut Self;
+
+    /// Adds a pipeline which draws the layers of a grid topology with a custom
+    /// fragment shader, and returns its handle to set in a [`TilemapLayer`].
+    ///
+    /// Like the pipelines of the grid topologies, it is kept until the app
+    /// exits, so the returned handle does not need to be held on to.
+    ///
+    /// [`TilemapLayer`]: crate::TilemapLayer
+    fn add_tilemap_pipeline(
+        &mut self,
+        resources: &Resources,
+        topology: GridTopology,
+        fragment: Shader,
+    ) -> Handle<PipelineDescriptor>;
+
+    /// Adds a node which binds the render resources of a material type as
+    /// uniforms of the chunk layer entities with a handle of it.
+    fn add_tilemap_material<M: RenderResources + Asset>(&mut self, name: &'static str)
+        -> &mut Self;
 }
 
 impl TilemapRenderGraphBuilder for RenderGraph {
@@ -149,29 +439,55 @@ impl TilemapRenderGraphBuilder for RenderGraph {
             .get_mut::<Assets<Shader>>()
             .expect("`Shader` is missing.");
 
-        pipelines.set_untracked(
-            CHUNK_SQUARE_PIPELINE,
-            build_chunk_square_pipeline(&mut shaders),
-        );
-        pipelines.set_untracked(CHUNK_HEX_X_PIPELINE, build_chunk_hex_x(&mut shaders));
-        pipelines.set_untracked(CHUNK_HEX_Y_PIPELINE, build_chunk_hex_y(&mut shaders));
-        pipelines.set_untracked(
-            CHUNK_HEXCOLS_EVEN_PIPELINE,
-            build_chunk_hexcols_even(&mut shaders),
-        );
-        pipelines.set_untracked(
-            CHUNK_HEXCOLS_ODD_PIPELINE,
-            build_chunk_hexcols_odd(&mut shaders),
-        );
-        pipelines.set_untracked(
-            CHUNK_HEXROWS_EVEN_PIPELINE,
-            build_chunk_hexrows_even(&mut shaders),
-        );
-        pipelines.set_untracked(
-            CHUNK_HEXROWS_ODD_PIPELINE,
-            build_chunk_hexrows_odd(&mut shaders),
-        );
+        let fragment = shaders.add(Shader::from_glsl(
+            ShaderStage::Fragment,
+            include_str!("tilemap.frag"),
+        ));
+        for topology in TOPOLOGIES.iter() {
+            let vertex = shaders.add(Shader::from_glsl(
+                ShaderStage::Vertex,
+                topology.vertex_shader(),
+            ));
+            pipelines.set_untracked(
+                topology.to_pipeline_handle(),
+                build_chunk_pipeline(vertex, fragment.clone()),
+            );
+        }
+
+        self
+    }
+
+    fn add_tilemap_pipeline(
+        &mut self,
+        resources: &Resources,
+        topology: GridTopology,
+        fragment: Shader,
+    ) -> Handle<PipelineDescriptor> {
+        let mut pipelines = resources
+            .get_mut::<Assets<PipelineDescriptor>>()
+            .expect("`PipelineDescriptor` is missing.");
+        let mut shaders = resources
+            .get_mut::<Assets<Shader>>()
+            .expect("`Shader` is missing.");
+
+        let vertex = shaders.add(Shader::from_glsl(
+            ShaderStage::Vertex,
+            topology.vertex_shader(),
+        ));
+        let fragment = shaders.add(fragment);
+        let handle = Handle::weak(HandleId::random::<PipelineDescriptor>());
+        pipelines.set_untracked(handle.clone_weak(), build_chunk_pipeline(vertex, fragment));
+        handle
+    }
 
+    fn add_tilemap_material<M: RenderResources + Asset>(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self {
+        self.add_system_node(name, AssetRenderResourcesNode::<M>::new(true));
+        if let Err(e) = self.add_node_edge(name, node::MAIN_PASS) {
+            error!("can not add tilemap material `{}`: {}", name, e);
+        }
         self
     }
 }
@@ -3,6 +3,9 @@
 layout(location = 0) in vec3 Vertex_Position;
 layout(location = 1) in float Vertex_Tile_Index;
 layout(location = 2) in vec4 Vertex_Tile_Color;
+layout(location = 3) in float Vertex_Tile_Flip;
+layout(location = 4) in vec2 Vertex_Cell_Dimensions;
+layout(location = 5) in vec2 Vertex_Tile_Anchor;
 
 layout(location = 0) out vec2 v_Uv;
 layout(location = 1) out vec4 v_Color;
@@ -38,7 +41,7 @@ void main() {
     int local_index = gl_VertexIndex % 4;
 
     vec3 vertex_position = vec3(
-        Vertex_Position.xy * sprite_dimensions,
+        Vertex_Position.xy * Vertex_Cell_Dimensions,
         0.0
     );
 
@@ -49,7 +52,7 @@ void main() {
     }
 
     // offset alternating rows
-    float xoffset = floor(0.25 * sprite_dimensions.x);
+    float xoffset = floor(0.25 * Vertex_Cell_Dimensions.x);
     if (row % 2 == 0) {
         vertex_position.x -= xoffset;
     } else {
@@ -57,15 +60,36 @@ void main() {
     }
 
     // compact (remove gaps between rows)
-    vertex_position.y -= float(row) * ceil(0.25 * sprite_dimensions.y);
+    vertex_position.y -= float(row) * ceil(0.25 * Vertex_Cell_Dimensions.y);
 
-    vec2 atlas_positions[4] = vec2[](
-        vec2(sprite_rect.begin.x, sprite_rect.end.y),
-        sprite_rect.begin,
-        vec2(sprite_rect.end.x, sprite_rect.begin.y),
-        sprite_rect.end
+    // the corner of the sprite for this vertex, with y pointing down
+    vec2 corners[4] = vec2[](
+        vec2(0.0, 1.0),
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.0)
     );
-    v_Uv = floor(atlas_positions[local_index]) / AtlasSize;
+    vec2 corner = corners[local_index];
+
+    // grow sprites of another size than a cell around their anchor
+    vec2 quad_corner = vec2(corner.x, 1.0 - corner.y);
+    vertex_position.xy += (quad_corner - Vertex_Tile_Anchor)
+        * (sprite_dimensions - Vertex_Cell_Dimensions);
+
+    // flip vertically, then horizontally, then along the anti-diagonal
+    int flip = int(Vertex_Tile_Flip + 0.5);
+    if ((flip & 2) != 0) {
+        corner.y = 1.0 - corner.y;
+    }
+    if ((flip & 1) != 0) {
+        corner.x = 1.0 - corner.x;
+    }
+    if ((flip & 4) != 0) {
+        corner = corner.yx;
+    }
+
+    vec2 atlas_position = mix(sprite_rect.begin, sprite_rect.end, corner);
+    v_Uv = floor(atlas_position) / AtlasSize;
     v_Color = Vertex_Tile_Color;
     gl_Position = ViewProj * ChunkTransform * vec4(ceil(vertex_position), 1.0);
 }
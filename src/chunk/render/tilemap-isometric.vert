@@ -0,0 +1,80 @@
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in float Vertex_Tile_Index;
+layout(location = 2) in vec4 Vertex_Tile_Color;
+layout(location = 3) in float Vertex_Tile_Flip;
+layout(location = 4) in vec2 Vertex_Cell_Dimensions;
+layout(location = 5) in vec2 Vertex_Tile_Anchor;
+
+layout(location = 0) out vec2 v_Uv;
+layout(location = 1) out vec4 v_Color;
+
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+
+// TODO: merge dimensions into "sprites" buffer when that is supported in the Uniforms derive abstraction
+layout(set = 1, binding = 0) uniform TextureAtlas_size {
+    vec2 AtlasSize;
+};
+
+struct Rect {
+    // Upper-left coordinate
+    vec2 begin;
+    // Bottom-right coordinate
+    vec2 end;
+};
+
+layout(set = 1, binding = 1) buffer TextureAtlas_textures {
+    Rect[] Textures;
+};
+
+layout(set = 2, binding = 0) uniform Transform {
+    mat4 ChunkTransform;
+};
+
+void main() {
+    Rect sprite_rect = Textures[int(Vertex_Tile_Index)];
+    vec2 sprite_dimensions = sprite_rect.end - sprite_rect.begin;
+    // the corner of the sprite for this vertex, with y pointing down
+    vec2 corners[4] = vec2[](
+        vec2(0.0, 1.0),
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.0)
+    );
+    vec2 corner = corners[gl_VertexIndex % 4];
+    vec2 quad_corner = vec2(corner.x, 1.0 - corner.y);
+
+    // the cell of the vertex in diamond coordinates, where the x axis points
+    // north-east and the y axis north-west
+    vec2 cell = Vertex_Position.xy - quad_corner + vec2(0.5, 0.5);
+    vec3 vertex_position = vec3(
+        (cell.x - cell.y) * 0.5 * Vertex_Cell_Dimensions.x,
+        (cell.x + cell.y) * 0.5 * Vertex_Cell_Dimensions.y,
+        0.0
+    );
+    vertex_position.xy += (quad_corner - vec2(0.5, 0.5)) * Vertex_Cell_Dimensions;
+
+    // grow sprites of another size than a cell around their anchor
+    vertex_position.xy += (quad_corner - Vertex_Tile_Anchor)
+        * (sprite_dimensions - Vertex_Cell_Dimensions);
+
+    // flip vertically, then horizontally, then along the anti-diagonal
+    int flip = int(Vertex_Tile_Flip + 0.5);
+    if ((flip & 2) != 0) {
+        corner.y = 1.0 - corner.y;
+    }
+    if ((flip & 1) != 0) {
+        corner.x = 1.0 - corner.x;
+    }
+    if ((flip & 4) != 0) {
+        corner = corner.yx;
+    }
+
+    vec2 atlas_position = mix(sprite_rect.begin, sprite_rect.end, corner);
+    v_Uv = floor(atlas_position + vec2(0.01, 0.01)) / AtlasSize;
+    v_Color = Vertex_Tile_Color;
+    gl_Position = ViewProj * ChunkTransform * vec4(ceil(vertex_position), 1.0);
+}
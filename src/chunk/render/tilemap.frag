@@ -16,4 +16,8 @@ void main() {
         sampler2D(TextureAtlas_texture, TextureAtlas_texture_sampler),
         v_Uv
     );
+    // transparent parts of sprites must not hide what is drawn after them
+    if (o_Target.a == 0.0) {
+        discard;
+    }
 }
@@ -1,48 +1,87 @@
 use crate::{
+    animation::apply_sprite_frames,
     chunk::{
-        entity::{ModifiedLayer, ZOrder},
+        entity::{ChunkTilemap, ZOrder},
         mesh::ChunkMesh,
+        render::GridTopologyExt,
     },
     lib::*,
+    tile::TileAnchor,
     Tilemap,
 };
 
 /// The chunk update system that is used to set attributes of the tiles and
 /// tints if they need updating.
-pub(crate) fn chunk_update(
-    mut meshes: ResMut<Assets<Mesh>>,
-    map_query: Query<&Tilemap>,
-    mut chunk_query: Query<(&Parent, &Point2, &ZOrder, &Handle<Mesh>), Changed<ModifiedLayer>>,
-) {
-    for (parent, point, z_order, mesh_handle) in chunk_query.iter_mut() {
-        let tilemap = if let Ok(tilemap) = map_query.get(**parent) {
-            tilemap
-        } else {
-            error!("`Tilemap` is missing, can not update chunk");
-            return;
-        };
-        let chunk = if let Some(chunk) = tilemap.get_chunk(point) {
-            chunk
-        } else {
-            error!("`Chunk` is missing, can not update chunk");
-            return;
-        };
-        let mesh = if let Some(mesh) = meshes.get_mut(mesh_handle) {
-            mesh
-        } else {
-            error!("`Mesh` is missing, can not update chunk");
-            return;
-        };
-        let (indexes, colors) = if let Some((index, colors)) =
-            chunk.tiles_to_renderer_parts(z_order.0, tilemap.chunk_dimensions())
-        {
-            (index, colors)
-        } else {
-            error!("Tiles are missing, can not update chunk");
-            return;
-        };
-        mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_INDEX, indexes);
-        mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_COLOR, colors);
+///
+/// Only the chunks which changed since the last update are visited, and only
+/// the vertices of their tiles which changed are rewritten, in as few ranges
+/// as possible.
+pub(crate) fn chunk_update(mut meshes: ResMut<Assets<Mesh>>, mut map_query: Query<&mut Tilemap>) {
+    for mut tilemap in map_query.iter_mut() {
+        if !tilemap.has_dirty_chunks() {
+            continue;
+        }
+        let area = tilemap.chunk_dimensions().area() as usize;
+        let fog_style = tilemap.fog_style();
+        let sprite_frames = tilemap.sprite_animation_frames();
+        let dirty_chunks = tilemap.take_dirty_chunks();
+        for chunk_point in dirty_chunks.iter() {
+            let chunk = match tilemap.chunks_mut().get_mut(chunk_point) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            for z_order in 0..chunk.z_layers() {
+                let ranges = chunk.take_dirty_ranges(z_order, area);
+                if ranges.is_empty() || chunk.get_entity(z_order).is_none() {
+                    continue;
+                }
+                let mesh = if let Some(mesh) = chunk
+                    .mesh(z_order)
+                    .and_then(|handle| meshes.get_mut(handle))
+                {
+                    mesh
+                } else {
+                    error!("`Mesh` is missing, can not update chunk");
+                    continue;
+                };
+                for range in ranges {
+                    let start = range.start * 4;
+                    let (mut indexes, colors, flips) = if let Some(parts) =
+                        chunk.tiles_to_renderer_range(z_order, range, &fog_style)
+                    {
+                        parts
+                    } else {
+                        error!("Tiles are missing, can not update chunk");
+                        break;
+                    };
+                    apply_sprite_frames(&sprite_frames, &mut indexes);
+                    if let Some(VertexAttributeValues::Float(values)) =
+                        mesh.attribute_mut(ChunkMesh::ATTRIBUTE_TILE_INDEX)
+                    {
+                        overwrite(values, start, &indexes);
+                    }
+                    if let Some(VertexAttributeValues::Float4(values)) =
+                        mesh.attribute_mut(ChunkMesh::ATTRIBUTE_TILE_COLOR)
+                    {
+                        overwrite(values, start, &colors);
+                    }
+                    if let Some(VertexAttributeValues::Float(values)) =
+                        mesh.attribute_mut(ChunkMesh::ATTRIBUTE_TILE_FLIP)
+                    {
+                        overwrite(values, start, &flips);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Overwrites the values of a vertex attribute from a vertex onwards.
+fn overwrite<T: Copy>(values: &mut [T], start: usize, new_values: &[T]) {
+    if let Some(values) = values.get_mut(start..start + new_values.len()) {
+        values.copy_from_slice(new_values);
+    } else {
+        error!("vertex attribute is out of bounds, can not update chunk");
     }
 }
 
@@ -53,15 +92,13 @@ fn auto_spawn(
     tilemap: &mut Tilemap,
     spawn_dimensions: Dimension2,
 ) {
-    let translation = camera_transform.translation - tilemap_transform.translation;
-    let point_x = translation.x / tilemap.tile_width() as f32;
-    let point_y = translation.y / tilemap.tile_height() as f32;
-    let (chunk_x, chunk_y) = tilemap.point_to_chunk_point((point_x as i32, point_y as i32));
+    let point = tilemap.world_to_tile(camera_transform.translation.truncate(), tilemap_transform);
+    let (chunk_x, chunk_y) = tilemap.point_to_chunk_point(point);
     let mut new_spawned: Vec<Point2> = Vec::new();
     let spawn_width = spawn_dimensions.width as i32;
     let spawn_height = spawn_dimensions.height as i32;
-    for y in -spawn_width as i32..spawn_width + 1 {
-        for x in -spawn_height..spawn_height + 1 {
+    for y in -spawn_height..spawn_height + 1 {
+        for x in -spawn_width..spawn_width + 1 {
             let chunk_x = x + chunk_x;
             let chunk_y = y + chunk_y;
             if let Some(width) = tilemap.width() {
@@ -145,3 +182,134 @@ pub(crate) fn chunk_auto_spawn(
         }
     }
 }
+
+/// Transforms a rectangle to world space and returns the opposite corners of
+/// the rectangle that bounds it.
+fn world_corners(transform: &GlobalTransform, min: Vec2, max: Vec2) -> (Vec2, Vec2) {
+    let corners = [
+        Vec2::new(min.x, min.y),
+        Vec2::new(min.x, max.y),
+        Vec2::new(max.x, min.y),
+        Vec2::new(max.x, max.y),
+    ];
+    let mut world_min = Vec2::splat(f32::INFINITY);
+    let mut world_max = Vec2::splat(f32::NEG_INFINITY);
+    for corner in corners.iter() {
+        let corner = transform.mul_vec3(corner.extend(0.0)).truncate();
+        world_min = world_min.min(corner);
+        world_max = world_max.max(corner);
+    }
+    (world_min, world_max)
+}
+
+/// Returns the corners of the area a chunk layer may draw to, from the center
+/// of the chunk in pixels.
+///
+/// Sprites which are larger than their grid cell overhang it away from their
+/// anchor, by as much as the largest sprite of the texture atlas of the layer.
+pub(crate) fn chunk_bounds(
+    tilemap: &Tilemap,
+    z_order: usize,
+    texture_atlas: Option<&TextureAtlas>,
+) -> (Vec2, Vec2) {
+    let chunk_dimensions = tilemap.chunk_dimensions();
+    let tile_dimensions = tilemap.tile_dimensions();
+    let cell = Vec2::new(tile_dimensions.width as f32, tile_dimensions.height as f32);
+    // The first and last two columns and rows, as staggered grids shift every
+    // other one.
+    let last_x = chunk_dimensions.width as i32 - 1;
+    let last_y = chunk_dimensions.height as i32 - 1;
+    let columns = [0, 1.min(last_x), (last_x - 1).max(0), last_x];
+    let rows = [0, 1.min(last_y), (last_y - 1).max(0), last_y];
+    let mut min = Vec2::splat(f32::INFINITY);
+    let mut max = Vec2::splat(f32::NEG_INFINITY);
+    for x in columns.iter() {
+        for y in rows.iter() {
+            let center = tilemap.topology().tile_center(
+                Point2::new(*x, *y),
+                chunk_dimensions,
+                tile_dimensions,
+            );
+            min = min.min(center);
+            max = max.max(center);
+        }
+    }
+
+    let sprite = texture_atlas.map_or(cell, |texture_atlas| {
+        texture_atlas
+            .textures
+            .iter()
+            .fold(cell, |size, rect| size.max(rect.max - rect.min))
+    });
+    let anchor = match tilemap.layers().get(z_order) {
+        Some(Some(layer)) => layer.anchor,
+        _ => TileAnchor::default(),
+    };
+    let [anchor_x, anchor_y] = anchor.to_fraction();
+    let anchor = Vec2::new(anchor_x, anchor_y);
+    let overhang = sprite - cell;
+    (
+        min - cell * 0.5 - anchor * overhang,
+        max + cell * 0.5 + (Vec2::one() - anchor) * overhang,
+    )
+}
+
+/// Returns `true` if the bounds of a chunk layer in world space overlap any of
+/// the views.
+pub(crate) fn is_in_view(min: Vec2, max: Vec2, views: &[(Vec2, Vec2)]) -> bool {
+    views.iter().any(|(view_min, view_max)| {
+        min.x <= view_max.x && max.x >= view_min.x && min.y <= view_max.y && max.y >= view_min.y
+    })
+}
+
+/// Hides the chunk layers of tilemaps with auto culling which are outside of
+/// the view of every orthographic camera, and shows the ones inside.
+pub(crate) fn chunk_visibility(
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    tilemap_query: Query<&Tilemap>,
+    camera_query: Query<(&OrthographicProjection, &GlobalTransform), With<Camera>>,
+    mut chunk_query: Query<(&ChunkTilemap, &ZOrder, &GlobalTransform, &mut Visible)>,
+) {
+    let views: Vec<(Vec2, Vec2)> = camera_query
+        .iter()
+        .map(|(projection, transform)| {
+            world_corners(
+                transform,
+                Vec2::new(projection.left, projection.bottom),
+                Vec2::new(projection.right, projection.top),
+            )
+        })
+        .collect();
+    if views.is_empty() {
+        return;
+    }
+
+    // The bounds of the chunk layers of each tilemap, if it culls them.
+    let mut layer_bounds = HashMap::default();
+    for (chunk_tilemap, z_order, transform, mut visible) in chunk_query.iter_mut() {
+        let bounds = *layer_bounds
+            .entry((chunk_tilemap.0, z_order.0))
+            .or_insert_with(|| {
+                tilemap_query
+                    .get(chunk_tilemap.0)
+                    .ok()
+                    .filter(|tilemap| tilemap.auto_cull())
+                    .map(|tilemap| {
+                        let texture_atlas = tilemap
+                            .layer_texture_atlas(z_order.0)
+                            .and_then(|handle| texture_atlases.get(handle));
+                        chunk_bounds(tilemap, z_order.0, texture_atlas)
+                    })
+            });
+        let (min, max) = if let Some(bounds) = bounds {
+            bounds
+        } else {
+            continue;
+        };
+        let (min, max) = world_corners(transform, min, max);
+        let is_visible = is_in_view(min, max, &views);
+        if visible.is_visible != is_visible {
+            visible.is_visible = is_visible;
+        }
+    }
+}
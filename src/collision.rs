@@ -0,0 +1,1126 @@
+//! Collider shapes built from the tiles of layers.
+//!
+//! Every tile of a layer is solid unless it has a [`TileCollider`] of its
+//! own. The shapes are computed without a physics engine, in pixels relative
+//! to the tilemap, and are built into the colliders of an engine by a
+//! [`ColliderBackend`]. The one of `bevy_rapier2d` is used by the physics
+//! systems to spawn colliders, and the shapes are also available with
+//! [`Tilemap::chunk_colliders`]. Simple games can do without an engine at all
+//! and test boxes against the tiles with [`Tilemap::collide_aabb`].
+//!
+//! By default a tile collider has the shape of its grid cell, which is a
+//! rectangle on square grids, a hexagon on hex grids and a diamond on
+//! isometric grids. Half blocks, slopes and other polygons are set per sprite
+//! index on the [`TilemapLayer`], or per tile with a [`TileCollider`] as tile
+//! data, which takes precedence. Tiles can also be one-way platforms or
+//! sensors, such as ladders and water, which send events when entities enter
+//! and exit them.
+//!
+//! With a [`ColliderMerge`] set on a [`TilemapLayer`], the solid tiles with
+//! the shape of their cell are merged into fewer colliders instead, which are
+//! rebuilt whenever a tile of the chunk changes.
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{collision::ColliderMerge, prelude::*};
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let walls = TilemapLayer {
+//!     kind: LayerKind::Sparse,
+//!     collider_merge: ColliderMerge::Rectangles,
+//!     ..Default::default()
+//! };
+//! let mut tilemap = TilemapBuilder::new()
+//!     .texture_atlas(texture_atlas_handle)
+//!     .tile_dimensions(32, 32)
+//!     .add_layer(walls, 0)
+//!     .finish()
+//!     .unwrap();
+//!
+//! let tiles = (0..4).map(|x| Tile { point: (x, 0), ..Default::default() });
+//! tilemap.insert_tiles(tiles).unwrap();
+//!
+//! // The 4 tiles in a row are a single rectangle.
+//! assert_eq!(tilemap.chunk_colliders((0, 0), 0).unwrap().len(), 1);
+//! ```
+//!
+//! Colliders per sprite index:
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_math::Vec2;
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{
+//!     collision::{ColliderKind, ColliderShape, TileCollider},
+//!     prelude::*,
+//! };
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut layer = TilemapLayer::default();
+//! // Sprite 1 is a half block, 2 is a slope, 3 a platform and 4 a ladder.
+//! layer.tile_colliders.insert(
+//!     1,
+//!     TileCollider::rectangle(Vec2::new(-16.0, -16.0), Vec2::new(16.0, 0.0)),
+//! );
+//! layer.tile_colliders.insert(
+//!     2,
+//!     TileCollider::polygon(vec![
+//!         Vec2::new(-16.0, -16.0),
+//!         Vec2::new(16.0, -16.0),
+//!         Vec2::new(16.0, 16.0),
+//!     ]),
+//! );
+//! layer.tile_colliders.insert(
+//!     3,
+//!     TileCollider { kind: ColliderKind::OneWay, ..Default::default() },
+//! );
+//! layer.tile_colliders.insert(
+//!     4,
+//!     TileCollider { kind: ColliderKind::Sensor, ..Default::default() },
+//! );
+//! let mut tilemap = TilemapBuilder::new()
+//!     .texture_atlas(texture_atlas_handle)
+//!     .tile_dimensions(32, 32)
+//!     .add_layer(layer, 0)
+//!     .finish()
+//!     .unwrap();
+//!
+//! tilemap.insert_tile(Tile { point: (0, 0), sprite_index: 3, ..Default::default() }).unwrap();
+//!
+//! // Only the top of a platform is solid.
+//! let colliders = tilemap.chunk_colliders((0, 0), 0).unwrap();
+//! assert_eq!(colliders[0].kind, ColliderKind::OneWay);
+//! assert_eq!(
+//!     colliders[0].shape,
+//!     ColliderShape::Segments(vec![[Vec2::new(32.0, 32.0), Vec2::new(0.0, 32.0)]])
+//! );
+//! ```
+//!
+//! [`Tilemap::chunk_colliders`]: crate::tilemap::Tilemap::chunk_colliders
+//! [`Tilemap::collide_aabb`]: crate::tilemap::Tilemap::collide_aabb
+//! [`TilemapLayer`]: crate::tilemap::TilemapLayer
+
+use crate::{
+    chunk::{render::GridTopologyExt, Chunk},
+    lib::*,
+    tilemap::{ErrorKind, Tilemap, TilemapLayer, TilemapResult},
+    GridTopology,
+};
+
+/// How the tiles of a layer are merged into colliders.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ColliderMerge {
+    /// Every tile has a collider of its own.
+    None,
+    /// The tiles of a chunk are greedily merged into as few rectangles as
+    /// possible.
+    ///
+    /// On isometric grids the rectangles are along the axes of the grid, so
+    /// they are parallelograms on the screen. The tiles of other grids can not
+    /// be merged into rectangles and keep a collider each.
+    Rectangles,
+    /// The tiles of a chunk are merged into the line segments of their
+    /// outline, which is hollow.
+    Outline,
+}
+
+impl Default for ColliderMerge {
+    fn default() -> ColliderMerge {
+        ColliderMerge::None
+    }
+}
+
+/// How a collider interacts with the bodies touching it.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ColliderKind {
+    /// The tile has no collider.
+    None,
+    /// The collider blocks bodies from every side.
+    Solid,
+    /// Only the upward facing edges of the collider block bodies, and only
+    /// the ones which are not moving up, so they can jump through it from
+    /// below.
+    OneWay,
+    /// The collider does not block bodies, but entities with a
+    /// [`TileSensors`] component get events when they enter and exit it.
+    Sensor,
+}
+
+impl Default for ColliderKind {
+    fn default() -> ColliderKind {
+        ColliderKind::Solid
+    }
+}
+
+/// The collider of a tile.
+///
+/// This is set per sprite index with [`TilemapLayer::tile_colliders`], or
+/// per tile as tile data with [`Tilemap::set_tile_data`].
+///
+/// [`TilemapLayer::tile_colliders`]: crate::tilemap::TilemapLayer::tile_colliders
+/// [`Tilemap::set_tile_data`]: crate::tilemap::Tilemap::set_tile_data
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct TileCollider {
+    /// The vertices of a convex polygon in counter clockwise order, in pixels
+    /// from the center of the tile, or `None` for the shape of the grid cell.
+    pub polygon: Option<Vec<Vec2>>,
+    /// How the collider interacts with bodies.
+    pub kind: ColliderKind,
+}
+
+impl TileCollider {
+    /// Constructs a solid rectangle from its corners in pixels from the center
+    /// of the tile, such as a half block.
+    pub fn rectangle(min: Vec2, max: Vec2) -> TileCollider {
+        TileCollider::polygon(vec![
+            min,
+            Vec2::new(max.x, min.y),
+            max,
+            Vec2::new(min.x, max.y),
+        ])
+    }
+
+    /// Constructs a solid convex polygon from its vertices in counter
+    /// clockwise order, in pixels from the center of the tile, such as a
+    /// slope.
+    pub fn polygon(vertices: Vec<Vec2>) -> TileCollider {
+        TileCollider {
+            polygon: Some(vertices),
+            kind: ColliderKind::Solid,
+        }
+    }
+
+    /// Returns `true` if the tiles with this collider can be merged with
+    /// their neighbours.
+    fn is_mergeable(&self) -> bool {
+        self.polygon.is_none() && self.kind == ColliderKind::Solid
+    }
+}
+
+/// The shape of a collider, in pixels relative to the tilemap.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ColliderShape {
+    /// A convex polygon with its vertices in counter clockwise order.
+    Polygon(Vec<Vec2>),
+    /// Line segments between pairs of vertices.
+    Segments(Vec<[Vec2; 2]>),
+}
+
+impl ColliderShape {
+    /// Returns the minimum and maximum corners of the axis aligned box around
+    /// the shape.
+    ///
+    /// The minimum is larger than the maximum for a shape without vertices.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let mut min = Vec2::new(f32::MAX, f32::MAX);
+        let mut max = Vec2::new(f32::MIN, f32::MIN);
+        let mut extend = |vertex: &Vec2| {
+            min = min.min(*vertex);
+            max = max.max(*vertex);
+        };
+        match self {
+            ColliderShape::Polygon(vertices) => vertices.iter().for_each(&mut extend),
+            ColliderShape::Segments(segments) => segments
+                .iter()
+                .flat_map(|segment| segment.iter())
+                .for_each(&mut extend),
+        }
+        (min, max)
+    }
+
+    /// Returns `true` if the shape overlaps the inside of an axis aligned box,
+    /// given by its minimum and maximum corners.
+    ///
+    /// Shapes which only touch the edges of the box do not overlap it, so
+    /// that a box resting on a tile or next to a wall is free.
+    pub fn intersects_aabb(&self, min: Vec2, max: Vec2) -> bool {
+        match self {
+            ColliderShape::Polygon(vertices) => polygon_intersects_aabb(vertices, min, max),
+            ColliderShape::Segments(segments) => segments
+                .iter()
+                .any(|[start, end]| segment_intersects_aabb(*start, *end, min, max)),
+        }
+    }
+}
+
+/// Returns `true` if a convex polygon overlaps the inside of a box, which is
+/// when none of the axes of the box and the edge normals of the polygon
+/// separate them.
+fn polygon_intersects_aabb(vertices: &[Vec2], min: Vec2, max: Vec2) -> bool {
+    if vertices.is_empty() {
+        return false;
+    }
+    let center = (min + max) / 2.0;
+    let half_extents = (max - min) / 2.0;
+    let separates = |axis: Vec2| {
+        if axis.x == 0.0 && axis.y == 0.0 {
+            return false;
+        }
+        let mut low = f32::MAX;
+        let mut high = f32::MIN;
+        for vertex in vertices {
+            let distance = axis.dot(*vertex);
+            low = low.min(distance);
+            high = high.max(distance);
+        }
+        let middle = axis.dot(center);
+        let radius = half_extents.x * axis.x.abs() + half_extents.y * axis.y.abs();
+        high <= middle - radius || low >= middle + radius
+    };
+    if separates(Vec2::new(1.0, 0.0)) || separates(Vec2::new(0.0, 1.0)) {
+        return false;
+    }
+    (0..vertices.len()).all(|i| {
+        let edge = vertices[(i + 1) % vertices.len()] - vertices[i];
+        !separates(Vec2::new(-edge.y, edge.x))
+    })
+}
+
+/// Returns `true` if a line segment passes through the inside of a box, by
+/// clipping it to the slabs of the box.
+fn segment_intersects_aabb(start: Vec2, end: Vec2, min: Vec2, max: Vec2) -> bool {
+    let direction = end - start;
+    let mut enter = 0.0f32;
+    let mut exit = 1.0f32;
+    for &(origin, delta, low, high) in &[
+        (start.x, direction.x, min.x, max.x),
+        (start.y, direction.y, min.y, max.y),
+    ] {
+        if delta == 0.0 {
+            if origin <= low || origin >= high {
+                return false;
+            }
+        } else {
+            let near = (low - origin) / delta;
+            let far = (high - origin) / delta;
+            enter = enter.max(near.min(far));
+            exit = exit.min(near.max(far));
+        }
+    }
+    enter < exit
+}
+
+/// A collider of a chunk layer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChunkCollider {
+    /// The shape of the collider.
+    pub shape: ColliderShape,
+    /// How the collider interacts with bodies.
+    pub kind: ColliderKind,
+}
+
+/// A physics engine which the colliders of chunks are built for.
+///
+/// The colliders of a chunk layer are generated as engine-neutral
+/// [`ChunkCollider`]s, which an implementation turns into colliders of its
+/// engine. [`RapierBackend`] is the one used by the plugin with the
+/// `bevy_rapier2d` feature, others can be driven by the
+/// [`TilemapCollisionEvent`]s of the tilemap and the
+/// [`TilemapChunkLayerEvent`]s of its chunks.
+///
+/// [`TilemapCollisionEvent`]: crate::event::TilemapCollisionEvent
+/// [`TilemapChunkLayerEvent`]: crate::event::TilemapChunkLayerEvent
+///
+/// # Examples
+/// ```
+/// use bevy_asset::{prelude::*, HandleId};
+/// use bevy_math::Vec2;
+/// use bevy_sprite::prelude::*;
+/// use bevy_tilemap::{
+///     collision::{ChunkCollider, ColliderBackend, ColliderMerge},
+///     prelude::*,
+/// };
+///
+/// /// An engine which only knows about boxes.
+/// struct Boxes;
+///
+/// impl ColliderBackend for Boxes {
+///     type Collider = (Vec2, Vec2);
+///
+///     fn build_collider(&self, collider: &ChunkCollider) -> Option<(Vec2, Vec2)> {
+///         Some(collider.shape.bounds())
+///     }
+/// }
+///
+/// // In production use a strong handle from an actual source.
+/// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+///
+/// let walls = TilemapLayer {
+///     collider_merge: ColliderMerge::Rectangles,
+///     ..Default::default()
+/// };
+/// let mut tilemap = TilemapBuilder::new()
+///     .texture_atlas(texture_atlas_handle)
+///     .tile_dimensions(32, 32)
+///     .add_layer(walls, 0)
+///     .finish()
+///     .unwrap();
+///
+/// let tiles = (0..4).map(|x| Tile { point: (x, 0), ..Default::default() });
+/// tilemap.insert_tiles(tiles).unwrap();
+///
+/// assert_eq!(
+///     Boxes.chunk_colliders(&tilemap, (0, 0), 0).unwrap(),
+///     vec![(Vec2::new(0.0, 0.0), Vec2::new(128.0, 32.0))]
+/// );
+/// ```
+pub trait ColliderBackend {
+    /// The collider of the engine.
+    type Collider;
+
+    /// Builds a collider of the engine from a collider in pixels relative to
+    /// the tilemap, or returns `None` if the engine can not represent it.
+    fn build_collider(&self, collider: &ChunkCollider) -> Option<Self::Collider>;
+
+    /// Builds the colliders of a chunk layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or the chunk does not exist.
+    fn chunk_colliders<P: Into<Point2>>(
+        &self,
+        tilemap: &Tilemap,
+        point: P,
+        z_order: usize,
+    ) -> TilemapResult<Vec<Self::Collider>> {
+        Ok(tilemap
+            .chunk_colliders(point, z_order)?
+            .iter()
+            .filter_map(|collider| self.build_collider(collider))
+            .collect())
+    }
+}
+
+/// The colliders of `bevy_rapier2d`, in physics units.
+///
+/// Polygons are convex hulls and segments are polylines. One-way platforms
+/// are marked for [`OneWayPlatforms`] and sensor tiles are sensors.
+#[cfg(feature = "bevy_rapier2d")]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RapierBackend {
+    /// The pixels per physics unit, see [`Tilemap::physics_scale`].
+    pub physics_scale: f32,
+}
+
+#[cfg(feature = "bevy_rapier2d")]
+impl Default for RapierBackend {
+    fn default() -> RapierBackend {
+        RapierBackend { physics_scale: 1.0 }
+    }
+}
+
+#[cfg(feature = "bevy_rapier2d")]
+impl ColliderBackend for RapierBackend {
+    type Collider = ColliderBuilder;
+
+    fn build_collider(&self, collider: &ChunkCollider) -> Option<ColliderBuilder> {
+        let to_physics =
+            |point: &Vec2| Point::new(point.x / self.physics_scale, point.y / self.physics_scale);
+        let builder = match &collider.shape {
+            ColliderShape::Polygon(corners) => {
+                let points: Vec<Point<f32>> = corners.iter().map(to_physics).collect();
+                ColliderBuilder::convex_hull(&points)?
+            }
+            ColliderShape::Segments(segments) => {
+                let mut vertices = Vec::with_capacity(segments.len() * 2);
+                let mut indices = Vec::with_capacity(segments.len());
+                for [start, end] in segments.iter() {
+                    let first = vertices.len() as u32;
+                    vertices.push(to_physics(start));
+                    vertices.push(to_physics(end));
+                    indices.push([first, first + 1]);
+                }
+                ColliderBuilder::polyline(vertices, Some(indices))
+            }
+        };
+        Some(match collider.kind {
+            ColliderKind::OneWay => builder.user_data(ONE_WAY_USER_DATA),
+            ColliderKind::Sensor => builder.sensor(true),
+            ColliderKind::None | ColliderKind::Solid => builder,
+        })
+    }
+}
+
+/// Tracks the sensor tiles an entity is on, to send
+/// [`TilemapCollisionEvent`]s when it enters and exits them.
+///
+/// The entity is on the tiles under the translation of its `GlobalTransform`,
+/// on every layer of every tilemap.
+///
+/// [`TilemapCollisionEvent`]: crate::event::TilemapCollisionEvent
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct TileSensors {
+    /// The tilemap entity, point and z order of the sensor tiles the entity
+    /// is on.
+    pub(crate) tiles: Vec<(Entity, Point2, usize)>,
+}
+
+impl TileSensors {
+    /// Returns the tilemap entity, point and z order of the sensor tiles the
+    /// entity is on.
+    pub fn tiles(&self) -> &[(Entity, Point2, usize)] {
+        &self.tiles
+    }
+}
+
+/// The user data of the colliders of one-way platforms.
+#[cfg(feature = "bevy_rapier2d")]
+const ONE_WAY_USER_DATA: u128 = 0x6f6e_655f_7761_79;
+
+/// A contact pair filter which lets bodies moving up pass through one-way
+/// platforms.
+///
+/// Rapier resolves contacts on both sides of a collider, so this filter must
+/// be set as the contact filter of the `InteractionPairFilters` resource of
+/// `bevy_rapier2d` for one-way platforms to work.
+#[cfg(feature = "bevy_rapier2d")]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct OneWayPlatforms;
+
+#[cfg(feature = "bevy_rapier2d")]
+impl ContactPairFilter for OneWayPlatforms {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        let body = if context.collider1.user_data == ONE_WAY_USER_DATA {
+            context.rigid_body2
+        } else if context.collider2.user_data == ONE_WAY_USER_DATA {
+            context.rigid_body1
+        } else {
+            return Some(SolverFlags::COMPUTE_IMPULSES);
+        };
+        if body.linvel().y > 0.0 {
+            None
+        } else {
+            Some(SolverFlags::COMPUTE_IMPULSES)
+        }
+    }
+}
+
+impl Tilemap {
+    /// Returns the grid a chunk is laid out on, in pixels relative to the
+    /// tilemap.
+    pub(crate) fn chunk_grid(&self, point: Point2) -> ChunkGrid {
+        ChunkGrid {
+            topology: self.topology(),
+            point,
+            chunk_dimensions: self.chunk_dimensions(),
+            tile_dimensions: self.tile_dimensions(),
+        }
+    }
+
+    /// Returns the collider of the tile at a point, if there is a tile.
+    ///
+    /// This is the [`TileCollider`] of the tile data of the tile, or else the
+    /// one of its sprite index on the layer, or else a solid collider in the
+    /// shape of the grid cell.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{
+    ///     collision::{ColliderKind, TileCollider},
+    ///     prelude::*,
+    /// };
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_tile(Tile { point: (0, 0), ..Default::default() }).unwrap();
+    /// tilemap.insert_tile(Tile { point: (1, 0), ..Default::default() }).unwrap();
+    ///
+    /// let water = TileCollider { kind: ColliderKind::Sensor, ..Default::default() };
+    /// tilemap.set_tile_data((1, 0), 0, water.clone()).unwrap();
+    ///
+    /// assert_eq!(tilemap.tile_collider((0, 0), 0), Some(TileCollider::default()));
+    /// assert_eq!(tilemap.tile_collider((1, 0), 0), Some(water));
+    /// assert_eq!(tilemap.tile_collider((2, 0), 0), None);
+    /// ```
+    pub fn tile_collider<P: Into<Point2>>(&self, point: P, z_order: usize) -> Option<TileCollider> {
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+        let layer = self.layers().get(z_order)?.as_ref()?;
+        let chunk = self.chunks().get(&chunk_point)?;
+        Self::resolve_tile_collider(layer, chunk, z_order, index)
+    }
+
+    /// Returns the collider of a tile in a chunk, if there is a tile.
+    fn resolve_tile_collider(
+        layer: &TilemapLayer,
+        chunk: &Chunk,
+        z_order: usize,
+        index: usize,
+    ) -> Option<TileCollider> {
+        let tile = chunk.get_tile(z_order, index)?;
+        if let Some(collider) = chunk.tile_data().get::<TileCollider>(z_order, index) {
+            return Some(collider.clone());
+        }
+        Some(
+            layer
+                .tile_colliders
+                .get(&tile.index)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns the collider of a tile of a chunk layer, if there is a tile.
+    pub(crate) fn chunk_tile_collider(
+        &self,
+        point: Point2,
+        z_order: usize,
+        index: usize,
+    ) -> Option<TileCollider> {
+        let layer = self.layers().get(z_order)?.as_ref()?;
+        let chunk = self.chunks().get(&point)?;
+        Self::resolve_tile_collider(layer, chunk, z_order, index)
+    }
+
+    /// Returns the colliders of every tile of a chunk layer, by index.
+    pub(crate) fn chunk_tile_colliders(
+        &self,
+        point: Point2,
+        z_order: usize,
+    ) -> Vec<(usize, TileCollider)> {
+        let (layer, chunk) = match (
+            self.layers().get(z_order).and_then(|layer| layer.as_ref()),
+            self.chunks().get(&point),
+        ) {
+            (Some(layer), Some(chunk)) => (layer, chunk),
+            _ => return Vec::new(),
+        };
+        chunk
+            .get_tile_indices(z_order)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|index| {
+                Self::resolve_tile_collider(layer, chunk, z_order, index)
+                    .map(|collider| (index, collider))
+            })
+            .collect()
+    }
+
+    /// Computes the colliders of a chunk layer, without a physics engine.
+    ///
+    /// The solid tiles in the shape of their cell are merged as set by the
+    /// [`ColliderMerge`] of the layer and the shapes are in pixels relative to
+    /// the tilemap, like [`tile_to_world`].
+    ///
+    /// [`tile_to_world`]: Tilemap::tile_to_world
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or the chunk does not exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{collision::{ColliderKind, ColliderShape}, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_tile(Tile { point: (0, 0), ..Default::default() }).unwrap();
+    ///
+    /// let colliders = tilemap.chunk_colliders((0, 0), 0).unwrap();
+    ///
+    /// assert_eq!(colliders.len(), 1);
+    /// assert_eq!(colliders[0].kind, ColliderKind::Solid);
+    /// assert!(matches!(&colliders[0].shape, ColliderShape::Polygon(corners) if corners.len() == 4));
+    /// assert!(tilemap.chunk_colliders((0, 0), 1).is_err());
+    /// ```
+    pub fn chunk_colliders<P: Into<Point2>>(
+        &self,
+        point: P,
+        z_order: usize,
+    ) -> TilemapResult<Vec<ChunkCollider>> {
+        let merge = match self.layers().get(z_order).and_then(|layer| layer.as_ref()) {
+            Some(layer) => layer.collider_merge,
+            None => return Err(ErrorKind::LayerDoesNotExist(z_order).into()),
+        };
+        let point: Point2 = point.into();
+        if !self.chunks().contains_key(&point) {
+            return Err(ErrorKind::MissingChunk.into());
+        }
+        let tiles = self.chunk_tile_colliders(point, z_order);
+        Ok(self.chunk_grid(point).merged_colliders(merge, &tiles))
+    }
+
+    /// Returns the points of the tiles which may overlap an axis aligned box,
+    /// in pixels relative to the tilemap.
+    pub(crate) fn points_in_area(&self, min: Vec2, max: Vec2) -> Vec<Point2> {
+        let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+        let mut low = self.position_to_tile(min);
+        let mut high = low;
+        for corner in corners.iter() {
+            let point = self.position_to_tile(*corner);
+            low = Point2::new(low.x.min(point.x), low.y.min(point.y));
+            high = Point2::new(high.x.max(point.x), high.y.max(point.y));
+        }
+
+        // The tiles of every grid are laid out close enough to a linear grid
+        // that the tiles in the box are within a tile of its corners.
+        let mut points = Vec::new();
+        for y in low.y - 1..=high.y + 1 {
+            for x in low.x - 1..=high.x + 1 {
+                points.push(Point2::new(x, y));
+            }
+        }
+        points
+    }
+
+    /// Returns the point and z order of every tile with a solid collider that
+    /// overlaps an axis aligned box, without a physics engine.
+    ///
+    /// The box is given by its minimum and maximum corners in pixels relative
+    /// to the tilemap, like [`tile_to_world`], and is tested against the
+    /// actual shape of the colliders, such as half blocks and slopes.
+    /// Colliders which only touch the box do not overlap it, and one-way
+    /// platforms and sensors are left out.
+    ///
+    /// This is enough for simple games to move boxes around the tilemap
+    /// without pulling in a physics engine.
+    ///
+    /// [`tile_to_world`]: Tilemap::tile_to_world
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_math::Vec2;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    /// use bevy_tilemap_types::point::Point2;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_tile(Tile { point: (0, 0), ..Default::default() }).unwrap();
+    /// tilemap.insert_tile(Tile { point: (1, 0), ..Default::default() }).unwrap();
+    ///
+    /// // The floor is from 0 to 32 pixels high.
+    /// let player = (Vec2::new(16.0, 20.0), Vec2::new(40.0, 60.0));
+    /// assert_eq!(
+    ///     tilemap.collide_aabb(player.0, player.1),
+    ///     vec![(Point2::new(0, 0), 0), (Point2::new(1, 0), 0)]
+    /// );
+    ///
+    /// let standing = (Vec2::new(16.0, 32.0), Vec2::new(40.0, 72.0));
+    /// assert!(tilemap.collide_aabb(standing.0, standing.1).is_empty());
+    /// ```
+    pub fn collide_aabb(&self, min: Vec2, max: Vec2) -> Vec<(Point2, usize)> {
+        let mut collisions = Vec::new();
+        for point in self.points_in_area(min, max) {
+            let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+            let tile_point = self.point_to_tile_point(point);
+            let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+            let grid = self.chunk_grid(chunk_point);
+            for z_order in 0..self.layer_count() {
+                let collider = match self.chunk_tile_collider(chunk_point, z_order, index) {
+                    Some(collider) if collider.kind == ColliderKind::Solid => collider,
+                    _ => continue,
+                };
+                if let Some(collider) = grid.tile_collider(index, &collider) {
+                    if collider.shape.intersects_aabb(min, max) {
+                        collisions.push((point, z_order));
+                    }
+                }
+            }
+        }
+        collisions
+    }
+}
+
+/// The grid a chunk of tiles is laid out on, in pixels relative to the
+/// tilemap.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ChunkGrid {
+    /// The topology of the grid.
+    pub topology: GridTopology,
+    /// The point of the chunk.
+    pub point: Point2,
+    /// The dimensions of the chunk in tiles.
+    pub chunk_dimensions: Dimension2,
+    /// The dimensions of a tile in pixels.
+    pub tile_dimensions: Dimension2,
+}
+
+impl ChunkGrid {
+    /// Returns the center of a tile of the chunk.
+    fn tile_center(&self, tile_point: Point2) -> Vec2 {
+        self.topology
+            .chunk_translation(self.point, self.chunk_dimensions, self.tile_dimensions)
+            + self
+                .topology
+                .tile_center(tile_point, self.chunk_dimensions, self.tile_dimensions)
+    }
+
+    /// Returns the tile point of an index in the chunk.
+    fn tile_point(&self, index: usize) -> Point2 {
+        let width = self.chunk_dimensions.width as usize;
+        Point2::new((index % width) as i32, (index / width) as i32)
+    }
+
+    /// Returns the polygon of the cell of a tile.
+    fn cell_polygon(&self, index: usize) -> Vec<Vec2> {
+        let center = self.tile_center(self.tile_point(index));
+        self.topology
+            .tile_polygon(self.tile_dimensions)
+            .into_iter()
+            .map(|corner| center + corner)
+            .collect()
+    }
+
+    /// Returns the collider of a tile, if it has one.
+    pub(crate) fn tile_collider(
+        &self,
+        index: usize,
+        collider: &TileCollider,
+    ) -> Option<ChunkCollider> {
+        let polygon = match &collider.polygon {
+            Some(polygon) => {
+                let center = self.tile_center(self.tile_point(index));
+                polygon.iter().map(|vertex| center + *vertex).collect()
+            }
+            None => self.cell_polygon(index),
+        };
+        let shape = match collider.kind {
+            ColliderKind::None => return None,
+            ColliderKind::Solid | ColliderKind::Sensor => ColliderShape::Polygon(polygon),
+            // The outward normal of an edge points up when it runs from right
+            // to left.
+            ColliderKind::OneWay => ColliderShape::Segments(
+                (0..polygon.len())
+                    .map(|i| [polygon[i], polygon[(i + 1) % polygon.len()]])
+                    .filter(|[start, end]| end.x < start.x)
+                    .collect(),
+            ),
+        };
+        Some(ChunkCollider {
+            shape,
+            kind: collider.kind,
+        })
+    }
+
+    /// Returns the colliders of the tiles at the indices, with the solid
+    /// tiles in the shape of their cell merged together.
+    ///
+    /// With `ColliderMerge::None` there is one collider per tile, in the same
+    /// order as the tiles.
+    pub(crate) fn merged_colliders(
+        &self,
+        merge: ColliderMerge,
+        tiles: &[(usize, TileCollider)],
+    ) -> Vec<ChunkCollider> {
+        let mut indices = Vec::new();
+        let mut colliders = Vec::new();
+        for (index, collider) in tiles {
+            if merge != ColliderMerge::None && collider.is_mergeable() {
+                indices.push(*index);
+            } else if let Some(collider) = self.tile_collider(*index, collider) {
+                colliders.push(collider);
+            }
+        }
+        let shapes = match merge {
+            ColliderMerge::Rectangles
+                if self.topology == GridTopology::Square
+                    || self.topology == GridTopology::Isometric =>
+            {
+                self.rectangles(&indices)
+            }
+            ColliderMerge::Outline => {
+                let segments = self.outline(&indices);
+                if segments.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![ColliderShape::Segments(segments)]
+                }
+            }
+            _ => indices
+                .iter()
+                .map(|index| ColliderShape::Polygon(self.cell_polygon(*index)))
+                .collect(),
+        };
+        colliders.extend(shapes.into_iter().map(|shape| ChunkCollider {
+            shape,
+            kind: ColliderKind::Solid,
+        }));
+        colliders
+    }
+
+    /// Greedily merges the tiles into rectangles along the axes of the grid.
+    fn rectangles(&self, indices: &[usize]) -> Vec<ColliderShape> {
+        let width = self.chunk_dimensions.width as usize;
+        let height = self.chunk_dimensions.height as usize;
+        let mut solid = vec![false; width * height];
+        for index in indices {
+            if let Some(tile) = solid.get_mut(*index) {
+                *tile = true;
+            }
+        }
+
+        // Tiles are linear on these grids, so a rectangle of tiles is mapped
+        // to the screen from the axes of a single tile.
+        let origin = self.tile_center(Point2::new(0, 0));
+        let x_axis = self.tile_center(Point2::new(1, 0)) - origin;
+        let y_axis = self.tile_center(Point2::new(0, 1)) - origin;
+        let corner = |x: f32, y: f32| origin + x_axis * (x - 0.5) + y_axis * (y - 0.5);
+
+        let mut shapes = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if !solid[y * width + x] {
+                    continue;
+                }
+                let mut rect_width = 1;
+                while x + rect_width < width && solid[y * width + x + rect_width] {
+                    rect_width += 1;
+                }
+                let mut rect_height = 1;
+                while y + rect_height < height
+                    && (x..x + rect_width).all(|column| solid[(y + rect_height) * width + column])
+                {
+                    rect_height += 1;
+                }
+                for row in y..y + rect_height {
+                    for tile in &mut solid[row * width + x..row * width + x + rect_width] {
+                        *tile = false;
+                    }
+                }
+
+                let (x0, y0) = (x as f32, y as f32);
+                let (x1, y1) = ((x + rect_width) as f32, (y + rect_height) as f32);
+                shapes.push(ColliderShape::Polygon(vec![
+                    corner(x0, y0),
+                    corner(x1, y0),
+                    corner(x1, y1),
+                    corner(x0, y1),
+                ]));
+            }
+        }
+        shapes
+    }
+
+    /// Returns the edges of the tile cells which are not shared with another
+    /// tile.
+    fn outline(&self, indices: &[usize]) -> Vec<[Vec2; 2]> {
+        // Shared edges are found by their end points, rounded to a hundredth
+        // of a pixel so that both tiles agree on them.
+        let key = |point: Vec2| {
+            (
+                (point.x * 100.0).round() as i64,
+                (point.y * 100.0).round() as i64,
+            )
+        };
+        let mut edges = Vec::new();
+        let mut counts: HashMap<_, usize> = HashMap::default();
+        for index in indices {
+            let polygon = self.cell_polygon(*index);
+            for (i, start) in polygon.iter().enumerate() {
+                let end = polygon[(i + 1) % polygon.len()];
+                let (a, b) = (key(*start), key(end));
+                let edge_key = if a < b { (a, b) } else { (b, a) };
+                *counts.entry(edge_key).or_insert(0) += 1;
+                edges.push((edge_key, [*start, end]));
+            }
+        }
+        edges
+            .into_iter()
+            .filter(|(edge_key, _)| counts.get(edge_key) == Some(&1))
+            .map(|(_, edge)| edge)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk::LayerKind, tile::Tile, tilemap::test_builder};
+
+    /// Returns the collider shapes of the tiles at the points, which all are
+    /// in the chunk at the origin.
+    fn colliders(
+        topology: GridTopology,
+        merge: ColliderMerge,
+        points: &[(i32, i32, usize)],
+    ) -> Vec<ColliderShape> {
+        let mut layer = TilemapLayer {
+            kind: LayerKind::Sparse,
+            collider_merge: merge,
+            ..Default::default()
+        };
+        // Sprite 1 is a half block and sprite 2 has no collider.
+        layer.tile_colliders.insert(
+            1,
+            TileCollider::rectangle(Vec2::new(-16.0, -16.0), Vec2::new(16.0, 0.0)),
+        );
+        layer.tile_colliders.insert(
+            2,
+            TileCollider {
+                kind: ColliderKind::None,
+                ..Default::default()
+            },
+        );
+        let mut tilemap = test_builder()
+            .topology(topology)
+            .add_layer(layer, 0)
+            .finish()
+            .unwrap();
+        let tiles = points.iter().map(|(x, y, sprite_index)| Tile {
+            point: (*x, *y),
+            sprite_index: *sprite_index,
+            ..Default::default()
+        });
+        tilemap.insert_tiles(tiles).unwrap();
+        tilemap
+            .chunk_colliders((0, 0), 0)
+            .unwrap()
+            .into_iter()
+            .map(|collider| collider.shape)
+            .collect()
+    }
+
+    #[test]
+    fn colliders_per_tile() {
+        let points = [(0, 0, 0), (1, 0, 0)];
+        for topology in [
+            GridTopology::Square,
+            GridTopology::HexY,
+            GridTopology::HexOddCols,
+        ]
+        .iter()
+        {
+            let shapes = colliders(*topology, ColliderMerge::None, &points);
+            assert_eq!(shapes.len(), 2, "{:?}", topology);
+            let corners = if *topology == GridTopology::Square {
+                4
+            } else {
+                6
+            };
+            for shape in shapes {
+                match shape {
+                    ColliderShape::Polygon(polygon) => assert_eq!(polygon.len(), corners),
+                    ColliderShape::Segments(_) => panic!("{:?} has segments", topology),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn colliders_merge_rectangles() {
+        // An L shape is a row of 3 tiles and a column of 2 tiles above it.
+        let points = [(0, 0, 0), (1, 0, 0), (2, 0, 0), (0, 1, 0), (0, 2, 0)];
+        let shapes = colliders(GridTopology::Square, ColliderMerge::Rectangles, &points);
+        assert_eq!(shapes.len(), 2);
+        assert!(shapes.contains(&ColliderShape::Polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(96.0, 0.0),
+            Vec2::new(96.0, 32.0),
+            Vec2::new(0.0, 32.0),
+        ])));
+
+        let shapes = colliders(GridTopology::Isometric, ColliderMerge::Rectangles, &points);
+        assert_eq!(shapes.len(), 2);
+        // Hex tiles can not be merged into rectangles.
+        let shapes = colliders(GridTopology::HexX, ColliderMerge::Rectangles, &points);
+        assert_eq!(shapes.len(), points.len());
+    }
+
+    #[test]
+    fn colliders_merge_outline() {
+        let points = [(0, 0, 0), (1, 0, 0)];
+        for (topology, edges) in [(GridTopology::Square, 6), (GridTopology::HexY, 10)].iter() {
+            let shapes = colliders(*topology, ColliderMerge::Outline, &points);
+            match shapes.as_slice() {
+                [ColliderShape::Segments(segments)] => {
+                    assert_eq!(segments.len(), *edges, "{:?}", topology)
+                }
+                _ => panic!("{:?} is not a single outline", topology),
+            }
+        }
+    }
+
+    #[test]
+    fn colliders_per_sprite() {
+        // The half block and the tile without a collider are not merged.
+        let points = [(0, 0, 0), (1, 0, 1), (2, 0, 0), (3, 0, 2)];
+        let shapes = colliders(GridTopology::Square, ColliderMerge::Rectangles, &points);
+        assert_eq!(shapes.len(), 3);
+        assert!(shapes.contains(&ColliderShape::Polygon(vec![
+            Vec2::new(32.0, 0.0),
+            Vec2::new(64.0, 0.0),
+            Vec2::new(64.0, 16.0),
+            Vec2::new(32.0, 16.0),
+        ])));
+    }
+
+    #[test]
+    fn collide_aabb_shapes() {
+        let mut layer = TilemapLayer::default();
+        // Sprite 1 is a half block and sprite 2 is water.
+        layer.tile_colliders.insert(
+            1,
+            TileCollider::rectangle(Vec2::new(-16.0, -16.0), Vec2::new(16.0, 0.0)),
+        );
+        layer.tile_colliders.insert(
+            2,
+            TileCollider {
+                kind: ColliderKind::Sensor,
+                ..Default::default()
+            },
+        );
+        let mut tilemap = test_builder().add_layer(layer, 0).finish().unwrap();
+        let tiles = [(0, 0, 1), (1, 0, 2), (2, 0, 0)]
+            .iter()
+            .map(|(x, y, sprite_index)| Tile {
+                point: (*x, *y),
+                sprite_index: *sprite_index,
+                ..Default::default()
+            });
+        tilemap.insert_tiles(tiles).unwrap();
+
+        // The half block is from 0 to 16 pixels high.
+        let above = tilemap.collide_aabb(Vec2::new(4.0, 20.0), Vec2::new(28.0, 30.0));
+        assert!(above.is_empty());
+        let inside = tilemap.collide_aabb(Vec2::new(4.0, 10.0), Vec2::new(28.0, 30.0));
+        assert_eq!(inside, vec![(Point2::new(0, 0), 0)]);
+        // The water is not solid.
+        let row = tilemap.collide_aabb(Vec2::new(0.0, 20.0), Vec2::new(96.0, 30.0));
+        assert_eq!(row, vec![(Point2::new(2, 0), 0)]);
+    }
+
+    #[test]
+    fn collide_aabb_hex() {
+        let mut tilemap = test_builder()
+            .topology(GridTopology::HexY)
+            .finish()
+            .unwrap();
+        tilemap
+            .insert_tile(Tile {
+                point: (0, 0),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let nudge = Vec2::new(2.0, 2.0);
+        let center = tilemap.tile_to_world((0, 0));
+        let hits = tilemap.collide_aabb(center - nudge, center + nudge);
+        assert_eq!(hits, vec![(Point2::new(0, 0), 0)]);
+        let center = tilemap.tile_to_world((3, 0));
+        assert!(tilemap
+            .collide_aabb(center - nudge, center + nudge)
+            .is_empty());
+    }
+}
@@ -1,7 +1,6 @@
 //! The tilemap events.
 
 use crate::lib::*;
-#[cfg(feature = "bevy_rapier2d")]
 use crate::Tile;
 
 #[derive(Debug)]
@@ -24,9 +23,67 @@ pub enum TilemapChunkEvent {
         /// The point of the chunk to despawn.
         point: Point2,
     },
+    /// An event when the entity of a single layer of a chunk needs to be
+    /// despawned, as the layer was removed.
+    LayerDespawned {
+        /// The chunk layer entity to despawn.
+        entity: Entity,
+        /// The point of the chunk.
+        point: Point2,
+        /// The z order of the removed layer.
+        z_order: usize,
+    },
+}
+
+/// Sent when the entity of a chunk layer is spawned or despawned.
+///
+/// Use these to attach your own components to chunk layer entities when they
+/// are spawned, and to clean up anything kept elsewhere when they are
+/// despawned. The components and children of a despawned entity are removed
+/// with it.
+///
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_tilemap::event::TilemapChunkLayerEvent;
+///
+/// fn chunk_layers(
+///     mut reader: Local<EventReader<TilemapChunkLayerEvent>>,
+///     events: Res<Events<TilemapChunkLayerEvent>>,
+/// ) {
+///     for event in reader.iter(&events) {
+///         if let TilemapChunkLayerEvent::Spawned { entity, .. } = event {
+///             // Insert components on `entity`.
+///         }
+///     }
+/// }
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TilemapChunkLayerEvent {
+    /// An event when the entity of a chunk layer has been spawned.
+    Spawned {
+        /// The chunk layer entity.
+        entity: Entity,
+        /// The tilemap entity which owns the chunk.
+        tilemap: Entity,
+        /// The point of the chunk.
+        point: Point2,
+        /// The z order of the layer.
+        z_order: usize,
+    },
+    /// An event when the entity of a chunk layer has been despawned.
+    Despawned {
+        /// The chunk layer entity.
+        entity: Entity,
+        /// The tilemap entity which owns the chunk.
+        tilemap: Entity,
+        /// The point of the chunk.
+        point: Point2,
+        /// The z order of the layer.
+        z_order: usize,
+    },
 }
 
-#[cfg(feature = "bevy_rapier2d")]
 #[derive(Debug)]
 /// Events that can happen to collisions.
 pub enum TilemapCollisionEvent {
@@ -44,4 +101,22 @@ pub enum TilemapCollisionEvent {
         /// The point in the chunk that needs a collision spawned.
         tiles: Vec<Tile<Point2>>,
     },
+    /// An event when an entity with `TileSensors` entered a sensor tile.
+    SensorEntered {
+        /// The entity which entered the tile.
+        entity: Entity,
+        /// The point of the tile.
+        point: Point2,
+        /// The z order of the tile.
+        z_order: usize,
+    },
+    /// An event when an entity with `TileSensors` exited a sensor tile.
+    SensorExited {
+        /// The entity which exited the tile.
+        entity: Entity,
+        /// The point of the tile.
+        point: Point2,
+        /// The z order of the tile.
+        z_order: usize,
+    },
 }
@@ -0,0 +1,328 @@
+//! Fog of war which is kept per tile and rendered as an overlay.
+//!
+//! A layer added with [`LayerKind::Fog`] has no tiles of its own. Instead it
+//! stores a [`FogState`] for every tile of each chunk, which is part of the
+//! chunk and therefore survives it being despawned and spawned again. Tiles
+//! start out unexplored.
+//!
+//! The layer is rendered with the [`FogStyle`] of the tilemap, which is either
+//! a darkening tint over a plain sprite or a dedicated sprite for each state.
+//! Visible tiles are not covered at all. Fog layers should have a higher z
+//! order than the layers they cover.
+//!
+//! Fog states are updated in bulk with [`Tilemap::set_fog_states`] or from a
+//! [`FieldOfView`] with [`Tilemap::reveal_fog`]. Only chunks with a changed
+//! state have their meshes rebuilt.
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{fog::FogState, fov::FieldOfView, prelude::*};
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = TilemapBuilder::new()
+//!     .texture_atlas(texture_atlas_handle)
+//!     .tile_dimensions(32, 32)
+//!     .add_layer(TilemapLayer { kind: LayerKind::Dense, ..Default::default() }, 0)
+//!     .add_layer(TilemapLayer { kind: LayerKind::Fog, ..Default::default() }, 1)
+//!     .finish()
+//!     .unwrap();
+//! tilemap.insert_chunk((0, 0)).unwrap();
+//!
+//! let mut fov = FieldOfView::default();
+//! fov.update(tilemap.field_of_view((0, 0), 2, 0, |_, _| false));
+//! fov.update(tilemap.field_of_view((4, 0), 2, 0, |_, _| false));
+//! tilemap.reveal_fog(&fov, 1).unwrap();
+//!
+//! assert_eq!(tilemap.fog_state((4, 0), 1), Some(FogState::Visible));
+//! assert_eq!(tilemap.fog_state((0, 0), 1), Some(FogState::Explored));
+//! assert_eq!(tilemap.fog_state((0, 8), 1), Some(FogState::Unexplored));
+//! ```
+//!
+//! [`LayerKind::Fog`]: crate::chunk::LayerKind::Fog
+//! [`Tilemap::set_fog_states`]: crate::tilemap::Tilemap::set_fog_states
+//! [`Tilemap::reveal_fog`]: crate::tilemap::Tilemap::reveal_fog
+//! [`FieldOfView`]: crate::fov::FieldOfView
+
+use crate::{
+    chunk::{LayerKind, RawTile},
+    fov::FieldOfView,
+    lib::*,
+    tile::TileFlip,
+    tilemap::{ErrorKind, TilemapResult},
+    Tilemap,
+};
+
+/// The fog of war state of a tile.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum FogState {
+    /// The tile has never been seen.
+    Unexplored,
+    /// The tile has been seen before but is not in view.
+    Explored,
+    /// The tile is in view.
+    Visible,
+}
+
+impl Default for FogState {
+    fn default() -> FogState {
+        FogState::Unexplored
+    }
+}
+
+/// How the fog of war is rendered over tiles that are not visible.
+///
+/// Each state is drawn as a sprite with a tint. For a darkening tint, point
+/// both states at a plain white sprite and tint it with a translucent color.
+/// For dedicated sprites, use their sprite indices with a white tint.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FogStyle {
+    /// The overlay of tiles that have never been seen.
+    pub unexplored: RawTile,
+    /// The overlay of tiles that have been seen but are not in view.
+    pub explored: RawTile,
+}
+
+impl Default for FogStyle {
+    fn default() -> FogStyle {
+        FogStyle {
+            unexplored: RawTile {
+                index: 0,
+                color: Color::BLACK,
+                flip: TileFlip::empty(),
+            },
+            explored: RawTile {
+                index: 0,
+                color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+                flip: TileFlip::empty(),
+            },
+        }
+    }
+}
+
+impl FogStyle {
+    /// Returns the overlay of a fog state, which is fully transparent for
+    /// visible tiles.
+    pub(crate) fn tile(&self, state: FogState) -> RawTile {
+        match state {
+            FogState::Unexplored => self.unexplored,
+            FogState::Explored => self.explored,
+            FogState::Visible => RawTile {
+                index: 0,
+                color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+                flip: TileFlip::empty(),
+            },
+        }
+    }
+}
+
+impl Tilemap {
+    /// Sets how fog layers are rendered, updating every spawned fog layer.
+    pub fn set_fog_style(&mut self, style: FogStyle) {
+        *self.fog_style_mut() = style;
+
+        let mut modified: HashMap<Point2, HashMap<usize, Entity>> = HashMap::default();
+        for (chunk_point, chunk) in self.chunks_mut().iter_mut() {
+            for z_order in 0..chunk.z_layers() {
+                if chunk.layer_kind(z_order) != Some(LayerKind::Fog) {
+                    continue;
+                }
+                chunk.mark_layer_dirty(z_order);
+                if let Some(entity) = chunk.get_entity(z_order) {
+                    modified
+                        .entry(*chunk_point)
+                        .or_insert_with(HashMap::default)
+                        .insert(z_order, entity);
+                }
+            }
+        }
+        self.send_modified(modified);
+    }
+
+    /// Gets the fog state of a tile on a fog layer.
+    ///
+    /// Returns `None` if the chunk does not exist or the layer is not a fog
+    /// layer.
+    pub fn fog_state<P: Into<Point2>>(&self, point: P, z_order: usize) -> Option<FogState> {
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+        self.chunks()
+            .get(&chunk_point)?
+            .get_fog_state(z_order, index)
+    }
+
+    /// Sets the fog state of many tiles on a fog layer at once.
+    ///
+    /// Points in chunks that do not exist are skipped. A single modified event
+    /// is sent per spawned chunk of which a state changed, so only those have
+    /// their meshes rebuilt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer is not a fog layer.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{fog::FogState, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = TilemapBuilder::new()
+    ///     .texture_atlas(texture_atlas_handle)
+    ///     .tile_dimensions(32, 32)
+    ///     .add_layer(TilemapLayer { kind: LayerKind::Fog, ..Default::default() }, 1)
+    ///     .finish()
+    ///     .unwrap();
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// let states = vec![((0, 0), FogState::Visible), ((1, 0), FogState::Explored)];
+    /// tilemap.set_fog_states(1, states).unwrap();
+    ///
+    /// assert_eq!(tilemap.fog_state((1, 0), 1), Some(FogState::Explored));
+    /// assert!(tilemap.set_fog_states(0, vec![((0, 0), FogState::Visible)]).is_err());
+    /// ```
+    pub fn set_fog_states<I, P>(&mut self, z_order: usize, states: I) -> TilemapResult<()>
+    where
+        I: IntoIterator<Item = (P, FogState)>,
+        P: Into<Point2>,
+    {
+        let is_fog = self
+            .layers()
+            .get(z_order)
+            .and_then(|layer| layer.as_ref())
+            .map_or(false, |layer| layer.kind == LayerKind::Fog);
+        if !is_fog {
+            return Err(ErrorKind::NotFogLayer(z_order).into());
+        }
+
+        let mut modified: HashMap<Point2, HashMap<usize, Entity>> = HashMap::default();
+        for (point, state) in states.into_iter() {
+            let point: Point2 = point.into();
+            let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+            let tile_point = self.point_to_tile_point(point);
+            let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+            let chunk = match self.chunks_mut().get_mut(&chunk_point) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            if !chunk.set_fog_state(z_order, index, state) {
+                continue;
+            }
+            if let Some(entity) = chunk.get_entity(z_order) {
+                modified
+                    .entry(chunk_point)
+                    .or_insert_with(HashMap::default)
+                    .insert(z_order, entity);
+            }
+        }
+        self.send_modified(modified);
+
+        Ok(())
+    }
+
+    /// Updates a fog layer from a field of view, where visible points become
+    /// visible and remembered points become explored.
+    ///
+    /// This is meant to be called every frame after the field of view was
+    /// updated. Only chunks of which a state changed have their meshes
+    /// rebuilt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer is not a fog layer.
+    pub fn reveal_fog(&mut self, fov: &FieldOfView, z_order: usize) -> TilemapResult<()> {
+        let visible = fov
+            .visible()
+            .iter()
+            .map(|point| (*point, FogState::Visible));
+        let explored = fov
+            .remembered()
+            .iter()
+            .map(|point| (*point, FogState::Explored));
+        self.set_fog_states(z_order, visible.chain(explored))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tilemap::test_builder, TilemapLayer};
+
+    #[test]
+    fn fog_survives_respawn() {
+        let mut tilemap = test_builder()
+            .add_layer(
+                TilemapLayer {
+                    kind: LayerKind::Fog,
+                    ..Default::default()
+                },
+                1,
+            )
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        // As the tilemap system does when it spawns the chunk.
+        tilemap.spawn_chunk((0, 0)).unwrap();
+        tilemap.spawned_chunks_mut().insert((0, 0));
+        let chunk_point = Point2::new(0, 0);
+        tilemap
+            .chunks_mut()
+            .get_mut(&chunk_point)
+            .unwrap()
+            .add_entity(1, Entity::new(1));
+
+        let mut fov = FieldOfView::default();
+        fov.update(vec![(1, 1), (2, 1)]);
+        fov.update(vec![(2, 1)]);
+        tilemap.reveal_fog(&fov, 1).unwrap();
+        assert!(tilemap.has_dirty_chunks());
+        tilemap.take_dirty_chunks();
+        // Nothing changes the second time.
+        tilemap.reveal_fog(&fov, 1).unwrap();
+        assert!(!tilemap.has_dirty_chunks());
+
+        tilemap.despawn_chunk((0, 0)).unwrap();
+        tilemap.spawn_chunk((0, 0)).unwrap();
+        assert_eq!(tilemap.fog_state((1, 1), 1), Some(FogState::Explored));
+        assert_eq!(tilemap.fog_state((2, 1), 1), Some(FogState::Visible));
+        assert_eq!(tilemap.fog_state((3, 1), 1), Some(FogState::Unexplored));
+
+        // The respawned layer is rendered from the states which were kept.
+        let style = tilemap.fog_style();
+        let (_, colors, _) = tilemap
+            .chunks()
+            .get(&chunk_point)
+            .unwrap()
+            .tiles_to_renderer_parts(1, tilemap.chunk_dimensions(), &style)
+            .unwrap();
+        let color = |state| -> [f32; 4] { style.tile(state).color.into() };
+        assert_eq!(colors[45 * 4], color(FogState::Explored));
+        assert_eq!(colors[46 * 4], color(FogState::Visible));
+        assert_eq!(colors[47 * 4], color(FogState::Unexplored));
+    }
+
+    #[test]
+    fn fog_states_only_on_fog_layers() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .tile_dimensions(32, 32)
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        assert_eq!(
+            tilemap.set_fog_states(0, vec![((0, 0), FogState::Visible)]),
+            Err(ErrorKind::NotFogLayer(0).into())
+        );
+        assert_eq!(tilemap.fog_state((0, 0), 0), None);
+    }
+}
@@ -0,0 +1,618 @@
+//! Field-of-view and line-of-sight over the tiles of a tilemap layer.
+//!
+//! Opacity is read from a single z layer through a predicate which is given
+//! each [`RawTile`] on the way. Points without a tile on the layer never block
+//! the view.
+//!
+//! Square and isometric grids use recursive shadowcasting over the 8 octants
+//! around the origin, with a circular radius. Hex grids use the same idea over the 6
+//! sextants, where every ring of hexes narrows the view by the shadows of the
+//! opaque hexes in the rings before it.
+//!
+//! Opaque tiles are visible themselves, so walls are seen but not what lies
+//! behind them. A [`FieldOfView`] keeps track of what was seen before, which
+//! can be faded with [`Tilemap::tint_field_of_view`].
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{chunk::RawTile, fov::FieldOfView, prelude::*};
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+//! tilemap.insert_chunk((0, 0)).unwrap();
+//!
+//! // Sprite 1 is a wall to the east of the origin.
+//! tilemap.insert_tile(Tile { point: (2, 0), sprite_index: 1, ..Default::default() }).unwrap();
+//!
+//! let is_opaque = |_, tile: &RawTile| tile.index == 1;
+//! let visible = tilemap.field_of_view((0, 0), 5, 0, is_opaque);
+//!
+//! let mut fov = FieldOfView::default();
+//! fov.update(visible);
+//!
+//! assert!(fov.is_visible((2, 0)));
+//! assert!(!fov.is_visible((4, 0)));
+//! assert!(fov.is_visible((0, 4)));
+//! assert!(!tilemap.line_of_sight((0, 0), (4, 0), 0, is_opaque));
+//! ```
+//!
+//! [`RawTile`]: crate::chunk::RawTile
+//! [`Tilemap::tint_field_of_view`]: crate::tilemap::Tilemap::tint_field_of_view
+
+use crate::{chunk::RawTile, lib::*, GridTopology, Tilemap};
+
+/// The tiles in view and the tiles that were in view before.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct FieldOfView {
+    /// The points which are currently visible.
+    visible: HashSet<Point2>,
+    /// The points which were visible before but are not anymore.
+    remembered: HashSet<Point2>,
+}
+
+impl FieldOfView {
+    /// Replaces the visible points, remembering the ones that went out of
+    /// view.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::fov::FieldOfView;
+    ///
+    /// let mut fov = FieldOfView::default();
+    /// fov.update(vec![(0, 0), (1, 0)]);
+    /// fov.update(vec![(1, 0)]);
+    ///
+    /// assert!(fov.is_visible((1, 0)));
+    /// assert!(fov.is_remembered((0, 0)));
+    /// assert!(!fov.is_remembered((1, 0)));
+    /// ```
+    pub fn update<I, P>(&mut self, visible: I)
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Point2>,
+    {
+        let visible = visible.into_iter().map(|point| point.into()).collect();
+        let previous = mem::replace(&mut self.visible, visible);
+        self.remembered.extend(previous);
+        let visible = &self.visible;
+        self.remembered.retain(|point| !visible.contains(point));
+    }
+
+    /// Returns the points which are currently visible.
+    pub fn visible(&self) -> &HashSet<Point2> {
+        &self.visible
+    }
+
+    /// Returns the points which were visible before but are not anymore.
+    pub fn remembered(&self) -> &HashSet<Point2> {
+        &self.remembered
+    }
+
+    /// Returns `true` if the point is currently visible.
+    pub fn is_visible<P: Into<Point2>>(&self, point: P) -> bool {
+        self.visible.contains(&point.into())
+    }
+
+    /// Returns `true` if the point was visible before but is not anymore.
+    pub fn is_remembered<P: Into<Point2>>(&self, point: P) -> bool {
+        self.remembered.contains(&point.into())
+    }
+
+    /// Forgets every point that is not currently visible.
+    pub fn forget(&mut self) {
+        self.remembered.clear();
+    }
+}
+
+impl Tilemap {
+    /// Computes the points visible from an origin within a radius on a layer.
+    ///
+    /// The predicate is given each tile that is looked at and returns `true`
+    /// if the tile blocks the view. Points without a tile on the layer never
+    /// do. Opaque tiles are visible themselves, as is the origin.
+    ///
+    /// Square grids use recursive shadowcasting with a circular radius, hex
+    /// grids use shadowcasting over rings of hexes.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = TilemapBuilder::new()
+    ///     .texture_atlas(texture_atlas_handle)
+    ///     .tile_dimensions(32, 32)
+    ///     .topology(GridTopology::HexY)
+    ///     .finish()
+    ///     .unwrap();
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// let visible = tilemap.field_of_view((0, 0), 2, 0, |_, _| true);
+    ///
+    /// // Nothing blocks the view without any tiles.
+    /// assert_eq!(visible.len(), 19);
+    /// ```
+    pub fn field_of_view<P, F>(
+        &self,
+        origin: P,
+        radius: u32,
+        z_order: usize,
+        is_opaque: F,
+    ) -> HashSet<Point2>
+    where
+        P: Into<Point2>,
+        F: Fn(Point2, &RawTile) -> bool,
+    {
+        compute(self.topology(), origin.into(), radius, |point| {
+            self.raw_tile(point, z_order)
+                .map_or(false, |tile| is_opaque(point, tile))
+        })
+    }
+
+    /// Returns `true` if no tile between two points blocks the view on a
+    /// layer.
+    ///
+    /// The points themselves are not checked. The predicate works the same
+    /// as in [`field_of_view`], and the tiles checked are the ones of
+    /// [`GridTopology::line`].
+    ///
+    /// [`field_of_view`]: Tilemap::field_of_view
+    /// [`GridTopology::line`]: crate::GridTopology::line
+    pub fn line_of_sight<P, F>(&self, from: P, to: P, z_order: usize, is_opaque: F) -> bool
+    where
+        P: Into<Point2>,
+        F: Fn(Point2, &RawTile) -> bool,
+    {
+        line_of_sight(self.topology(), from.into(), to.into(), |point| {
+            self.raw_tile(point, z_order)
+                .map_or(false, |tile| is_opaque(point, tile))
+        })
+    }
+
+    /// Tints every tile of a layer by what a field of view has seen.
+    ///
+    /// Visible tiles are tinted white, remembered tiles with the remembered
+    /// tint and all other tiles with the unseen tint. This replaces any tint
+    /// the tiles had before.
+    ///
+    /// Tiles on dense layers with an alpha of `0` are considered removed, so
+    /// use a dark tint such as `Color::BLACK` to hide unseen tiles instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_render::prelude::*;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{chunk::RawTile, fov::FieldOfView, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    /// tilemap.insert_tile(Tile { point: (5, 0), ..Default::default() }).unwrap();
+    ///
+    /// let mut fov = FieldOfView::default();
+    /// fov.update(tilemap.field_of_view((4, 0), 3, 0, |_, _| false));
+    /// fov.update(tilemap.field_of_view((0, 0), 3, 0, |_, _| false));
+    ///
+    /// let remembered = Color::rgb(0.5, 0.5, 0.5);
+    /// tilemap.tint_field_of_view(&fov, 0, remembered, Color::BLACK);
+    ///
+    /// assert_eq!(tilemap.get_tile((5, 0), 0), Some(&RawTile { index: 0, color: remembered, ..Default::default() }));
+    /// ```
+    pub fn tint_field_of_view(
+        &mut self,
+        fov: &FieldOfView,
+        z_order: usize,
+        remembered: Color,
+        unseen: Color,
+    ) {
+        let width = self.chunk_dimensions().width as i32;
+        let height = self.chunk_dimensions().height as i32;
+        let mut modified: HashMap<Point2, HashMap<usize, Entity>> = HashMap::default();
+        for (chunk_point, chunk) in self.chunks_mut().iter_mut() {
+            let indices = match chunk.get_tile_indices(z_order) {
+                Some(indices) => indices,
+                None => continue,
+            };
+            let mut changed = false;
+            for index in indices.into_iter() {
+                let point = Point2::new(
+                    index as i32 % width + width * chunk_point.x - width / 2,
+                    index as i32 / width + height * chunk_point.y - height / 2,
+                );
+                let color = if fov.is_visible(point) {
+                    Color::WHITE
+                } else if fov.is_remembered(point) {
+                    remembered
+                } else {
+                    unseen
+                };
+                if let Some(tile) = chunk.get_tile_mut(z_order, index) {
+                    if tile.color != color {
+                        tile.color = color;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                continue;
+            }
+            if let Some(entity) = chunk.get_entity(z_order) {
+                modified
+                    .entry(*chunk_point)
+                    .or_insert_with(HashMap::default)
+                    .insert(z_order, entity);
+            }
+        }
+        self.send_modified(modified);
+    }
+}
+
+/// Computes the points visible from an origin within a radius.
+pub(crate) fn compute<F>(
+    topology: GridTopology,
+    origin: Point2,
+    radius: u32,
+    is_opaque: F,
+) -> HashSet<Point2>
+where
+    F: Fn(Point2) -> bool,
+{
+    let mut visible = HashSet::default();
+    visible.insert(origin);
+    if topology.is_hex() {
+        for sextant in 0..6 {
+            cast_hex(topology, origin, sextant, radius, &is_opaque, &mut visible);
+        }
+    } else {
+        /// The transforms of each octant from its row and column.
+        const OCTANTS: [(i32, i32, i32, i32); 8] = [
+            (1, 0, 0, 1),
+            (0, 1, 1, 0),
+            (0, -1, 1, 0),
+            (-1, 0, 0, 1),
+            (-1, 0, 0, -1),
+            (0, -1, -1, 0),
+            (0, 1, -1, 0),
+            (1, 0, 0, -1),
+        ];
+        for transform in OCTANTS.iter() {
+            let octant = Octant {
+                topology,
+                origin: topology.point_to_diamond(origin),
+                radius: radius as i32,
+                transform: *transform,
+            };
+            octant.cast(1, 1.0, 0.0, &is_opaque, &mut visible);
+        }
+    }
+    visible
+}
+
+/// Returns `true` if no point between two points is opaque.
+///
+/// The points themselves are not checked, so a wall can always be seen from
+/// next to it.
+pub(crate) fn line_of_sight<F>(
+    topology: GridTopology,
+    from: Point2,
+    to: Point2,
+    is_opaque: F,
+) -> bool
+where
+    F: Fn(Point2) -> bool,
+{
+    let line = topology.line(from, to);
+    let inner = line.len().saturating_sub(1);
+    line.into_iter()
+        .take(inner)
+        .skip(1)
+        .all(|point| !is_opaque(point))
+}
+
+/// An octant of a square or isometric grid which is shadowcast.
+struct Octant {
+    /// The topology of the grid.
+    topology: GridTopology,
+    /// The point the view starts from, in diamond coordinates.
+    origin: Point2,
+    /// How far the view reaches.
+    radius: i32,
+    /// Maps a column and row of the octant onto the grid.
+    transform: (i32, i32, i32, i32),
+}
+
+impl Octant {
+    /// Returns the grid point of a column and row of the octant.
+    fn point(&self, col: i32, row: i32) -> Point2 {
+        let (xx, xy, yx, yy) = self.transform;
+        self.topology.diamond_to_point(Point2::new(
+            self.origin.x + col * xx + row * xy,
+            self.origin.y + col * yx + row * yy,
+        ))
+    }
+
+    /// Casts light over the rows of the octant between two slopes, recursing
+    /// into the gaps between opaque tiles.
+    fn cast<F>(
+        &self,
+        row: i32,
+        mut start: f32,
+        end: f32,
+        is_opaque: &F,
+        visible: &mut HashSet<Point2>,
+    ) where
+        F: Fn(Point2) -> bool,
+    {
+        if start < end {
+            return;
+        }
+        let radius_squared = self.radius * self.radius;
+        let mut next_start = start;
+        for distance in row..=self.radius {
+            let mut blocked = false;
+            let dy = -distance;
+            for dx in -distance..=0 {
+                let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right_slope {
+                    continue;
+                } else if end > left_slope {
+                    break;
+                }
+
+                let point = self.point(dx, dy);
+                if dx * dx + dy * dy <= radius_squared {
+                    visible.insert(point);
+                }
+
+                let opaque = is_opaque(point);
+                if blocked {
+                    if opaque {
+                        next_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = next_start;
+                    }
+                } else if opaque && distance < self.radius {
+                    blocked = true;
+                    self.cast(distance + 1, start, left_slope, is_opaque, visible);
+                    next_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+}
+
+/// Casts the view over a sextant of a hex grid, ring by ring.
+///
+/// A hex at position `i` of ring `r` covers the part of the sextant between
+/// `(i - 0.5) / r` and `(i + 0.5) / r`, and is visible while its center is not
+/// in a shadow. The last hex of each ring is the first of the next sextant,
+/// which lets shadows carry over the edge.
+fn cast_hex<F>(
+    topology: GridTopology,
+    origin: Point2,
+    sextant: usize,
+    radius: u32,
+    is_opaque: &F,
+    visible: &mut HashSet<Point2>,
+) where
+    F: Fn(Point2) -> bool,
+{
+    let neighbours = topology.neighbours(origin);
+    let cube = topology.point_to_cube(origin);
+    let direction = |n: usize| {
+        let neighbour = neighbours
+            .get(n % 6)
+            .map_or(cube, |point| topology.point_to_cube(*point));
+        (neighbour.x - cube.x, neighbour.y - cube.y)
+    };
+    let corner = direction(sextant);
+    let step = direction(sextant + 2);
+
+    // The shadows, merged and sorted by where they start.
+    let mut shadows: Vec<(f32, f32)> = Vec::new();
+    for ring in 1..=radius as i32 {
+        for i in 0..=ring {
+            let center = i as f32 / ring as f32;
+            if shadows
+                .iter()
+                .any(|(start, end)| *start < center && center < *end)
+            {
+                continue;
+            }
+
+            let q = cube.x + corner.0 * ring + step.0 * i;
+            let r = cube.y + corner.1 * ring + step.1 * i;
+            let point = topology.cube_to_point(Point3::new(q, r, -q - r));
+            visible.insert(point);
+
+            if is_opaque(point) {
+                let half = 0.5 / ring as f32;
+                add_shadow(&mut shadows, (center - half, center + half));
+            }
+        }
+        if shadows
+            .first()
+            .map_or(false, |(start, end)| *start < 0.0 && *end > 1.0)
+        {
+            break;
+        }
+    }
+}
+
+/// Adds a shadow, merging it with the ones it overlaps or touches.
+fn add_shadow(shadows: &mut Vec<(f32, f32)>, shadow: (f32, f32)) {
+    let (mut start, mut end) = shadow;
+    shadows.retain(|(other_start, other_end)| {
+        if *other_start <= end && start <= *other_end {
+            start = start.min(*other_start);
+            end = end.max(*other_end);
+            false
+        } else {
+            true
+        }
+    });
+    let index = shadows
+        .iter()
+        .position(|(other_start, _)| *other_start > start)
+        .unwrap_or_else(|| shadows.len());
+    shadows.insert(index, (start, end));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns every point within a radius of the origin on a square grid.
+    fn disc(radius: i32) -> HashSet<Point2> {
+        let mut points = HashSet::default();
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                if x * x + y * y <= radius * radius {
+                    points.insert(Point2::new(x, y));
+                }
+            }
+        }
+        points
+    }
+
+    /// Checks that the view is the same in every octant of a square grid.
+    fn assert_octant_symmetric(visible: &HashSet<Point2>) {
+        for point in visible.iter() {
+            for (x, y) in [(point.x, point.y), (point.y, point.x)].iter() {
+                for (sign_x, sign_y) in [(1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+                    assert!(visible.contains(&Point2::new(x * sign_x, y * sign_y)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn square_open_field() {
+        let visible = compute(GridTopology::Square, Point2::new(0, 0), 5, |_| false);
+        assert_eq!(visible, disc(5));
+        assert_octant_symmetric(&visible);
+
+        let origin = Point2::new(7, -3);
+        let visible = compute(GridTopology::Square, origin, 5, |_| false);
+        let expected: HashSet<Point2> = disc(5)
+            .into_iter()
+            .map(|point| Point2::new(point.x + origin.x, point.y + origin.y))
+            .collect();
+        assert_eq!(visible, expected);
+    }
+
+    #[test]
+    fn square_pillars_are_symmetric() {
+        // A pillar on each axis, all at the same distance.
+        let pillars = [(2, 0), (0, 2), (-2, 0), (0, -2)];
+        let is_opaque = |point: Point2| pillars.contains(&(point.x, point.y));
+        let visible = compute(GridTopology::Square, Point2::new(0, 0), 6, is_opaque);
+        assert_octant_symmetric(&visible);
+
+        for (x, y) in pillars.iter() {
+            // The pillars are seen, but not what lies right behind them.
+            assert!(visible.contains(&Point2::new(*x, *y)));
+            assert!(!visible.contains(&Point2::new(x * 2, y * 2)));
+            assert!(!visible.contains(&Point2::new(x * 3, y * 3)));
+        }
+        assert!(visible.contains(&Point2::new(3, 3)));
+        assert!(visible.contains(&Point2::new(2, 1)));
+    }
+
+    #[test]
+    fn square_walls_hide_rooms() {
+        // A wall on every side of a room, with the view from inside it.
+        let is_opaque = |point: Point2| point.x.abs() == 2 || point.y.abs() == 2;
+        let visible = compute(GridTopology::Square, Point2::new(0, 0), 8, is_opaque);
+        assert_octant_symmetric(&visible);
+        assert!(visible
+            .iter()
+            .all(|point| point.x.abs() <= 2 && point.y.abs() <= 2));
+        assert!(visible.contains(&Point2::new(2, 0)));
+        assert!(visible.contains(&Point2::new(2, 1)));
+    }
+
+    #[test]
+    fn hex_open_field() {
+        for topology in [GridTopology::HexY, GridTopology::HexEvenRows].iter() {
+            let origin = Point2::new(1, 2);
+            let visible = compute(*topology, origin, 3, |_| false);
+            let expected: HashSet<Point2> = topology.spiral(origin, 3).into_iter().collect();
+            assert_eq!(visible.len(), 37);
+            assert_eq!(visible, expected);
+        }
+    }
+
+    #[test]
+    fn hex_walls_cast_shadows() {
+        let topology = GridTopology::HexY;
+        let origin = Point2::new(0, 0);
+        let cube = topology.point_to_cube(origin);
+        for neighbour in topology.neighbours(origin) {
+            let wall = topology.point_to_cube(neighbour);
+            let behind = topology.cube_to_point(Point3::new(
+                2 * wall.x - cube.x,
+                2 * wall.y - cube.y,
+                2 * wall.z - cube.z,
+            ));
+            let visible = compute(topology, origin, 4, |point| point == neighbour);
+            assert!(visible.contains(&neighbour));
+            assert!(!visible.contains(&behind));
+            // Out of the 61 hexes in view, 7 are in the shadow of the wall.
+            assert_eq!(visible.len(), 61 - 7);
+        }
+    }
+
+    #[test]
+    fn line_of_sight_skips_ends() {
+        let is_opaque = |point: Point2| point == Point2::new(2, 0);
+        let topology = GridTopology::Square;
+        let origin = Point2::new(0, 0);
+        assert!(!line_of_sight(
+            topology,
+            origin,
+            Point2::new(4, 0),
+            is_opaque
+        ));
+        assert!(!line_of_sight(
+            topology,
+            Point2::new(4, 0),
+            origin,
+            is_opaque
+        ));
+        assert!(line_of_sight(
+            topology,
+            origin,
+            Point2::new(2, 0),
+            is_opaque
+        ));
+        assert!(line_of_sight(
+            topology,
+            Point2::new(2, 0),
+            Point2::new(4, 0),
+            is_opaque
+        ));
+        assert!(line_of_sight(
+            topology,
+            origin,
+            Point2::new(0, 4),
+            is_opaque
+        ));
+    }
+}
@@ -14,7 +14,7 @@
 //! * Easy to use and stable API with thorough documentation.
 //! * Endless or constrained tilemaps.
 //! * Batched rendering of many tiles.
-//! * Square and hex tiles.
+//! * Square, isometric and hex tiles.
 //!
 //! ## Design
 //! This is not intended to be just another Tilemap. It is meant to be a
@@ -52,6 +52,30 @@
 //! ```
 //!
 //! See the library `bevy_tilemap_types` for more information.
+//!
+//! # Tiled support
+//!
+//! Maps made with the Tiled editor can be loaded through the `AssetServer`
+//! as `Tilemap` assets.
+//!
+//! ```toml
+//! [dependencies]
+//! bevy_tilemap = { version = "0.3", features = ["tiled"] }
+//! ```
+//!
+//! See the `loader::tiled` module for more information.
+//!
+//! # LDtk support
+//!
+//! Projects made with the LDtk level editor can be loaded through the
+//! `AssetServer`, with a `Tilemap` asset for each level.
+//!
+//! ```toml
+//! [dependencies]
+//! bevy_tilemap = { version = "0.3", features = ["ldtk"] }
+//! ```
+//!
+//! See the `loader::ldtk` module for more information.
 
 #![doc(html_root_url = "https://docs.rs/bevy_tilemap/0.3.1")]
 // This was broken even further and no longer will work at all with the previous
@@ -88,16 +112,33 @@ pub use bevy_tilemap_types::dimension;
 #[cfg(feature = "types")]
 #[doc(inline)]
 pub use bevy_tilemap_types::point;
+#[cfg(feature = "types")]
+#[doc(inline)]
+pub use bevy_tilemap_types::topology;
 
+#[no_implicit_prelude]
+pub mod animation;
+#[no_implicit_prelude]
+pub mod auto_tile;
 #[no_implicit_prelude]
 pub mod chunk;
 #[no_implicit_prelude]
+pub mod collision;
+#[no_implicit_prelude]
 pub mod default_plugin;
 #[no_implicit_prelude]
 pub mod entity;
 #[no_implicit_prelude]
+pub mod fog;
+#[no_implicit_prelude]
+pub mod fov;
+#[no_implicit_prelude]
+pub mod pathfinding;
+#[no_implicit_prelude]
 pub mod prelude;
 #[no_implicit_prelude]
+pub mod query;
+#[no_implicit_prelude]
 pub mod stage {
     //! The stages for the tilemap in the bevy app.
 
@@ -106,11 +147,18 @@ pub mod stage {
 }
 #[no_implicit_prelude]
 pub mod event;
+#[cfg(any(feature = "ldtk", feature = "tiled"))]
+#[no_implicit_prelude]
+pub mod loader;
+#[no_implicit_prelude]
+pub mod storage;
 #[no_implicit_prelude]
 mod system;
 #[no_implicit_prelude]
 pub mod tile;
 #[no_implicit_prelude]
+pub mod tile_data;
+#[no_implicit_prelude]
 pub mod tilemap;
 
 use crate::{chunk::render::TilemapRenderGraphBuilder, event::TilemapChunkEvent, lib::*};
@@ -118,6 +166,7 @@ pub use crate::{
     tile::Tile,
     tilemap::{Tilemap, TilemapLayer},
 };
+pub use bevy_tilemap_types::topology::GridTopology;
 
 /// The Bevy Tilemap 2D main plugin.
 #[derive(Default)]
@@ -126,11 +175,13 @@ pub struct Tilemap2DPlugin;
 impl Plugin for Tilemap2DPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_asset::<Tilemap>()
+            .add_event::<crate::event::TilemapChunkLayerEvent>()
             .add_stage_before(
                 app_stage::POST_UPDATE,
                 stage::TILEMAP,
                 SystemStage::parallel(),
             )
+            .add_system_to_stage(stage::TILEMAP, crate::animation::tile_animations.system())
             .add_system_to_stage(stage::TILEMAP, crate::system::tilemap_events.system())
             .add_system_to_stage(stage::TILEMAP, crate::chunk::system::chunk_update.system())
             .add_system_to_stage(
@@ -140,18 +191,36 @@ impl Plugin for Tilemap2DPlugin {
             .add_system_to_stage(
                 stage::TILEMAP,
                 crate::chunk::system::chunk_auto_spawn.system(),
+            )
+            .add_system_to_stage(
+                stage::TILEMAP,
+                crate::chunk::system::chunk_visibility.system(),
             );
+        app.add_system_to_stage(stage::TILEMAP, crate::system::tile_sensor_events.system());
         #[cfg(feature = "bevy_rapier2d")]
         app.add_system_to_stage(
             stage::TILEMAP,
             crate::system::tilemap_collision_events.system(),
         );
+        #[cfg(feature = "ldtk")]
+        app.add_asset::<crate::loader::ldtk::LdtkProject>()
+            .init_asset_loader::<crate::loader::ldtk::LdtkProjectLoader>()
+            .add_event::<crate::loader::ldtk::LdtkEntityEvent>()
+            .add_system_to_stage(
+                stage::TILEMAP,
+                crate::loader::ldtk::ldtk_entity_events.system(),
+            );
+        #[cfg(feature = "tiled")]
+        app.init_asset_loader::<crate::loader::tiled::TiledMapLoader>();
 
+        // Headless apps have no render graph, their tilemaps are still kept
+        // up to date but not drawn.
         let resources = app.resources_mut();
-        let mut render_graph = resources
-            .get_mut::<RenderGraph>()
-            .expect("`RenderGraph` is missing.");
-        render_graph.add_tilemap_graph(resources);
+        if let Some(mut render_graph) = resources.get_mut::<RenderGraph>() {
+            render_graph.add_tilemap_graph(resources);
+        } else {
+            warn!("`RenderGraph` is missing, tilemaps will not be drawn");
+        }
     }
 }
 
@@ -160,6 +229,7 @@ impl Plugin for Tilemap2DPlugin {
 mod lib {
     extern crate bevy_app;
     extern crate bevy_asset;
+    extern crate bevy_core;
     extern crate bevy_ecs;
     extern crate bevy_log;
     extern crate bevy_math;
@@ -177,68 +247,115 @@ mod lib {
     extern crate serde;
     extern crate std;
 
+    #[cfg(feature = "ldtk")]
+    pub(crate) use bevy_app::EventReader;
     pub(crate) use bevy_app::{
         stage as app_stage, AppBuilder, Events, Plugin, PluginGroup, PluginGroupBuilder,
     };
-    pub(crate) use bevy_asset::{AddAsset, Assets, Handle, HandleUntyped};
+    #[cfg(feature = "ldtk")]
+    pub(crate) use bevy_asset::AssetEvent;
+    pub(crate) use bevy_asset::{AddAsset, Asset, Assets, Handle, HandleId, HandleUntyped};
+    #[cfg(any(feature = "ldtk", feature = "tiled"))]
+    pub(crate) use bevy_asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+    pub(crate) use bevy_core::Time;
+    #[cfg(feature = "ldtk")]
+    pub(crate) use bevy_ecs::Local;
     pub(crate) use bevy_ecs::{
         Bundle, Changed, Commands, Entity, IntoSystem, Query, Res, ResMut, Resources, SystemStage,
+        With,
     };
     pub(crate) use bevy_log::{error, info, warn};
+    pub(crate) use bevy_math::Vec2;
+    #[cfg(test)]
     pub(crate) use bevy_math::Vec3;
     #[cfg(feature = "bevy_rapier2d")]
     pub(crate) use bevy_rapier2d::rapier::{
         dynamics::RigidBodyBuilder,
-        geometry::{ColliderBuilder, InteractionGroups},
+        geometry::{
+            ColliderBuilder, ContactPairFilter, InteractionGroups, PairFilterContext, SolverFlags,
+        },
+        math::Point,
     };
     pub(crate) use bevy_reflect::{TypeUuid, Uuid};
+    #[cfg(any(feature = "ldtk", feature = "tiled"))]
+    pub(crate) use bevy_render::texture::Texture;
     pub(crate) use bevy_render::{
-        camera::Camera,
+        camera::{Camera, OrthographicProjection},
         color::Color,
         draw::{Draw, Visible},
-        mesh::{Indices, Mesh},
+        mesh::{Indices, Mesh, VertexAttributeValues},
         pipeline::{
             BlendDescriptor, BlendFactor, BlendOperation, ColorStateDescriptor, ColorWrite,
             CompareFunction, CullMode, DepthStencilStateDescriptor, FrontFace, PipelineDescriptor,
             PrimitiveTopology, RasterizationStateDescriptor, RenderPipeline, RenderPipelines,
             StencilStateDescriptor, StencilStateFaceDescriptor,
         },
-        render_graph::{base::MainPass, RenderGraph},
+        render_graph::{
+            base::{node, MainPass},
+            AssetRenderResourcesNode, RenderGraph,
+        },
+        renderer::RenderResources,
         shader::{Shader, ShaderStage, ShaderStages},
         texture::TextureFormat,
     };
+    #[cfg(any(feature = "ldtk", feature = "tiled"))]
+    pub(crate) use bevy_sprite::Rect;
     pub(crate) use bevy_sprite::TextureAtlas;
     pub(crate) use bevy_tilemap_types::{
         dimension::{Dimension2, DimensionError},
-        point::Point2,
+        point::{Point2, Point3},
     };
     pub(crate) use bevy_transform::{
-        components::{GlobalTransform, Parent, Transform},
+        components::{GlobalTransform, Transform},
         hierarchy::{BuildChildren, DespawnRecursiveExt},
     };
+    #[cfg(any(feature = "ldtk", feature = "tiled"))]
+    pub(crate) use bevy_utils::BoxedFuture;
     pub(crate) use bevy_utils::{HashMap, HashSet};
     pub(crate) use bevy_window::WindowResized;
 
     pub(crate) use crate::bitflags::*;
 
     #[cfg(feature = "serde")]
-    pub(crate) use serde::{Deserialize, Serialize};
+    pub(crate) use serde::{
+        de::{DeserializeOwned, Error as DeError},
+        ser::Error as SerError,
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
 
     pub(crate) use std::{
+        any::{Any, TypeId},
         boxed::Box,
         clone::Clone,
-        cmp::Ord,
+        cmp::{Ord, PartialEq, Reverse},
+        collections::BinaryHeap,
         convert::{AsMut, AsRef, From, Into},
         default::Default,
         error::Error,
         fmt::{Debug, Display, Formatter, Result as FmtResult},
+        io::{self, Read, Seek, SeekFrom, Write},
         iter::{Extend, IntoIterator, Iterator},
+        marker::{Send, Sync},
+        mem,
+        ops::{Fn, Range},
         option::Option::{self, *},
         result::Result::{self, *},
+        string::{String, ToString},
         vec::Vec,
     };
 
+    #[cfg(any(feature = "ldtk", feature = "tiled"))]
+    pub(crate) use std::path::Path;
+    #[cfg(feature = "serde")]
+    pub(crate) use std::{any::type_name, collections::BTreeMap};
+    #[cfg(feature = "tiled")]
+    pub(crate) use std::{path::PathBuf, str::FromStr};
+
     // Macros
+    #[cfg(any(feature = "ldtk", feature = "tiled", feature = "serde"))]
+    pub(crate) use std::format;
+    #[cfg(test)]
+    pub(crate) use std::{assert, assert_eq, panic};
     pub(crate) use std::{vec, write};
 
     #[cfg(debug_assertions)]
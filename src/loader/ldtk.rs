@@ -0,0 +1,725 @@
+//! Imports projects made with the [LDtk](https://ldtk.io/) level editor.
+//!
+//! The [`LdtkProjectLoader`] is registered for the `ldtk` extension and loads
+//! an [`LdtkProject`]. Every level of the project becomes its own [`Tilemap`]
+//! asset, labeled with the level identifier, e.g. `world.ldtk#Level_0`. Each
+//! tileset with an image becomes a texture atlas labeled `atlas/` followed by
+//! the tileset identifier. Tilesets without an image, like the internal icons
+//! of LDtk, are skipped along with the layers that use them. The texture
+//! atlas of a level tilemap is the one of its bottom-most tile layer, and
+//! layers which use another tileset have the texture atlas of that tileset.
+//!
+//! Tile layers, auto-layers and IntGrid layers with auto-layer rules become z
+//! layers of the level tilemap, bottom-most first. Layers that are at least
+//! half filled are imported as dense layers, everything else is sparse. The
+//! values of IntGrid layers are kept with the level and are available through
+//! [`LdtkLevel::int_grid_value`].
+//!
+//! When a project is loaded or reloaded, an [`LdtkEntityEvent`] is sent for
+//! every entity instance of every level so that they can be spawned by the
+//! game.
+//!
+//! LDtk counts rows from the top down while the tilemap counts up, so rows are
+//! flipped on import and levels are centered around the origin.
+//!
+//! ```no_run
+//! use bevy_app::prelude::*;
+//! use bevy_ecs::prelude::*;
+//! use bevy_tilemap::loader::ldtk::LdtkEntityEvent;
+//!
+//! fn spawn_entities(
+//!     mut reader: Local<EventReader<LdtkEntityEvent>>,
+//!     events: Res<Events<LdtkEntityEvent>>,
+//! ) {
+//!     for event in reader.iter(&events) {
+//!         if event.entity.identifier == "Player" {
+//!             // Spawn the player at `event.entity.position`.
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! # Limitations
+//!
+//! * Levels saved in separate files are not supported.
+//! * Layers with a grid size other than the one of the bottom-most layer are
+//!   skipped.
+
+use crate::{
+    chunk::LayerKind,
+    lib::*,
+    loader::{floor_even, grid_area, grid_texture_atlas, ErrorKind, LoaderResult},
+    tile::{Tile, TileFlip},
+    tilemap::{Tilemap, TilemapLayer},
+};
+use ::serde_json::Value;
+
+/// The label prefix of the texture atlases built from the tilesets.
+const ATLAS_LABEL_PREFIX: &str = "atlas/";
+
+/// The root of an LDtk project file.
+#[derive(Deserialize)]
+struct ProjectJson {
+    /// The definitions of the project.
+    defs: DefsJson,
+    /// All levels of the project.
+    levels: Vec<LevelJson>,
+}
+
+/// The definitions of an LDtk project.
+#[derive(Deserialize)]
+struct DefsJson {
+    /// All tilesets of the project.
+    tilesets: Vec<TilesetJson>,
+}
+
+/// A tileset definition.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TilesetJson {
+    /// The unique ID of the tileset.
+    uid: i64,
+    /// The name of the tileset.
+    identifier: String,
+    /// The path of the image relative to the project. Missing for tilesets
+    /// without an image, like the internal icons.
+    rel_path: Option<String>,
+    /// The width of the image in pixels.
+    px_wid: u32,
+    /// The height of the image in pixels.
+    px_hei: u32,
+    /// The width and height of a tile in pixels.
+    tile_grid_size: u32,
+    /// The pixels between tiles in the image.
+    #[serde(default)]
+    spacing: u32,
+    /// The pixels around the tiles in the image.
+    #[serde(default)]
+    padding: u32,
+}
+
+/// A level of the project.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LevelJson {
+    /// The name of the level.
+    identifier: String,
+    /// The horizontal position of the level in the world in pixels.
+    world_x: i32,
+    /// The vertical position of the level in the world in pixels, downwards.
+    world_y: i32,
+    /// The layers of the level, top-most first. Missing if the level is saved
+    /// in a separate file.
+    layer_instances: Option<Vec<LayerJson>>,
+}
+
+/// A layer of a level.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerJson {
+    /// The name of the layer.
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    /// The width of the layer in cells.
+    #[serde(rename = "__cWid")]
+    width: i32,
+    /// The height of the layer in cells.
+    #[serde(rename = "__cHei")]
+    height: i32,
+    /// The width and height of a cell in pixels.
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    /// The opacity of the layer.
+    #[serde(rename = "__opacity", default = "default_opacity")]
+    opacity: f32,
+    /// The tileset used by the tiles of the layer.
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_def_uid: Option<i64>,
+    /// The IntGrid values of every cell, zero meaning empty.
+    #[serde(default)]
+    int_grid_csv: Vec<i32>,
+    /// The IntGrid values of non-empty cells, used by older projects.
+    #[serde(default)]
+    int_grid: Vec<IntGridValueJson>,
+    /// The tiles of a tile layer.
+    #[serde(default)]
+    grid_tiles: Vec<TileJson>,
+    /// The tiles generated by auto-layer rules.
+    #[serde(default)]
+    auto_layer_tiles: Vec<TileJson>,
+    /// The entity instances of an entity layer.
+    #[serde(default)]
+    entity_instances: Vec<EntityJson>,
+}
+
+/// The IntGrid value of a single cell, used by older projects.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntGridValueJson {
+    /// The index of the cell in the layer.
+    coord_id: usize,
+    /// The index of the value, starting at zero.
+    v: i32,
+}
+
+/// A tile of a layer.
+#[derive(Deserialize)]
+struct TileJson {
+    /// The position of the tile in the layer in pixels.
+    px: [i32; 2],
+    /// The ID of the tile in its tileset.
+    t: usize,
+    /// The flip bits of the tile, where bit 0 is an x flip and bit 1 a y flip.
+    #[serde(default)]
+    f: u8,
+}
+
+/// An entity instance.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntityJson {
+    /// The name of the entity definition.
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    /// The cell of the entity.
+    #[serde(rename = "__grid")]
+    grid: [i32; 2],
+    /// The position of the entity pivot in the layer in pixels.
+    px: [i32; 2],
+    /// The width of the entity in pixels.
+    width: u32,
+    /// The height of the entity in pixels.
+    height: u32,
+    /// The custom fields of the entity.
+    #[serde(default)]
+    field_instances: Vec<FieldJson>,
+}
+
+/// A custom field of an entity.
+#[derive(Deserialize)]
+struct FieldJson {
+    /// The name of the field.
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    /// The value of the field.
+    #[serde(rename = "__value")]
+    value: Value,
+}
+
+/// The opacity of a layer which does not specify one.
+fn default_opacity() -> f32 {
+    1.0
+}
+
+/// A project made with LDtk, with a tilemap for each of its levels.
+#[derive(Debug)]
+pub struct LdtkProject {
+    /// The levels of the project in the order of the editor.
+    pub levels: Vec<LdtkLevel>,
+}
+
+impl TypeUuid for LdtkProject {
+    const TYPE_UUID: Uuid = Uuid::from_u128(260215417398410766128862377640139716385);
+}
+
+impl LdtkProject {
+    /// Returns the level with the given identifier.
+    pub fn level(&self, identifier: &str) -> Option<&LdtkLevel> {
+        self.levels
+            .iter()
+            .find(|level| level.identifier == identifier)
+    }
+}
+
+/// A single level of an LDtk project.
+#[derive(Debug)]
+pub struct LdtkLevel {
+    /// The name of the level.
+    pub identifier: String,
+    /// The position of the top left corner of the level in the world in
+    /// pixels, with Y pointing up.
+    pub world_position: Vec2,
+    /// The tilemap asset of the level.
+    pub tilemap: Handle<Tilemap>,
+    /// The entity instances of the level.
+    pub entities: Vec<LdtkEntity>,
+    /// The IntGrid values of each IntGrid layer by layer name.
+    int_grids: HashMap<String, HashMap<Point2, i32>>,
+}
+
+impl LdtkLevel {
+    /// Returns the IntGrid value of a tile in an IntGrid layer.
+    ///
+    /// The point uses the same coordinates as the tiles of the level tilemap.
+    /// Returns `None` if the layer does not exist or if the cell is empty.
+    pub fn int_grid_value<P: Into<Point2>>(&self, layer: &str, point: P) -> Option<i32> {
+        self.int_grids
+            .get(layer)
+            .and_then(|values| values.get(&point.into()))
+            .copied()
+    }
+}
+
+/// An entity instance placed in a level.
+#[derive(Clone, Debug)]
+pub struct LdtkEntity {
+    /// The name of the entity definition.
+    pub identifier: String,
+    /// The tile of the level tilemap which the entity is in.
+    pub point: Point2,
+    /// The position of the entity pivot relative to the level tilemap in
+    /// pixels.
+    pub position: Vec2,
+    /// The width of the entity in pixels.
+    pub width: u32,
+    /// The height of the entity in pixels.
+    pub height: u32,
+    /// The custom fields of the entity by name.
+    pub fields: HashMap<String, Value>,
+}
+
+/// Sent for every entity instance of a project when it is loaded.
+#[derive(Clone, Debug)]
+pub struct LdtkEntityEvent {
+    /// A weak handle to the project the entity is in.
+    pub project: Handle<LdtkProject>,
+    /// The identifier of the level the entity is in.
+    pub level: String,
+    /// The entity instance.
+    pub entity: LdtkEntity,
+}
+
+/// Converts LDtk cells and pixels of a level to the level tilemap.
+struct LevelSpace {
+    /// The offset added to columns.
+    offset_x: i32,
+    /// The offset rows are subtracted from.
+    offset_y: i32,
+    /// The width and height of a cell in pixels.
+    grid_size: i32,
+}
+
+impl LevelSpace {
+    /// Creates the space of a level with the given size in cells.
+    fn new(width: i32, height: i32, grid_size: u32) -> LevelSpace {
+        LevelSpace {
+            offset_x: -floor_even(width / 2),
+            offset_y: floor_even(height / 2),
+            grid_size: grid_size.max(1) as i32,
+        }
+    }
+
+    /// Returns the tilemap point of a cell.
+    fn point(&self, col: i32, row: i32) -> Point2 {
+        Point2::new(col + self.offset_x, self.offset_y - row)
+    }
+
+    /// Returns the tilemap point of a pixel position.
+    fn pixel_point(&self, [x, y]: [i32; 2]) -> Point2 {
+        self.point(x.div_euclid(self.grid_size), y.div_euclid(self.grid_size))
+    }
+
+    /// Returns the position of a pixel relative to the tilemap.
+    fn position(&self, [x, y]: [i32; 2]) -> Vec2 {
+        Vec2::new(
+            (x + self.offset_x * self.grid_size) as f32,
+            ((self.offset_y + 1) * self.grid_size - y) as f32,
+        )
+    }
+}
+
+/// Reads the IntGrid values of a layer, if it has any.
+fn int_grid_values(layer: &LayerJson, space: &LevelSpace) -> HashMap<Point2, i32> {
+    let width = layer.width.max(1) as usize;
+    let cell = |index: usize| space.point((index % width) as i32, (index / width) as i32);
+    let mut values = HashMap::default();
+    values.extend(
+        layer
+            .int_grid_csv
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value != 0)
+            .map(|(index, value)| (cell(index), *value)),
+    );
+    values.extend(
+        layer
+            .int_grid
+            .iter()
+            .map(|value| (cell(value.coord_id), value.v + 1)),
+    );
+    values
+}
+
+/// Builds the tilemap of a level out of its tile layers.
+fn level_tilemap(
+    layers: &[LayerJson],
+    grid_size: u32,
+    space: &LevelSpace,
+    atlases: &HashMap<i64, Handle<TextureAtlas>>,
+) -> LoaderResult<Tilemap> {
+    let mut tile_layers = Vec::new();
+    for layer in layers.iter().rev() {
+        if layer.grid_tiles.is_empty() && layer.auto_layer_tiles.is_empty() {
+            continue;
+        }
+        if layer.grid_size != grid_size {
+            warn!(
+                "skipping LDtk layer `{}` with a grid size of {} instead of {}",
+                layer.identifier, layer.grid_size, grid_size
+            );
+            continue;
+        }
+        let has_atlas = layer
+            .tileset_def_uid
+            .map_or(false, |uid| atlases.contains_key(&uid));
+        if !has_atlas {
+            warn!(
+                "skipping LDtk layer `{}` which does not use a tileset with an image",
+                layer.identifier
+            );
+            continue;
+        }
+        tile_layers.push(layer);
+    }
+    let atlas_uid = tile_layers.first().and_then(|layer| layer.tileset_def_uid);
+    let atlas = atlas_uid
+        .and_then(|uid| atlases.get(&uid))
+        .cloned()
+        .unwrap_or_default();
+
+    let mut builder = Tilemap::builder()
+        .tile_dimensions(grid_size, grid_size)
+        .auto_chunk()
+        .z_layers(tile_layers.len())
+        .texture_atlas(atlas);
+    for (z_order, layer) in tile_layers.iter().enumerate() {
+        let area = grid_area(layer.width, layer.height)?;
+        let count = layer.grid_tiles.len() + layer.auto_layer_tiles.len();
+        let kind = if count * 2 >= area {
+            LayerKind::Dense
+        } else {
+            LayerKind::Sparse
+        };
+        let texture_atlas = if layer.tileset_def_uid == atlas_uid {
+            None
+        } else {
+            layer
+                .tileset_def_uid
+                .and_then(|uid| atlases.get(&uid))
+                .cloned()
+        };
+        let layer = TilemapLayer {
+            kind,
+            texture_atlas,
+            ..Default::default()
+        };
+        builder = builder.add_layer(layer, z_order);
+    }
+    let mut tilemap = builder.finish()?;
+
+    for (z_order, layer) in tile_layers.iter().enumerate() {
+        let tint = Color::rgba(1.0, 1.0, 1.0, layer.opacity);
+        let tiles = layer
+            .auto_layer_tiles
+            .iter()
+            .chain(layer.grid_tiles.iter())
+            .map(|tile| Tile {
+                point: space.pixel_point(tile.px),
+                z_order,
+                sprite_index: tile.t,
+                tint,
+                flip: TileFlip::from_bits_truncate(tile.f),
+            });
+        tilemap.insert_tiles(tiles)?;
+    }
+
+    Ok(tilemap)
+}
+
+/// Reads the entity instances of all layers of a level.
+fn level_entities(layers: &[LayerJson], space: &LevelSpace) -> Vec<LdtkEntity> {
+    layers
+        .iter()
+        .flat_map(|layer| layer.entity_instances.iter())
+        .map(|entity| {
+            let [col, row] = entity.grid;
+            LdtkEntity {
+                identifier: entity.identifier.clone(),
+                point: space.point(col, row),
+                position: space.position(entity.px),
+                width: entity.width,
+                height: entity.height,
+                fields: entity
+                    .field_instances
+                    .iter()
+                    .map(|field| (field.identifier.clone(), field.value.clone()))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Builds the tilemap of a level and collects its data.
+fn load_level(
+    level: &LevelJson,
+    atlases: &HashMap<i64, Handle<TextureAtlas>>,
+    load_context: &mut LoadContext,
+) -> LoaderResult<LdtkLevel> {
+    let layers = level.layer_instances.as_ref().ok_or_else(|| {
+        ErrorKind::Unsupported(format!(
+            "the level `{}` saved in a separate file",
+            level.identifier
+        ))
+    })?;
+    let bottom = layers
+        .last()
+        .ok_or(ErrorKind::MissingElement("layerInstances"))?;
+    let space = LevelSpace::new(bottom.width, bottom.height, bottom.grid_size);
+    let tilemap = level_tilemap(layers, bottom.grid_size, &space, atlases)?;
+    let entities = level_entities(layers, &space);
+    let int_grids = layers
+        .iter()
+        .filter(|layer| !layer.int_grid_csv.is_empty() || !layer.int_grid.is_empty())
+        .map(|layer| (layer.identifier.clone(), int_grid_values(layer, &space)))
+        .collect();
+
+    load_context.set_labeled_asset(&level.identifier, LoadedAsset::new(tilemap));
+    let tilemap_path = AssetPath::new_ref(load_context.path(), Some(&level.identifier));
+    let tilemap = load_context.get_handle(tilemap_path);
+
+    Ok(LdtkLevel {
+        identifier: level.identifier.clone(),
+        world_position: Vec2::new(level.world_x as f32, -level.world_y as f32),
+        tilemap,
+        entities,
+        int_grids,
+    })
+}
+
+/// Loads a project and all of its labeled assets.
+fn load_project(bytes: &[u8], load_context: &mut LoadContext) -> LoaderResult<()> {
+    let project: ProjectJson =
+        ::serde_json::from_slice(bytes).map_err(|e| ErrorKind::Parse(e.to_string()))?;
+    let project_dir = load_context
+        .path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut atlases = HashMap::default();
+    for tileset in &project.defs.tilesets {
+        let rel_path = match &tileset.rel_path {
+            Some(rel_path) => rel_path,
+            None => continue,
+        };
+        let texture_path = AssetPath::new(project_dir.join(rel_path), None);
+        let texture = load_context.get_handle(texture_path.clone());
+        let atlas = grid_texture_atlas(
+            texture,
+            (tileset.px_wid, tileset.px_hei),
+            (tileset.tile_grid_size, tileset.tile_grid_size),
+            tileset.padding,
+            tileset.spacing,
+            0,
+            0,
+        );
+        let label = format!("{}{}", ATLAS_LABEL_PREFIX, tileset.identifier);
+        load_context.set_labeled_asset(
+            &label,
+            LoadedAsset::new(atlas).with_dependency(texture_path),
+        );
+        let atlas_path = AssetPath::new_ref(load_context.path(), Some(&label));
+        atlases.insert(tileset.uid, load_context.get_handle(atlas_path));
+    }
+
+    let mut levels = Vec::with_capacity(project.levels.len());
+    for level in &project.levels {
+        levels.push(load_level(level, &atlases, load_context)?);
+    }
+
+    load_context.set_default_asset(LoadedAsset::new(LdtkProject { levels }));
+
+    Ok(())
+}
+
+/// An asset loader for LDtk `ldtk` projects.
+#[derive(Default, Debug)]
+pub struct LdtkProjectLoader;
+
+impl AssetLoader for LdtkProjectLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), ::anyhow::Error>> {
+        Box::pin(async move { Ok(load_project(bytes, load_context)?) })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ldtk"]
+    }
+}
+
+/// Sends an event for every entity instance of a loaded project.
+pub(crate) fn ldtk_entity_events(
+    mut project_reader: Local<EventReader<AssetEvent<LdtkProject>>>,
+    project_events: Res<Events<AssetEvent<LdtkProject>>>,
+    projects: Res<Assets<LdtkProject>>,
+    mut entity_events: ResMut<Events<LdtkEntityEvent>>,
+) {
+    for event in project_reader.iter(&project_events) {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        let project = match projects.get(handle) {
+            Some(project) => project,
+            None => continue,
+        };
+        for level in &project.levels {
+            for entity in &level.entities {
+                entity_events.send(LdtkEntityEvent {
+                    project: handle.clone_weak(),
+                    level: level.identifier.clone(),
+                    entity: entity.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::RawTile;
+
+    /// A project with a tile layer, the internal icons, an IntGrid layer and
+    /// an entity in a 4x4 level.
+    const PROJECT: &str = include_str!("fixtures/project.ldtk");
+
+    fn project() -> ProjectJson {
+        ::serde_json::from_str(PROJECT).unwrap()
+    }
+
+    /// Returns the atlases of the tilesets with an image, like `load_project`.
+    fn atlases(project: &ProjectJson) -> HashMap<i64, Handle<TextureAtlas>> {
+        project
+            .defs
+            .tilesets
+            .iter()
+            .filter(|tileset| tileset.rel_path.is_some())
+            .map(|tileset| {
+                let handle = Handle::weak(HandleId::random::<TextureAtlas>());
+                (tileset.uid, handle)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_project() {
+        let project = project();
+        let tilesets = &project.defs.tilesets;
+        assert_eq!(tilesets.len(), 2);
+        assert_eq!(tilesets[0].identifier, "Tiles");
+        assert_eq!(tilesets[0].rel_path.as_deref(), Some("tiles.png"));
+        assert_eq!(tilesets[1].identifier, "Internal_Icons");
+        assert_eq!(tilesets[1].rel_path, None);
+
+        let level = &project.levels[0];
+        assert_eq!(level.identifier, "Level_0");
+        assert_eq!((level.world_x, level.world_y), (32, 16));
+        let layers = level.layer_instances.as_ref().unwrap();
+        assert_eq!(layers.len(), 4);
+        let ground = &layers[3];
+        assert_eq!(ground.identifier, "Ground");
+        assert_eq!(ground.tileset_def_uid, Some(1));
+        assert_eq!(ground.opacity, 0.5);
+        assert_eq!(ground.grid_tiles.len(), 3);
+        assert_eq!(ground.grid_tiles[2].px, [48, 48]);
+        assert_eq!(ground.grid_tiles[2].f, 3);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = ::serde_json::from_str::<ProjectJson>(r#"{ "defs": { "tilesets": [] } }"#);
+        assert!(err.is_err());
+        let err = ::serde_json::from_str::<TilesetJson>(r#"{ "uid": 1, "identifier": "Tiles" }"#);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn level_tiles() {
+        let project = project();
+        let atlases = atlases(&project);
+        let layers = project.levels[0].layer_instances.as_ref().unwrap();
+        let space = LevelSpace::new(4, 4, 16);
+        let mut tilemap = level_tilemap(layers, 16, &space, &atlases).unwrap();
+
+        // The icons layer has no atlas and is skipped, IntGrid and entity
+        // layers have no tiles.
+        assert_eq!(tilemap.layer_kinds(), vec![Some(LayerKind::Sparse)]);
+        assert_eq!(tilemap.texture_atlas(), &atlases[&1]);
+        assert_eq!(tilemap.tile_width(), 16);
+
+        let color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+        let tile = |index, flip| RawTile { index, color, flip };
+        assert_eq!(
+            tilemap.get_tile((-2, 2), 0),
+            Some(&tile(0, TileFlip::empty()))
+        );
+        assert_eq!(
+            tilemap.get_tile((-1, 2), 0),
+            Some(&tile(1, TileFlip::HORIZONTAL))
+        );
+        assert_eq!(
+            tilemap.get_tile((1, -1), 0),
+            Some(&tile(1, TileFlip::ROTATE_180))
+        );
+        assert_eq!(tilemap.get_tile((0, 0), 0), None);
+    }
+
+    #[test]
+    fn level_size_errors() {
+        let mut project = project();
+        let atlases = atlases(&project);
+        let layers = project.levels[0].layer_instances.as_mut().unwrap();
+        layers[3].width = -4;
+        let space = LevelSpace::new(4, 4, 16);
+
+        assert_eq!(
+            level_tilemap(layers, 16, &space, &atlases).err(),
+            Some(ErrorKind::InvalidValue("size", "-4x4".to_string()).into())
+        );
+    }
+
+    #[test]
+    fn level_int_grid() {
+        let project = project();
+        let layers = project.levels[0].layer_instances.as_ref().unwrap();
+        let space = LevelSpace::new(4, 4, 16);
+        let values = int_grid_values(&layers[2], &space);
+
+        assert_eq!(values.len(), 5);
+        for x in -2..2 {
+            assert_eq!(values.get(&Point2::new(x, 2)), Some(&1));
+        }
+        assert_eq!(values.get(&Point2::new(0, -1)), Some(&2));
+        assert_eq!(values.get(&Point2::new(0, 0)), None);
+    }
+
+    #[test]
+    fn level_entity() {
+        let project = project();
+        let layers = project.levels[0].layer_instances.as_ref().unwrap();
+        let space = LevelSpace::new(4, 4, 16);
+        let entities = level_entities(layers, &space);
+
+        assert_eq!(entities.len(), 1);
+        let player = &entities[0];
+        assert_eq!(player.identifier, "Player");
+        assert_eq!(player.point, Point2::new(-1, 0));
+        assert_eq!(player.position, Vec2::new(-8.0, 8.0));
+        assert_eq!((player.width, player.height), (16, 16));
+        assert_eq!(player.fields.get("hp"), Some(&Value::from(3)));
+    }
+}
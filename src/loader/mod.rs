@@ -0,0 +1,157 @@
+//! Asset loaders which import tilemaps made with external map editors.
+//!
+//! Each loader is behind its own feature and is registered with the
+//! `AssetServer` by the [`Tilemap2DPlugin`] when enabled. A loaded map is a
+//! regular [`Tilemap`] asset which can be taken out of `Assets<Tilemap>` and
+//! spawned with a [`TilemapBundle`].
+//!
+//! ```toml
+//! [dependencies]
+//! bevy_tilemap = { version = "0.3", features = ["ldtk", "tiled"] }
+//! ```
+//!
+//! [`Tilemap2DPlugin`]: crate::Tilemap2DPlugin
+//! [`Tilemap`]: crate::tilemap::Tilemap
+//! [`TilemapBundle`]: crate::entity::TilemapBundle
+
+#[cfg(feature = "ldtk")]
+pub mod ldtk;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+
+use crate::{lib::*, tilemap::TilemapError};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+/// The kinds of errors that can occur while importing a map.
+pub enum ErrorKind {
+    /// The document could not be parsed.
+    Parse(String),
+    /// A required element is missing from the document.
+    MissingElement(&'static str),
+    /// A required attribute is missing from an element.
+    MissingAttribute(String, &'static str),
+    /// An attribute or value could not be interpreted.
+    InvalidValue(&'static str, String),
+    /// The tile data encoding or compression is not supported.
+    UnsupportedEncoding(String),
+    /// A feature of the map format is not supported.
+    Unsupported(String),
+    /// The imported map could not be constructed.
+    Tilemap(TilemapError),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use ErrorKind::*;
+        match self {
+            Parse(err) => write!(f, "failed to parse the map: {}", err),
+            MissingElement(element) => write!(f, "the `{}` element is missing", element),
+            MissingAttribute(element, attribute) => write!(
+                f,
+                "the `{}` element is missing the `{}` attribute",
+                element, attribute
+            ),
+            InvalidValue(name, value) => write!(f, "`{}` has an invalid value `{}`", name, value),
+            UnsupportedEncoding(encoding) => {
+                write!(f, "the tile data encoding `{}` is not supported", encoding)
+            }
+            Unsupported(feature) => write!(f, "{} is not supported", feature),
+            Tilemap(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for ErrorKind {}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+/// The error type for operations when importing a map.
+pub struct LoaderError(pub Box<ErrorKind>);
+
+impl Display for LoaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for LoaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl From<ErrorKind> for LoaderError {
+    fn from(kind: ErrorKind) -> LoaderError {
+        LoaderError(Box::new(kind))
+    }
+}
+
+impl From<TilemapError> for LoaderError {
+    fn from(err: TilemapError) -> LoaderError {
+        LoaderError(Box::new(ErrorKind::Tilemap(err)))
+    }
+}
+
+/// A loader result.
+pub type LoaderResult<T> = Result<T, LoaderError>;
+
+/// Returns the closest even number that is less than or equal to `n`.
+///
+/// Imported maps are re-centered around the origin. Shifting by an even
+/// amount keeps the parity of rows and columns intact, which is what staggered
+/// hex topologies use to decide their offsets.
+pub(crate) fn floor_even(n: i32) -> i32 {
+    n - n.rem_euclid(2)
+}
+
+/// Returns the amount of tiles in a grid of a map, which can neither be
+/// negative nor overflow.
+pub(crate) fn grid_area(width: i32, height: i32) -> LoaderResult<usize> {
+    let area = if width < 0 || height < 0 {
+        None
+    } else {
+        (width as usize).checked_mul(height as usize)
+    };
+    area.ok_or_else(|| ErrorKind::InvalidValue("size", format!("{}x{}", width, height)).into())
+}
+
+/// Builds a texture atlas out of an image of tiles laid out in a grid.
+///
+/// The `margin` is the space around the grid and the `spacing` the space
+/// between tiles, both in pixels. If `columns` or `tile_count` are zero they
+/// are derived from the image dimensions instead.
+pub(crate) fn grid_texture_atlas(
+    texture: Handle<Texture>,
+    (image_width, image_height): (u32, u32),
+    (tile_width, tile_height): (u32, u32),
+    margin: u32,
+    spacing: u32,
+    columns: u32,
+    tile_count: u32,
+) -> TextureAtlas {
+    let stride_x = (tile_width + spacing).max(1);
+    let stride_y = (tile_height + spacing).max(1);
+    let columns = if columns > 0 {
+        columns
+    } else {
+        ((image_width.saturating_sub(margin * 2) + spacing) / stride_x).max(1)
+    };
+    let tile_count = if tile_count > 0 {
+        tile_count
+    } else {
+        let rows = (image_height.saturating_sub(margin * 2) + spacing) / stride_y;
+        columns * rows
+    };
+
+    let mut atlas =
+        TextureAtlas::new_empty(texture, Vec2::new(image_width as f32, image_height as f32));
+    for index in 0..tile_count {
+        let x = margin + (index % columns) * stride_x;
+        let y = margin + (index / columns) * stride_y;
+        atlas.add_texture(Rect {
+            min: Vec2::new(x as f32, y as f32),
+            max: Vec2::new((x + tile_width) as f32, (y + tile_height) as f32),
+        });
+    }
+
+    atlas
+}
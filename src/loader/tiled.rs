@@ -0,0 +1,982 @@
+//! Imports maps made with the [Tiled](https://www.mapeditor.org/) editor.
+//!
+//! The [`TiledMapLoader`] is registered for the `tmx` extension. Each tile
+//! layer of the map becomes a z layer of the [`Tilemap`] per tileset that its
+//! tiles are from, in the same order as in the editor, layers nested in groups
+//! included. Layers without any tiles are left out. Layers that are at least
+//! half filled by a tileset are imported as dense layers, everything else is
+//! sparse.
+//!
+//! Both finite and infinite maps are supported, as are the `csv`, `base64`,
+//! `base64+zlib`, `base64+gzip` and plain XML tile data encodings. Tilesets
+//! can either be embedded in the map or external `tsx` files, which are loaded
+//! relative to the map.
+//!
+//! Tiled counts rows from the top down while the tilemap counts up, so rows
+//! are flipped on import. Finite maps are centered around the origin, infinite
+//! maps keep their own coordinates, flipped. On isometric maps flipping the
+//! rows swaps the axes, so Tiled columns become the Y axis of the tilemap.
+//!
+//! The loaded [`Tilemap`] is not spawned. Take it out of its assets and spawn
+//! it like any other tilemap.
+//!
+//! ```no_run
+//! use bevy_asset::prelude::*;
+//! use bevy_ecs::prelude::*;
+//! use bevy_tilemap::prelude::*;
+//!
+//! struct Map(Handle<Tilemap>);
+//!
+//! fn setup(commands: &mut Commands, asset_server: Res<AssetServer>) {
+//!     commands.insert_resource(Map(asset_server.load("maps/dungeon.tmx")));
+//! }
+//!
+//! fn spawn_map(
+//!     commands: &mut Commands,
+//!     map: Res<Map>,
+//!     mut tilemaps: ResMut<Assets<Tilemap>>,
+//! ) {
+//!     if let Some(tilemap) = tilemaps.remove(&map.0) {
+//!         commands.spawn(TilemapBundle {
+//!             tilemap,
+//!             transform: Default::default(),
+//!             global_transform: Default::default(),
+//!         });
+//!     }
+//! }
+//! ```
+//!
+//! # Limitations
+//!
+//! * Orthogonal, isometric, staggered and hexagonal maps are supported.
+//!   Hexagonal maps are expected to use a side length of half the tile size
+//!   along the stagger axis, and staggered maps to stagger along the Y axis.
+//! * Tilesets which are a collection of images are not supported, their
+//!   tiles are skipped.
+//! * Object layers and image layers are ignored. Tiles keep their flips, but
+//!   the rotation flags of hexagonal maps are ignored.
+
+use crate::{
+    chunk::LayerKind,
+    lib::*,
+    loader::{floor_even, grid_area, grid_texture_atlas, ErrorKind, LoaderResult},
+    prelude::GridTopology,
+    tile::{Tile, TileAnchor, TileFlip},
+    tilemap::{Tilemap, TilemapLayer},
+};
+use ::flate2::read::{GzDecoder, ZlibDecoder};
+use ::xml::reader::{EventReader, XmlEvent};
+
+/// The label of the texture atlas built from the tilesets of a map.
+const ATLAS_LABEL: &str = "atlas";
+
+/// The bits of a global tile ID which Tiled uses for flipping and rotation.
+const GID_FLAGS: u32 = 0xF000_0000;
+/// The bit of a global tile ID for a horizontal flip.
+const GID_FLIP_HORIZONTAL: u32 = 0x8000_0000;
+/// The bit of a global tile ID for a vertical flip.
+const GID_FLIP_VERTICAL: u32 = 0x4000_0000;
+/// The bit of a global tile ID for an anti-diagonal flip, which is a 60 degree
+/// rotation on hexagonal maps.
+const GID_FLIP_ANTI_DIAGONAL: u32 = 0x2000_0000;
+
+/// A minimal XML element tree, enough for TMX and TSX documents.
+#[derive(Default, Debug)]
+struct Element {
+    /// The local name of the element.
+    name: String,
+    /// The attributes of the element by their local name.
+    attributes: HashMap<String, String>,
+    /// The child elements in document order.
+    children: Vec<Element>,
+    /// All text and CDATA directly within the element.
+    text: String,
+}
+
+impl Element {
+    /// Parses a whole document and returns its root element.
+    fn parse(bytes: &[u8]) -> LoaderResult<Element> {
+        let mut stack: Vec<Element> = Vec::new();
+        let mut root = None;
+        for event in EventReader::new(bytes) {
+            match event.map_err(|e| ErrorKind::Parse(e.to_string()))? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(Element {
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|attribute| (attribute.name.local_name, attribute.value))
+                        .collect(),
+                    ..Default::default()
+                }),
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                XmlEvent::EndElement { .. } => {
+                    if let Some(element) = stack.pop() {
+                        if let Some(parent) = stack.last_mut() {
+                            parent.children.push(element);
+                        } else {
+                            root = Some(element);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        root.ok_or_else(|| ErrorKind::MissingElement("root").into())
+    }
+
+    /// Returns the first child element with the given name.
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Returns all child elements with the given name.
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Returns an attribute if it exists.
+    fn attr_opt(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Returns an attribute or an error if it is missing.
+    fn attr(&self, name: &'static str) -> LoaderResult<&str> {
+        self.attr_opt(name)
+            .ok_or_else(|| ErrorKind::MissingAttribute(self.name.clone(), name).into())
+    }
+
+    /// Parses an attribute which must exist.
+    fn parse_attr<T: FromStr>(&self, name: &'static str) -> LoaderResult<T> {
+        let value = self.attr(name)?;
+        value
+            .parse()
+            .map_err(|_| ErrorKind::InvalidValue(name, value.to_string()).into())
+    }
+
+    /// Parses an attribute or returns the default if it is missing.
+    fn parse_attr_or<T: FromStr>(&self, name: &'static str, default: T) -> LoaderResult<T> {
+        match self.attr_opt(name) {
+            Some(_) => self.parse_attr(name),
+            None => Ok(default),
+        }
+    }
+}
+
+/// The image of a tileset.
+#[derive(Debug)]
+struct TilesetImage {
+    /// The path of the image relative to the asset folder.
+    path: PathBuf,
+    /// The width of the image in pixels.
+    width: u32,
+    /// The height of the image in pixels.
+    height: u32,
+}
+
+/// A tileset used by the map.
+#[derive(Debug)]
+struct Tileset {
+    /// The name of the tileset in the editor.
+    name: String,
+    /// The global tile ID of the first tile in the set.
+    first_gid: u32,
+    /// The width of a tile in pixels.
+    tile_width: u32,
+    /// The height of a tile in pixels.
+    tile_height: u32,
+    /// The pixels between tiles in the image.
+    spacing: u32,
+    /// The pixels around the tiles in the image.
+    margin: u32,
+    /// The amount of tiles in the set.
+    tile_count: u32,
+    /// The amount of tile columns in the image.
+    columns: u32,
+    /// The image of the set, if it is not a collection of images.
+    image: Option<TilesetImage>,
+}
+
+impl Tileset {
+    /// Reads a tileset element, with relative paths resolved from `base`.
+    fn from_element(element: &Element, first_gid: u32, base: &Path) -> LoaderResult<Tileset> {
+        let image = match element.child("image") {
+            Some(image) => Some(TilesetImage {
+                path: base.join(image.attr("source")?),
+                width: image.parse_attr_or("width", 0)?,
+                height: image.parse_attr_or("height", 0)?,
+            }),
+            None => None,
+        };
+
+        Ok(Tileset {
+            name: element.attr_opt("name").unwrap_or_default().to_string(),
+            first_gid,
+            tile_width: element.parse_attr("tilewidth")?,
+            tile_height: element.parse_attr("tileheight")?,
+            spacing: element.parse_attr_or("spacing", 0)?,
+            margin: element.parse_attr_or("margin", 0)?,
+            tile_count: element.parse_attr_or("tilecount", 0)?,
+            columns: element.parse_attr_or("columns", 0)?,
+            image,
+        })
+    }
+
+    /// Builds a texture atlas with a sprite for every tile in the set.
+    fn texture_atlas(&self, image: &TilesetImage, texture: Handle<Texture>) -> TextureAtlas {
+        grid_texture_atlas(
+            texture,
+            (image.width, image.height),
+            (self.tile_width, self.tile_height),
+            self.margin,
+            self.spacing,
+            self.columns,
+            self.tile_count,
+        )
+    }
+}
+
+/// A tile layer of the map, with its tiles in Tiled coordinates.
+#[derive(Debug)]
+struct TileLayer {
+    /// The name of the layer in the editor.
+    name: String,
+    /// The opacity of the layer, including the opacity of its groups.
+    opacity: f32,
+    /// The column, row and global tile ID of every non-empty tile.
+    tiles: Vec<(i32, i32, u32)>,
+    /// The area of the layer in tiles, used to decide the layer kind.
+    area: usize,
+}
+
+impl TileLayer {
+    /// Reads a tile layer element.
+    fn from_element(element: &Element, opacity: f32) -> LoaderResult<TileLayer> {
+        let width: i32 = element.parse_attr("width")?;
+        let height: i32 = element.parse_attr("height")?;
+        let data = element
+            .child("data")
+            .ok_or(ErrorKind::MissingElement("data"))?;
+        let encoding = data.attr_opt("encoding");
+        let compression = data.attr_opt("compression");
+
+        let mut tiles = Vec::new();
+        let mut area = 0;
+        let mut chunks = data.children_named("chunk").peekable();
+        if chunks.peek().is_none() {
+            area = grid_area(width, height)?;
+            let gids = decode_gids(data, encoding, compression, area)?;
+            push_tiles(&mut tiles, &gids, 0, 0, width);
+        } else {
+            for chunk in chunks {
+                let x: i32 = chunk.parse_attr("x")?;
+                let y: i32 = chunk.parse_attr("y")?;
+                let chunk_width: i32 = chunk.parse_attr("width")?;
+                let chunk_height: i32 = chunk.parse_attr("height")?;
+                let chunk_area = grid_area(chunk_width, chunk_height)?;
+                let gids = decode_gids(chunk, encoding, compression, chunk_area)?;
+                push_tiles(&mut tiles, &gids, x, y, chunk_width);
+                area = area.saturating_add(chunk_area);
+            }
+        }
+
+        Ok(TileLayer {
+            name: element.attr_opt("name").unwrap_or_default().to_string(),
+            opacity: opacity * element.parse_attr_or("opacity", 1.0)?,
+            tiles,
+            area,
+        })
+    }
+
+    /// The kind of tilemap layer which suits the layer best.
+    fn kind(&self) -> LayerKind {
+        if self.tiles.len() * 2 >= self.area {
+            LayerKind::Dense
+        } else {
+            LayerKind::Sparse
+        }
+    }
+}
+
+/// Pushes every non-empty tile of a block of global tile IDs.
+fn push_tiles(tiles: &mut Vec<(i32, i32, u32)>, gids: &[u32], x: i32, y: i32, width: i32) {
+    let width = width.max(1) as usize;
+    tiles.extend(
+        gids.iter()
+            .enumerate()
+            .filter(|(_, gid)| **gid & !GID_FLAGS != 0)
+            .map(|(index, gid)| {
+                let col = x + (index % width) as i32;
+                let row = y + (index / width) as i32;
+                (col, row, *gid)
+            }),
+    );
+}
+
+/// Decodes the global tile IDs of a `data` or `chunk` element with an area
+/// of tiles.
+fn decode_gids(
+    element: &Element,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+    area: usize,
+) -> LoaderResult<Vec<u32>> {
+    match encoding {
+        None => element
+            .children_named("tile")
+            .map(|tile| tile.parse_attr_or("gid", 0))
+            .collect(),
+        Some("csv") => element
+            .text
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| ErrorKind::InvalidValue("csv", value.to_string()).into())
+            })
+            .collect(),
+        Some("base64") => {
+            let bytes = ::base64::decode(element.text.trim())
+                .map_err(|e| ErrorKind::Parse(e.to_string()))?;
+            // Every tile is 4 bytes, so the data can not decompress to more.
+            let max_len = (area as u64).saturating_mul(4);
+            let bytes = match compression {
+                None => bytes,
+                Some("zlib") => decompress(ZlibDecoder::new(bytes.as_slice()), max_len)?,
+                Some("gzip") => decompress(GzDecoder::new(bytes.as_slice()), max_len)?,
+                Some(other) => {
+                    return Err(ErrorKind::UnsupportedEncoding(format!("base64+{}", other)).into())
+                }
+            };
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| {
+                    let mut bytes = [0; 4];
+                    bytes.copy_from_slice(gid);
+                    u32::from_le_bytes(bytes)
+                })
+                .collect())
+        }
+        Some(other) => Err(ErrorKind::UnsupportedEncoding(other.to_string()).into()),
+    }
+}
+
+/// Reads a decoder to the end, failing if it has more than `max_len` bytes.
+fn decompress<R: Read>(reader: R, max_len: u64) -> LoaderResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .take(max_len.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(|e| ErrorKind::Parse(e.to_string()))?;
+    if bytes.len() as u64 > max_len {
+        let err = format!("the tile data decompresses to more than {} bytes", max_len);
+        return Err(ErrorKind::Parse(err).into());
+    }
+    Ok(bytes)
+}
+
+/// Splits a layer into a layer per tileset that its tiles are from, in the
+/// order of the tilesets. Tiles which are not from any tileset are dropped.
+fn split_layer<F>(layer: &TileLayer, tileset_count: usize, owner: F) -> Vec<(usize, TileLayer)>
+where
+    F: Fn(u32) -> Option<usize>,
+{
+    let mut tiles = vec![Vec::new(); tileset_count];
+    for tile in &layer.tiles {
+        if let Some(index) = owner(tile.2) {
+            tiles[index].push(*tile);
+        }
+    }
+    tiles
+        .into_iter()
+        .enumerate()
+        .filter(|(_, tiles)| !tiles.is_empty())
+        .map(|(index, tiles)| {
+            let part = TileLayer {
+                name: layer.name.clone(),
+                opacity: layer.opacity,
+                tiles,
+                area: layer.area,
+            };
+            (index, part)
+        })
+        .collect()
+}
+
+/// Collects the tile layers of the map or a group in drawing order.
+fn collect_layers(
+    element: &Element,
+    opacity: f32,
+    layers: &mut Vec<TileLayer>,
+) -> LoaderResult<()> {
+    for child in &element.children {
+        match child.name.as_str() {
+            "layer" => layers.push(TileLayer::from_element(child, opacity)?),
+            "group" => {
+                let opacity = opacity * child.parse_attr_or("opacity", 1.0)?;
+                collect_layers(child, opacity, layers)?;
+            }
+            "objectgroup" | "imagelayer" => {
+                info!(
+                    "skipping the unsupported Tiled {} `{}`",
+                    child.name,
+                    child.attr_opt("name").unwrap_or_default()
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps the orientation of the map to a grid topology.
+fn topology(map: &Element) -> LoaderResult<GridTopology> {
+    match map.attr("orientation")? {
+        "orthogonal" => Ok(GridTopology::Square),
+        "isometric" => Ok(GridTopology::Isometric),
+        "staggered" => match map.attr_opt("staggeraxis").unwrap_or("y") {
+            "y" => Ok(GridTopology::StaggeredIsometric),
+            other => {
+                let value = other.to_string();
+                Err(ErrorKind::InvalidValue("staggeraxis", value).into())
+            }
+        },
+        "hexagonal" => {
+            let axis = map.attr_opt("staggeraxis").unwrap_or("y");
+            let index = map.attr_opt("staggerindex").unwrap_or("odd");
+            match (axis, index) {
+                ("x", "odd") => Ok(GridTopology::HexOddCols),
+                ("x", "even") => Ok(GridTopology::HexEvenCols),
+                ("y", "odd") => Ok(GridTopology::HexOddRows),
+                ("y", "even") => Ok(GridTopology::HexEvenRows),
+                _ => {
+                    let value = format!("{}/{}", axis, index);
+                    Err(ErrorKind::InvalidValue("staggeraxis", value).into())
+                }
+            }
+        }
+        other => Err(ErrorKind::Unsupported(format!("the `{}` orientation", other)).into()),
+    }
+}
+
+/// Returns the flips of a global tile ID.
+///
+/// The anti-diagonal flag of hexagonal maps is a rotation, which is ignored.
+fn tile_flip(gid: u32, topology: GridTopology) -> TileFlip {
+    let mut flip = TileFlip::empty();
+    flip.set(TileFlip::HORIZONTAL, gid & GID_FLIP_HORIZONTAL != 0);
+    flip.set(TileFlip::VERTICAL, gid & GID_FLIP_VERTICAL != 0);
+    flip.set(
+        TileFlip::ANTI_DIAGONAL,
+        !topology.is_hex() && gid & GID_FLIP_ANTI_DIAGONAL != 0,
+    );
+    flip
+}
+
+/// Converts Tiled columns and rows of a map to the tilemap.
+struct MapSpace {
+    /// The topology of the map.
+    topology: GridTopology,
+    /// The offset added to columns.
+    offset_x: i32,
+    /// The offset rows are subtracted from.
+    offset_y: i32,
+    /// The offset added to rows after flipping them.
+    stagger_shift: i32,
+}
+
+impl MapSpace {
+    /// Creates the space of a map element with the given topology.
+    fn new(map: &Element, topology: GridTopology) -> LoaderResult<MapSpace> {
+        let infinite = map.parse_attr_or::<u8>("infinite", 0)? != 0;
+        let (offset_x, offset_y) = if infinite {
+            (0, 0)
+        } else {
+            let width: i32 = map.parse_attr("width")?;
+            let height: i32 = map.parse_attr("height")?;
+            (-floor_even(width / 2), floor_even(height / 2))
+        };
+        // Staggered maps which offset their even rows need those rows to be
+        // odd in the tilemap.
+        let stagger_shift = match (topology, map.attr_opt("staggerindex")) {
+            (GridTopology::StaggeredIsometric, Some("even")) => 1,
+            _ => 0,
+        };
+
+        Ok(MapSpace {
+            topology,
+            offset_x,
+            offset_y,
+            stagger_shift,
+        })
+    }
+
+    /// Returns the tilemap point of a cell.
+    fn point(&self, col: i32, row: i32) -> Point2 {
+        match self.topology {
+            GridTopology::Isometric => Point2::new(self.offset_y - row, -self.offset_x - col),
+            _ => Point2::new(
+                col + self.offset_x,
+                self.offset_y - row + self.stagger_shift,
+            ),
+        }
+    }
+}
+
+/// Loads a map and all of its labeled assets.
+async fn load_map(bytes: &[u8], load_context: &mut LoadContext<'_>) -> LoaderResult<()> {
+    let map = Element::parse(bytes)?;
+    if map.name != "map" {
+        return Err(ErrorKind::MissingElement("map").into());
+    }
+    let topology = topology(&map)?;
+    let space = MapSpace::new(&map, topology)?;
+    let map_dir = load_context
+        .path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut tilesets = Vec::new();
+    for element in map.children_named("tileset") {
+        let first_gid = element.parse_attr("firstgid")?;
+        let tileset = match element.attr_opt("source") {
+            Some(source) => {
+                let path = map_dir.join(source);
+                let bytes = load_context
+                    .read_asset_bytes(&path)
+                    .await
+                    .map_err(|e| ErrorKind::Parse(e.to_string()))?;
+                let tsx = Element::parse(&bytes)?;
+                let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                Tileset::from_element(&tsx, first_gid, &base)?
+            }
+            None => Tileset::from_element(element, first_gid, &map_dir)?,
+        };
+        tilesets.push(tileset);
+    }
+    tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+    // The first tileset with an image is the texture atlas of the tilemap,
+    // the others are the texture atlases of the layers which use them.
+    let mut atlases = Vec::with_capacity(tilesets.len());
+    for tileset in &tilesets {
+        let image = if let Some(image) = &tileset.image {
+            image
+        } else {
+            atlases.push(None);
+            continue;
+        };
+        let label = if atlases.iter().any(Option::is_some) {
+            format!("{}/{}", ATLAS_LABEL, tileset.name)
+        } else {
+            ATLAS_LABEL.to_string()
+        };
+        let texture_path = AssetPath::new(image.path.clone(), None);
+        let texture = load_context.get_handle(texture_path.clone());
+        load_context.set_labeled_asset(
+            &label,
+            LoadedAsset::new(tileset.texture_atlas(image, texture)).with_dependency(texture_path),
+        );
+        let atlas_path = AssetPath::new_ref(load_context.path(), Some(&label));
+        atlases.push(Some(load_context.get_handle::<_, TextureAtlas>(atlas_path)));
+    }
+    let atlas_index = atlases
+        .iter()
+        .position(Option::is_some)
+        .ok_or(ErrorKind::MissingElement("image"))?;
+    let owner = |gid: u32| {
+        let gid = gid & !GID_FLAGS;
+        tilesets
+            .iter()
+            .rposition(|tileset| tileset.first_gid <= gid)
+    };
+
+    let mut layers = Vec::new();
+    collect_layers(&map, 1.0, &mut layers)?;
+    // A tilemap layer has a single texture atlas, so each layer is split into
+    // a layer per tileset that its tiles are from.
+    let mut parts = Vec::new();
+    for layer in &layers {
+        for (tileset_index, part) in split_layer(layer, tilesets.len(), &owner) {
+            if atlases[tileset_index].is_none() {
+                warn!(
+                    "skipped {} tiles in Tiled layer `{}` from the tileset `{}`, which has no single image",
+                    part.tiles.len(),
+                    part.name,
+                    tilesets[tileset_index].name
+                );
+                continue;
+            }
+            parts.push((tileset_index, part));
+        }
+    }
+
+    let mut builder = Tilemap::builder()
+        .topology(topology)
+        .tile_dimensions(map.parse_attr("tilewidth")?, map.parse_attr("tileheight")?)
+        .auto_chunk()
+        .z_layers(parts.len())
+        .texture_atlas(atlases[atlas_index].clone().unwrap_or_default());
+    for (z_order, (tileset_index, layer)) in parts.iter().enumerate() {
+        // Tiled draws tiles which are larger than the grid from the bottom
+        // left corner of their cell.
+        let layer = TilemapLayer {
+            kind: layer.kind(),
+            anchor: TileAnchor::BottomLeft,
+            texture_atlas: if *tileset_index == atlas_index {
+                None
+            } else {
+                atlases[*tileset_index].clone()
+            },
+            ..Default::default()
+        };
+        builder = builder.add_layer(layer, z_order);
+    }
+    let mut tilemap = builder.finish()?;
+
+    for (z_order, (tileset_index, layer)) in parts.iter().enumerate() {
+        let tileset = &tilesets[*tileset_index];
+        let tint = Color::rgba(1.0, 1.0, 1.0, layer.opacity);
+        let tiles = layer.tiles.iter().map(|(col, row, gid)| Tile {
+            point: space.point(*col, *row),
+            z_order,
+            sprite_index: ((gid & !GID_FLAGS) - tileset.first_gid) as usize,
+            tint,
+            flip: tile_flip(*gid, topology),
+        });
+        tilemap.insert_tiles(tiles)?;
+    }
+
+    load_context.set_default_asset(LoadedAsset::new(tilemap));
+
+    Ok(())
+}
+
+/// An asset loader for Tiled `tmx` maps.
+///
+/// The texture atlas built from the first tileset with an image is the one of
+/// the tilemap and is available as a labeled asset with the `atlas` label,
+/// e.g. `maps/dungeon.tmx#atlas`. Those of the other tilesets are the texture
+/// atlases of the layers which use them, labeled `atlas/` followed by the name
+/// of the tileset.
+#[derive(Default, Debug)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), ::anyhow::Error>> {
+        Box::pin(async move { Ok(load_map(bytes, load_context).await?) })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An orthogonal 4x2 map with the same tiles in every tile data encoding,
+    /// the last layer in a group.
+    const MAP: &str = include_str!("fixtures/map.tmx");
+    /// An infinite hexagonal map with its tiles in two chunks.
+    const INFINITE: &str = include_str!("fixtures/infinite.tmx");
+
+    /// The tiles of every layer of `MAP`.
+    const TILES: [(i32, i32, u32); 6] = [
+        (0, 0, 1),
+        (1, 0, GID_FLIP_HORIZONTAL | 2),
+        (3, 0, 3),
+        (1, 1, GID_FLIP_VERTICAL | 4),
+        (2, 1, GID_FLIP_ANTI_DIAGONAL | 1),
+        (
+            3,
+            1,
+            GID_FLIP_HORIZONTAL | GID_FLIP_VERTICAL | GID_FLIP_ANTI_DIAGONAL | 2,
+        ),
+    ];
+
+    fn layers(document: &str) -> Vec<TileLayer> {
+        let map = Element::parse(document.as_bytes()).unwrap();
+        let mut layers = Vec::new();
+        collect_layers(&map, 1.0, &mut layers).unwrap();
+        layers
+    }
+
+    /// Parses an empty map element with the given attributes.
+    fn map(attributes: &str) -> Element {
+        Element::parse(format!("<map {}/>", attributes).as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn decode_encodings() {
+        let layers = layers(MAP);
+        let names: Vec<&str> = layers.iter().map(|layer| layer.name.as_str()).collect();
+        assert_eq!(names, vec!["xml", "csv", "base64", "zlib", "gzip"]);
+        for layer in &layers {
+            assert_eq!(layer.tiles, TILES.to_vec(), "layer `{}`", layer.name);
+            assert_eq!(layer.area, 8);
+            assert_eq!(layer.kind(), LayerKind::Dense);
+        }
+        assert_eq!(layers[0].opacity, 1.0);
+        assert_eq!(layers[4].opacity, 0.25);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let decode = |data: &str| {
+            let data = Element::parse(data.as_bytes()).unwrap();
+            decode_gids(
+                &data,
+                data.attr_opt("encoding"),
+                data.attr_opt("compression"),
+                8,
+            )
+        };
+
+        assert_eq!(
+            decode(r#"<data encoding="csv">1,x</data>"#),
+            Err(ErrorKind::InvalidValue("csv", "x".to_string()).into())
+        );
+        assert_eq!(
+            decode(r#"<data encoding="base64" compression="zstd">AAAAAA==</data>"#),
+            Err(ErrorKind::UnsupportedEncoding("base64+zstd".to_string()).into())
+        );
+        assert_eq!(
+            decode(r#"<data encoding="hex">00000000</data>"#),
+            Err(ErrorKind::UnsupportedEncoding("hex".to_string()).into())
+        );
+        assert!(decode(r#"<data encoding="base64">!!</data>"#).is_err());
+        assert!(decode(r#"<data encoding="base64" compression="zlib">AAAAAA==</data>"#).is_err());
+        assert!(decode(r#"<data encoding="base64" compression="gzip">AAAAAA==</data>"#).is_err());
+
+        assert_eq!(
+            grid_area(-4, 2),
+            Err(ErrorKind::InvalidValue("size", "-4x2".to_string()).into())
+        );
+        assert_eq!(grid_area(4, 2), Ok(8));
+    }
+
+    #[test]
+    fn decompressed_size_is_capped() {
+        let mut encoder = ::flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(&[0; 4 * 9]).unwrap();
+        let data = format!(
+            r#"<data encoding="base64" compression="zlib">{}</data>"#,
+            ::base64::encode(encoder.finish().unwrap())
+        );
+        let data = Element::parse(data.as_bytes()).unwrap();
+
+        let gids = decode_gids(&data, Some("base64"), Some("zlib"), 9).unwrap();
+        assert_eq!(gids, vec![0; 9]);
+        assert!(decode_gids(&data, Some("base64"), Some("zlib"), 8).is_err());
+    }
+
+    #[test]
+    fn layers_split_by_tileset() {
+        let layer = TileLayer {
+            name: "ground".to_string(),
+            opacity: 0.5,
+            tiles: vec![
+                (0, 0, 1),
+                (1, 0, 12),
+                (2, 0, 40),
+                (3, 0, GID_FLIP_HORIZONTAL | 2),
+            ],
+            area: 4,
+        };
+        // The tilesets start at 1 and 10, tiles above 30 are not from either.
+        let owner = |gid: u32| match gid & !GID_FLAGS {
+            1..=9 => Some(0),
+            10..=30 => Some(1),
+            _ => None,
+        };
+
+        let parts = split_layer(&layer, 2, owner);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0, 0);
+        assert_eq!(
+            parts[0].1.tiles,
+            vec![(0, 0, 1), (3, 0, GID_FLIP_HORIZONTAL | 2)]
+        );
+        assert_eq!(parts[0].1.kind(), LayerKind::Dense);
+        assert_eq!(parts[1].0, 1);
+        assert_eq!(parts[1].1.tiles, vec![(1, 0, 12)]);
+        assert_eq!(parts[1].1.kind(), LayerKind::Sparse);
+        assert_eq!(parts[1].1.name, "ground");
+        assert_eq!(parts[1].1.opacity, 0.5);
+
+        assert!(split_layer(&layer, 2, |_| None).is_empty());
+    }
+
+    #[test]
+    fn empty_tiles_keep_flags() {
+        let mut tiles = Vec::new();
+        push_tiles(&mut tiles, &[GID_FLIP_HORIZONTAL, 0, 5, 0], 1, 2, 2);
+        assert_eq!(tiles, vec![(1, 3, 5)]);
+    }
+
+    #[test]
+    fn gid_flips() {
+        let square = |gid: u32| tile_flip(gid | 1, GridTopology::Square);
+        assert_eq!(square(0), TileFlip::empty());
+        assert_eq!(square(GID_FLIP_HORIZONTAL), TileFlip::HORIZONTAL);
+        assert_eq!(square(GID_FLIP_VERTICAL), TileFlip::VERTICAL);
+        assert_eq!(square(GID_FLIP_ANTI_DIAGONAL), TileFlip::ANTI_DIAGONAL);
+        assert_eq!(
+            square(GID_FLIP_ANTI_DIAGONAL | GID_FLIP_HORIZONTAL),
+            TileFlip::ROTATE_90
+        );
+        assert_eq!(
+            square(GID_FLIP_HORIZONTAL | GID_FLIP_VERTICAL),
+            TileFlip::ROTATE_180
+        );
+        assert_eq!(
+            square(GID_FLIP_ANTI_DIAGONAL | GID_FLIP_VERTICAL),
+            TileFlip::ROTATE_270
+        );
+        assert_eq!(
+            tile_flip(GID_FLIP_ANTI_DIAGONAL | 1, GridTopology::Isometric),
+            TileFlip::ANTI_DIAGONAL
+        );
+
+        // The anti-diagonal flag is a rotation on hexagonal maps.
+        let hex = |gid: u32| tile_flip(gid | 1, GridTopology::HexOddRows);
+        assert_eq!(hex(GID_FLIP_ANTI_DIAGONAL), TileFlip::empty());
+        assert_eq!(
+            hex(GID_FLIP_ANTI_DIAGONAL | GID_FLIP_HORIZONTAL),
+            TileFlip::HORIZONTAL
+        );
+    }
+
+    #[test]
+    fn orientations() {
+        let cases = [
+            (r#"orientation="orthogonal""#, GridTopology::Square),
+            (r#"orientation="isometric""#, GridTopology::Isometric),
+            (
+                r#"orientation="staggered""#,
+                GridTopology::StaggeredIsometric,
+            ),
+            (
+                r#"orientation="staggered" staggeraxis="y" staggerindex="even""#,
+                GridTopology::StaggeredIsometric,
+            ),
+            (r#"orientation="hexagonal""#, GridTopology::HexOddRows),
+            (
+                r#"orientation="hexagonal" staggeraxis="y" staggerindex="even""#,
+                GridTopology::HexEvenRows,
+            ),
+            (
+                r#"orientation="hexagonal" staggeraxis="x" staggerindex="odd""#,
+                GridTopology::HexOddCols,
+            ),
+            (
+                r#"orientation="hexagonal" staggeraxis="x" staggerindex="even""#,
+                GridTopology::HexEvenCols,
+            ),
+        ];
+        for (attributes, expected) in &cases {
+            assert_eq!(topology(&map(attributes)), Ok(*expected), "{}", attributes);
+        }
+
+        assert_eq!(
+            topology(&map(r#"orientation="staggered" staggeraxis="x""#)),
+            Err(ErrorKind::InvalidValue("staggeraxis", "x".to_string()).into())
+        );
+        assert_eq!(
+            topology(&map(r#"orientation="hexagonal" staggerindex="both""#)),
+            Err(ErrorKind::InvalidValue("staggeraxis", "y/both".to_string()).into())
+        );
+        assert_eq!(
+            topology(&map(r#"orientation="oblique""#)),
+            Err(ErrorKind::Unsupported("the `oblique` orientation".to_string()).into())
+        );
+        assert_eq!(
+            topology(&map("")),
+            Err(ErrorKind::MissingAttribute("map".to_string(), "orientation").into())
+        );
+    }
+
+    #[test]
+    fn tile_points() {
+        let space = |attributes: &str, topology| MapSpace::new(&map(attributes), topology).unwrap();
+        let size = r#"width="4" height="2""#;
+
+        let square = space(size, GridTopology::Square);
+        assert_eq!(square.point(0, 0), Point2::new(-2, 0));
+        assert_eq!(square.point(3, 1), Point2::new(1, -1));
+
+        // Tiled columns point south-east and rows south-west.
+        let isometric = space(size, GridTopology::Isometric);
+        assert_eq!(isometric.point(0, 0), Point2::new(0, 2));
+        assert_eq!(isometric.point(1, 0), Point2::new(0, 1));
+        assert_eq!(isometric.point(3, 1), Point2::new(-1, -1));
+
+        let staggered = space(size, GridTopology::StaggeredIsometric);
+        assert_eq!(staggered.point(0, 1), Point2::new(-2, -1));
+        let staggered = space(
+            r#"width="4" height="2" staggerindex="even""#,
+            GridTopology::StaggeredIsometric,
+        );
+        assert_eq!(staggered.point(0, 0), Point2::new(-2, 1));
+        assert_eq!(staggered.point(0, 1), Point2::new(-2, 0));
+
+        let hex = space(size, GridTopology::HexEvenCols);
+        assert_eq!(hex.point(3, 1), Point2::new(1, -1));
+
+        assert!(MapSpace::new(&map(""), GridTopology::Square).is_err());
+    }
+
+    #[test]
+    fn infinite_hex_map() {
+        let map = Element::parse(INFINITE.as_bytes()).unwrap();
+        let topology = topology(&map).unwrap();
+        assert_eq!(topology, GridTopology::HexEvenCols);
+
+        let layers = layers(INFINITE);
+        assert_eq!(layers.len(), 1);
+        assert_eq!(
+            layers[0].tiles,
+            vec![
+                (-1, -2, 1),
+                (-2, -1, 2),
+                (0, 0, GID_FLIP_ANTI_DIAGONAL | 1),
+                (1, 1, 4),
+            ]
+        );
+        assert_eq!(layers[0].area, 8);
+        assert_eq!(layers[0].kind(), LayerKind::Dense);
+
+        // Infinite maps keep their coordinates, with the rows flipped.
+        let space = MapSpace::new(&map, topology).unwrap();
+        let tiles: Vec<(Point2, TileFlip)> = layers[0]
+            .tiles
+            .iter()
+            .map(|(col, row, gid)| (space.point(*col, *row), tile_flip(*gid, topology)))
+            .collect();
+        assert_eq!(
+            tiles,
+            vec![
+                (Point2::new(-1, 2), TileFlip::empty()),
+                (Point2::new(-2, 1), TileFlip::empty()),
+                (Point2::new(0, 0), TileFlip::empty()),
+                (Point2::new(1, -1), TileFlip::empty()),
+            ]
+        );
+    }
+}
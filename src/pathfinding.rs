@@ -0,0 +1,502 @@
+//! Pathfinding over the tiles of a tilemap layer.
+//!
+//! Paths are searched on a single z layer with a cost callback which is given
+//! each [`RawTile`] that is walked onto. Returning `None` from the callback
+//! makes the tile impassable, as are points without a tile on the layer.
+//! Every inserted chunk is searched, whether it is spawned or not.
+//!
+//! Steps are taken between the neighbours of the [`GridTopology`] of the
+//! tilemap, which are the 4 edges on square grids and the 6 edges on hex
+//! grids. The cost of a path is the sum of the costs of every tile entered,
+//! which excludes the start and includes the goal.
+//!
+//! For a single agent use [`Tilemap::find_path`], which is an A* search. For
+//! many agents heading to the same goals use [`Tilemap::dijkstra_map`], which
+//! gives every reachable tile its cost and next step towards the closest goal.
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{chunk::RawTile, prelude::*};
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+//! tilemap.insert_chunk((0, 0)).unwrap();
+//!
+//! // Sprite 0 is the floor, sprite 1 is a wall.
+//! let mut tiles = Vec::new();
+//! for y in -3..=3 {
+//!     for x in -3..=3 {
+//!         let sprite_index = if x == 0 && y < 3 { 1 } else { 0 };
+//!         tiles.push(Tile { point: (x, y), sprite_index, ..Default::default() });
+//!     }
+//! }
+//! tilemap.insert_tiles(tiles).unwrap();
+//!
+//! let cost = |_, tile: &RawTile| match tile.index {
+//!     0 => Some(1),
+//!     _ => None,
+//! };
+//!
+//! // The wall is walked around through the gap at the top.
+//! let path = tilemap.find_path((-1, 0), (1, 0), 0, cost).unwrap();
+//! assert_eq!(path.cost(), 8);
+//! assert_eq!(path.points().len(), 9);
+//! ```
+//!
+//! [`RawTile`]: crate::chunk::RawTile
+//! [`Tilemap::find_path`]: crate::tilemap::Tilemap::find_path
+//! [`Tilemap::dijkstra_map`]: crate::tilemap::Tilemap::dijkstra_map
+
+use crate::{chunk::RawTile, lib::*, GridTopology, Tilemap};
+
+/// A path found between two points.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Path {
+    /// The points of the path, from the start to the goal.
+    points: Vec<Point2>,
+    /// The total cost of the path.
+    cost: u32,
+}
+
+impl Path {
+    /// Returns the points of the path, from the start to the goal.
+    ///
+    /// The start is included, so a path to the start itself has a single
+    /// point.
+    pub fn points(&self) -> &[Point2] {
+        &self.points
+    }
+
+    /// Returns the sum of the costs of every tile entered along the path.
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    /// Consumes the path and returns its points.
+    pub fn into_points(self) -> Vec<Point2> {
+        self.points
+    }
+}
+
+/// A Dijkstra map, or flow field, of the costs to reach the closest goal.
+///
+/// Every point that can reach a goal has its cost and the next step towards
+/// that goal. Any number of agents can then follow the steps without
+/// searching again.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DijkstraMap {
+    /// The cost from each reachable point to the closest goal.
+    costs: HashMap<Point2, u32>,
+    /// The next step of each reachable point which is not a goal.
+    steps: HashMap<Point2, Point2>,
+}
+
+impl DijkstraMap {
+    /// Returns the cost to reach the closest goal from a point, or `None` if
+    /// no goal can be reached.
+    ///
+    /// Goals have a cost of `0`.
+    pub fn cost<P: Into<Point2>>(&self, point: P) -> Option<u32> {
+        self.costs.get(&point.into()).copied()
+    }
+
+    /// Returns the neighbour to step onto to get closer to the closest goal.
+    ///
+    /// Returns `None` if the point is a goal or no goal can be reached.
+    pub fn next_step<P: Into<Point2>>(&self, point: P) -> Option<Point2> {
+        self.steps.get(&point.into()).copied()
+    }
+
+    /// Returns the path from a point to the closest goal by following the
+    /// steps, or `None` if no goal can be reached.
+    pub fn path<P: Into<Point2>>(&self, point: P) -> Option<Path> {
+        let mut point = point.into();
+        let cost = self.cost(point)?;
+        let mut points = vec![point];
+        while let Some(next) = self.next_step(point) {
+            points.push(next);
+            point = next;
+        }
+        Some(Path { points, cost })
+    }
+
+    /// Returns an iterator over every point that can reach a goal along with
+    /// its cost.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2, u32)> + '_ {
+        self.costs.iter().map(|(point, cost)| (*point, *cost))
+    }
+
+    /// Returns the number of points that can reach a goal.
+    pub fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    /// Returns `true` if no point can reach a goal.
+    pub fn is_empty(&self) -> bool {
+        self.costs.is_empty()
+    }
+}
+
+impl Tilemap {
+    /// Finds the cheapest path between two points on a layer with A*.
+    ///
+    /// The cost callback is given each tile that is entered and returns its
+    /// cost, or `None` if the tile is impassable. Points without a tile on the
+    /// layer are impassable as well. Costs should be at least `1`, otherwise
+    /// the path may not be the cheapest one.
+    ///
+    /// Returns `None` if the goal can not be reached.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{chunk::RawTile, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// // Sprite 3 is a swamp which takes 5 times as long to cross.
+    /// let tiles = vec![
+    ///     Tile { point: (0, 0), sprite_index: 0, ..Default::default() },
+    ///     Tile { point: (1, 0), sprite_index: 3, ..Default::default() },
+    ///     Tile { point: (2, 0), sprite_index: 0, ..Default::default() },
+    /// ];
+    /// tilemap.insert_tiles(tiles).unwrap();
+    ///
+    /// let cost = |_, tile: &RawTile| if tile.index == 3 { Some(5) } else { Some(1) };
+    /// let path = tilemap.find_path((0, 0), (2, 0), 0, cost).unwrap();
+    ///
+    /// assert_eq!(path.cost(), 6);
+    /// assert!(tilemap.find_path((0, 0), (3, 0), 0, cost).is_none());
+    /// ```
+    pub fn find_path<P, F>(&self, start: P, goal: P, z_order: usize, cost: F) -> Option<Path>
+    where
+        P: Into<Point2>,
+        F: Fn(Point2, &RawTile) -> Option<u32>,
+    {
+        a_star(self.topology(), start.into(), goal.into(), |point| {
+            self.raw_tile(point, z_order)
+                .and_then(|tile| cost(point, tile))
+        })
+    }
+
+    /// Builds a Dijkstra map on a layer, which has the cost and next step
+    /// towards the closest goal for every point that can reach one.
+    ///
+    /// This is meant for many agents that head to the same goals, which then
+    /// only need to follow the steps. The cost callback works the same as in
+    /// [`find_path`], and goals which are impassable are ignored.
+    ///
+    /// [`find_path`]: Tilemap::find_path
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// let mut tiles = Vec::new();
+    /// for x in -5..=5 {
+    ///     tiles.push(Tile { point: (x, 0), ..Default::default() });
+    /// }
+    /// tilemap.insert_tiles(tiles).unwrap();
+    ///
+    /// let map = tilemap.dijkstra_map(vec![(-5, 0), (5, 0)], 0, |_, _| Some(1));
+    ///
+    /// assert_eq!(map.cost((-2, 0)), Some(3));
+    /// assert_eq!(map.cost((3, 0)), Some(2));
+    /// assert_eq!(map.path((3, 0)).unwrap().points().len(), 3);
+    /// assert_eq!(map.cost((0, 1)), None);
+    /// ```
+    pub fn dijkstra_map<I, P, F>(&self, goals: I, z_order: usize, cost: F) -> DijkstraMap
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Point2>,
+        F: Fn(Point2, &RawTile) -> Option<u32>,
+    {
+        let goals = goals.into_iter().map(|goal| goal.into()).collect();
+        dijkstra_map(self.topology(), goals, |point| {
+            self.raw_tile(point, z_order)
+                .and_then(|tile| cost(point, tile))
+        })
+    }
+}
+
+/// Finds the cheapest path between two points with A*.
+///
+/// The heuristic is the grid distance, so costs below `1` may lead to a path
+/// that is not the cheapest.
+pub(crate) fn a_star<F>(
+    topology: GridTopology,
+    start: Point2,
+    goal: Point2,
+    cost: F,
+) -> Option<Path>
+where
+    F: Fn(Point2) -> Option<u32>,
+{
+    let mut open = BinaryHeap::new();
+    let mut costs: HashMap<Point2, u32> = HashMap::default();
+    let mut came_from: HashMap<Point2, Point2> = HashMap::default();
+
+    costs.insert(start, 0);
+    open.push(Reverse((
+        topology.distance(start, goal),
+        0,
+        start.x,
+        start.y,
+    )));
+
+    while let Some(Reverse((_, current_cost, x, y))) = open.pop() {
+        let current = Point2::new(x, y);
+        if current == goal {
+            let mut points = vec![current];
+            let mut point = current;
+            while let Some(previous) = came_from.get(&point) {
+                points.push(*previous);
+                point = *previous;
+            }
+            points.reverse();
+            return Some(Path {
+                points,
+                cost: current_cost,
+            });
+        }
+        // A cheaper way to this point was already expanded.
+        if costs
+            .get(&current)
+            .map_or(false, |best| *best < current_cost)
+        {
+            continue;
+        }
+
+        for neighbour in topology.neighbours(current) {
+            let step = match cost(neighbour) {
+                Some(step) => step,
+                None => continue,
+            };
+            let neighbour_cost = current_cost.saturating_add(step);
+            if costs
+                .get(&neighbour)
+                .map_or(true, |best| neighbour_cost < *best)
+            {
+                costs.insert(neighbour, neighbour_cost);
+                came_from.insert(neighbour, current);
+                let estimate = neighbour_cost.saturating_add(topology.distance(neighbour, goal));
+                open.push(Reverse((
+                    estimate,
+                    neighbour_cost,
+                    neighbour.x,
+                    neighbour.y,
+                )));
+            }
+        }
+    }
+
+    None
+}
+
+/// Builds a Dijkstra map from every passable goal.
+///
+/// Stepping from a point onto a neighbour costs the neighbour, so the cost of
+/// a point is the cost of the path from it to its closest goal.
+pub(crate) fn dijkstra_map<F>(topology: GridTopology, goals: Vec<Point2>, cost: F) -> DijkstraMap
+where
+    F: Fn(Point2) -> Option<u32>,
+{
+    let mut open = BinaryHeap::new();
+    let mut costs: HashMap<Point2, u32> = HashMap::default();
+    let mut steps: HashMap<Point2, Point2> = HashMap::default();
+
+    for goal in goals {
+        if cost(goal).is_some() && costs.insert(goal, 0).is_none() {
+            open.push(Reverse((0, goal.x, goal.y)));
+        }
+    }
+
+    while let Some(Reverse((current_cost, x, y))) = open.pop() {
+        let current = Point2::new(x, y);
+        if costs
+            .get(&current)
+            .map_or(false, |best| *best < current_cost)
+        {
+            continue;
+        }
+        // Only passable points are ever added, so this always has a cost.
+        let step = match cost(current) {
+            Some(step) => step,
+            None => continue,
+        };
+        let neighbour_cost = current_cost.saturating_add(step);
+
+        for neighbour in topology.neighbours(current) {
+            if cost(neighbour).is_none() {
+                continue;
+            }
+            if costs
+                .get(&neighbour)
+                .map_or(true, |best| neighbour_cost < *best)
+            {
+                costs.insert(neighbour, neighbour_cost);
+                steps.insert(neighbour, current);
+                open.push(Reverse((neighbour_cost, neighbour.x, neighbour.y)));
+            }
+        }
+    }
+
+    DijkstraMap { costs, steps }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bounded area with a wall at `x == 0` that has a gap at the top, and
+    /// a swamp at `x == 2` which costs 5.
+    fn cost(point: Point2) -> Option<u32> {
+        if point.x.abs() > 5 || point.y.abs() > 5 || (point.x == 0 && point.y < 4) {
+            None
+        } else if point.x == 2 && point.y.abs() <= 2 {
+            Some(5)
+        } else {
+            Some(1)
+        }
+    }
+
+    /// Checks that a path only steps between neighbours and costs what the
+    /// tiles entered cost.
+    fn assert_valid(topology: GridTopology, path: &Path) {
+        for step in path.points().windows(2) {
+            assert!(topology.neighbours(step[0]).contains(&step[1]));
+        }
+        let total: u32 = path.points()[1..]
+            .iter()
+            .map(|point| cost(*point).unwrap())
+            .sum();
+        assert_eq!(total, path.cost());
+    }
+
+    #[test]
+    fn a_star_is_optimal() {
+        for topology in [GridTopology::Square, GridTopology::HexY].iter() {
+            let goal = Point2::new(4, 0);
+            let map = dijkstra_map(*topology, vec![goal], cost);
+            for y in -5..=5 {
+                for x in -5..=5 {
+                    let start = Point2::new(x, y);
+                    if cost(start).is_none() {
+                        continue;
+                    }
+                    let path = a_star(*topology, start, goal, cost).unwrap();
+                    assert_eq!(path.points().first(), Some(&start));
+                    assert_eq!(path.points().last(), Some(&goal));
+                    assert_eq!(Some(path.cost()), map.cost(start));
+                    assert_valid(*topology, &path);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_star_around_walls() {
+        let path = a_star(
+            GridTopology::Square,
+            Point2::new(-1, 0),
+            Point2::new(1, 0),
+            cost,
+        )
+        .unwrap();
+        // Up to the gap, across and back down.
+        assert_eq!(path.cost(), 10);
+        assert_eq!(path.points().len(), 11);
+
+        let path = a_star(
+            GridTopology::Square,
+            Point2::new(3, 3),
+            Point2::new(3, 3),
+            cost,
+        )
+        .unwrap();
+        assert_eq!(path.points(), &[Point2::new(3, 3)]);
+        assert_eq!(path.cost(), 0);
+    }
+
+    #[test]
+    fn a_star_unreachable() {
+        // The goal is a wall.
+        assert!(a_star(
+            GridTopology::Square,
+            Point2::new(-1, 0),
+            Point2::new(0, 0),
+            cost
+        )
+        .is_none());
+        // The goal is outside of the area.
+        assert!(a_star(
+            GridTopology::Square,
+            Point2::new(-1, 0),
+            Point2::new(9, 0),
+            cost
+        )
+        .is_none());
+        // The goal is walled in.
+        let walled = |point: Point2| {
+            if point == Point2::new(3, 3) {
+                Some(1)
+            } else if (point.x - 3).abs() <= 1 && (point.y - 3).abs() <= 1 {
+                None
+            } else {
+                cost(point)
+            }
+        };
+        assert!(a_star(
+            GridTopology::Square,
+            Point2::new(-3, -3),
+            Point2::new(3, 3),
+            walled
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn dijkstra_map_costs_and_steps() {
+        let map = dijkstra_map(
+            GridTopology::Square,
+            vec![Point2::new(1, -5), Point2::new(0, 0), Point2::new(5, -5)],
+            cost,
+        );
+        // The wall is not a goal, so every point heads to the closest corner.
+        assert_eq!(map.cost((0, 0)), None);
+        assert_eq!(map.cost((1, -5)), Some(0));
+        assert_eq!(map.cost((5, -5)), Some(0));
+        assert_eq!(map.next_step((1, -5)), None);
+        assert_eq!(map.cost((1, -4)), Some(1));
+        assert_eq!(map.next_step((1, -4)), Some(Point2::new(1, -5)));
+        assert_eq!(map.cost((3, -5)), Some(2));
+        // Left of the wall everything goes through the gap at the top.
+        assert_eq!(map.cost((-1, -5)), Some(20));
+        assert_eq!(map.len(), 11 * 11 - 9);
+
+        for (point, point_cost) in map.iter() {
+            let path = map.path(point).unwrap();
+            assert_eq!(path.cost(), point_cost);
+            assert_valid(GridTopology::Square, &path);
+            let goal = path.points().last().unwrap();
+            assert_eq!(map.cost(*goal), Some(0));
+        }
+    }
+}
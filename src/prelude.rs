@@ -11,10 +11,13 @@
 //! default plugins for the library.
 //! * [`bevy_tilemap::entity`]::[`TilemapBundle`], the component bundle
 //! for spawning with a Tilemap.
-//! * [`bevy_tilemap::tile`]::[`Tile`], a sprite tile which
-//! holds minimal amount of data for small data sizes.
+//! * [`bevy_tilemap::tile`]::{[`Tile`], [`TileAnchor`], [`TileFlip`]}, a
+//! sprite tile which holds minimal amount of data for small data sizes, where
+//! sprites are anchored to their cell and how they are flipped.
 //! * [`bevy_tilemap::tilemap`]::{[`Tilemap`], [`TilemapBuilder`]},
 //! the core object that is used for virtually everything in this library.
+//! * [`bevy_tilemap`]::[`GridTopology`], the layout of the tiles in a
+//! tilemap along with the grid algorithms for it.
 //! * [`bevy_tilemap`]::[`Tilemap2DPlugin`], the main plugin with
 //! a collection of systems, components and assets to be used in a Bevy app.
 //!
@@ -29,12 +32,12 @@
 /// Version 0 prelude.
 pub mod v0 {
     pub use crate::{
-        chunk::{render::GridTopology, LayerKind},
+        chunk::LayerKind,
         default_plugin::TilemapDefaultPlugins,
         entity::TilemapBundle,
-        tile::Tile,
+        tile::{Tile, TileAnchor, TileFlip},
         tilemap::{Tilemap, TilemapBuilder, TilemapLayer},
-        Tilemap2DPlugin,
+        GridTopology, Tilemap2DPlugin,
     };
 }
 
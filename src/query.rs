@@ -0,0 +1,683 @@
+//! Kinematic collision queries against the tiles of a tilemap.
+//!
+//! Rays, moving boxes, points and boxes are tested against the grid cells of
+//! the tiles for which a predicate returns `true`, on every layer, without a
+//! physics engine. The predicate is given the point, z order and
+//! [`RawTile`] of each tile, so walls can be told apart by sprite index or
+//! layer. Positions are in pixels relative to the tilemap, like
+//! [`Tilemap::tile_to_world`].
+//!
+//! Every query returns [`TileHit`]s with the point and z order of the tile
+//! and the normal of its surface, which is what a kinematic character
+//! controller needs to stop at or slide along walls:
+//!
+//! * [`Tilemap::raycast`] walks the cells along a ray, with a DDA on square
+//!   grids and across the edges of the cells on hex and isometric grids.
+//! * [`Tilemap::sweep_aabb`] finds the first tile a moving box hits.
+//! * [`Tilemap::overlap_point`] and [`Tilemap::overlap_aabb`] find the tiles
+//!   at a point or in a box, with how far to push out of them.
+//!
+//! To test against the collider shapes of the tiles instead, such as half
+//! blocks and slopes, use [`Tilemap::collide_aabb`].
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_math::Vec2;
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{chunk::RawTile, prelude::*};
+//! use bevy_tilemap_types::point::Point2;
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+//! // Sprite 1 is a wall, from 96 to 128 pixels on the x axis.
+//! let tiles = (0..4).map(|y| Tile { point: (3, y), sprite_index: 1, ..Default::default() });
+//! tilemap.insert_tiles(tiles).unwrap();
+//!
+//! let is_wall = |_, _, tile: &RawTile| tile.index == 1;
+//!
+//! // A box 20 pixels wide walking right stops at the wall.
+//! let (min, max) = (Vec2::new(10.0, 40.0), Vec2::new(30.0, 70.0));
+//! let hit = tilemap.sweep_aabb(min, max, Vec2::new(100.0, 0.0), is_wall).unwrap();
+//! assert_eq!(hit.point, Point2::new(3, 1));
+//! assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+//! assert_eq!(hit.distance, 66.0);
+//! ```
+//!
+//! [`RawTile`]: crate::chunk::RawTile
+//! [`Tilemap::tile_to_world`]: crate::tilemap::Tilemap::tile_to_world
+//! [`Tilemap::raycast`]: crate::tilemap::Tilemap::raycast
+//! [`Tilemap::sweep_aabb`]: crate::tilemap::Tilemap::sweep_aabb
+//! [`Tilemap::overlap_point`]: crate::tilemap::Tilemap::overlap_point
+//! [`Tilemap::overlap_aabb`]: crate::tilemap::Tilemap::overlap_aabb
+//! [`Tilemap::collide_aabb`]: crate::tilemap::Tilemap::collide_aabb
+
+use crate::{
+    chunk::{render::GridTopologyExt, RawTile},
+    lib::*,
+    GridTopology, Tilemap,
+};
+
+/// A tile found by a query.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TileHit {
+    /// The point of the tile.
+    pub point: Point2,
+    /// The z order of the layer of the tile.
+    pub z_order: usize,
+    /// The unit normal of the surface of the tile which was hit, pointing out
+    /// of the tile.
+    ///
+    /// It is zero for a ray which starts inside of the tile.
+    pub normal: Vec2,
+    /// For rays and moving boxes, the distance travelled before the hit in
+    /// pixels. For overlaps, how far to move along the normal to get out of
+    /// the tile.
+    pub distance: f32,
+}
+
+/// How far past an edge a ray looks for the next cell, in pixels.
+const EDGE_NUDGE: f32 = 0.01;
+
+/// Returns the corners of an axis aligned box.
+fn aabb_corners(min: Vec2, max: Vec2) -> [Vec2; 4] {
+    [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+}
+
+/// Returns the lowest and highest distances of the vertices along an axis.
+fn project(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+    let mut low = f32::MAX;
+    let mut high = f32::MIN;
+    for vertex in vertices {
+        let distance = axis.dot(*vertex);
+        low = low.min(distance);
+        high = high.max(distance);
+    }
+    (low, high)
+}
+
+/// Returns the unit axes which may separate a box from a convex polygon, the
+/// ones of the box and the outward normals of the polygon.
+fn separating_axes(polygon: &[Vec2]) -> Vec<Vec2> {
+    let mut axes = vec![Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0)];
+    for (i, start) in polygon.iter().enumerate() {
+        let edge = polygon[(i + 1) % polygon.len()] - *start;
+        if edge.x != 0.0 || edge.y != 0.0 {
+            axes.push(Vec2::new(edge.y, -edge.x).normalize());
+        }
+    }
+    axes
+}
+
+/// Returns how deep a box overlaps a convex polygon and the normal to push it
+/// out along, if they overlap.
+///
+/// A point is a box with the same minimum and maximum.
+fn penetration(min: Vec2, max: Vec2, polygon: &[Vec2]) -> Option<(f32, Vec2)> {
+    if polygon.is_empty() {
+        return None;
+    }
+    let corners = aabb_corners(min, max);
+    let mut depth = f32::MAX;
+    let mut normal = Vec2::new(0.0, 0.0);
+    for axis in separating_axes(polygon) {
+        let (box_low, box_high) = project(&corners, axis);
+        let (low, high) = project(polygon, axis);
+        let (backward, forward) = (box_high - low, high - box_low);
+        if backward <= 0.0 || forward <= 0.0 {
+            return None;
+        }
+        if backward < depth {
+            depth = backward;
+            normal = -axis;
+        }
+        if forward < depth {
+            depth = forward;
+            normal = axis;
+        }
+    }
+    Some((depth, normal))
+}
+
+/// Returns the fraction of the motion at which a moving box first touches a
+/// convex polygon and the normal of the polygon there, if it does.
+///
+/// Polygons which the box already overlaps are not hit, so that it can move
+/// out of them.
+fn sweep(min: Vec2, max: Vec2, motion: Vec2, polygon: &[Vec2]) -> Option<(f32, Vec2)> {
+    if polygon.is_empty() {
+        return None;
+    }
+    let corners = aabb_corners(min, max);
+    let mut enter = f32::MIN;
+    let mut exit = f32::MAX;
+    let mut normal = Vec2::new(0.0, 0.0);
+    for axis in separating_axes(polygon) {
+        let (box_low, box_high) = project(&corners, axis);
+        let (low, high) = project(polygon, axis);
+        let speed = motion.dot(axis);
+        if speed == 0.0 {
+            if box_high <= low || box_low >= high {
+                return None;
+            }
+            continue;
+        }
+        let (start, end, facing) = if speed > 0.0 {
+            ((low - box_high) / speed, (high - box_low) / speed, -axis)
+        } else {
+            ((high - box_low) / speed, (low - box_high) / speed, axis)
+        };
+        if start > enter {
+            enter = start;
+            normal = facing;
+        }
+        exit = exit.min(end);
+    }
+    if enter < 0.0 || enter > 1.0 || enter >= exit {
+        None
+    } else {
+        Some((enter, normal))
+    }
+}
+
+/// Walks the cells along a ray in order, until `hit` returns the z order of a
+/// layer at a point or the ray is longer than `max_distance`.
+///
+/// `locate` returns the point of the tile at a position and `cell` the
+/// polygon of the cell of a point, both in pixels relative to the tilemap.
+fn raycast<L, C, H>(
+    topology: GridTopology,
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    locate: L,
+    cell: C,
+    hit: H,
+) -> Option<TileHit>
+where
+    L: Fn(Vec2) -> Point2,
+    C: Fn(Point2) -> Vec<Vec2>,
+    H: Fn(Point2) -> Option<usize>,
+{
+    if direction.x == 0.0 && direction.y == 0.0 {
+        return None;
+    }
+    let direction = direction.normalize();
+    let start = locate(origin);
+    if topology == GridTopology::Square {
+        grid_raycast(origin, direction, max_distance, start, &cell(start), hit)
+    } else {
+        cell_raycast(origin, direction, max_distance, start, locate, cell, hit)
+    }
+}
+
+/// Walks a square grid along a ray with a DDA, from the cell of the point it
+/// starts in.
+fn grid_raycast<H>(
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    start: Point2,
+    start_cell: &[Vec2],
+    hit: H,
+) -> Option<TileHit>
+where
+    H: Fn(Point2) -> Option<usize>,
+{
+    let (cell_min_x, cell_max_x) = project(start_cell, Vec2::new(1.0, 0.0));
+    let (cell_min_y, cell_max_y) = project(start_cell, Vec2::new(0.0, 1.0));
+    // The distance along the ray to the next column and row, and between
+    // columns and rows.
+    let axis = |position: f32, direction: f32, low: f32, high: f32, size: f32| {
+        if direction > 0.0 {
+            (1, (high - position) / direction, size / direction)
+        } else if direction < 0.0 {
+            (-1, (low - position) / direction, -size / direction)
+        } else {
+            (0, f32::MAX, f32::MAX)
+        }
+    };
+    let (step_x, mut next_x, delta_x) = axis(
+        origin.x,
+        direction.x,
+        cell_min_x,
+        cell_max_x,
+        cell_max_x - cell_min_x,
+    );
+    let (step_y, mut next_y, delta_y) = axis(
+        origin.y,
+        direction.y,
+        cell_min_y,
+        cell_max_y,
+        cell_max_y - cell_min_y,
+    );
+
+    let mut point = start;
+    let mut distance = 0.0;
+    let mut normal = Vec2::new(0.0, 0.0);
+    loop {
+        if let Some(z_order) = hit(point) {
+            return Some(TileHit {
+                point,
+                z_order,
+                normal,
+                distance,
+            });
+        }
+        if next_x < next_y {
+            distance = next_x;
+            next_x += delta_x;
+            point.x += step_x;
+            normal = Vec2::new(-step_x as f32, 0.0);
+        } else {
+            distance = next_y;
+            next_y += delta_y;
+            point.y += step_y;
+            normal = Vec2::new(0.0, -step_y as f32);
+        }
+        if distance > max_distance {
+            return None;
+        }
+    }
+}
+
+/// Walks the cells of any grid along a ray, by leaving each cell through the
+/// edge the ray crosses and looking up the cell on the other side.
+fn cell_raycast<L, C, H>(
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    start: Point2,
+    locate: L,
+    cell: C,
+    hit: H,
+) -> Option<TileHit>
+where
+    L: Fn(Vec2) -> Point2,
+    C: Fn(Point2) -> Vec<Vec2>,
+    H: Fn(Point2) -> Option<usize>,
+{
+    let mut point = start;
+    let mut distance = 0.0;
+    let mut normal = Vec2::new(0.0, 0.0);
+    loop {
+        if let Some(z_order) = hit(point) {
+            return Some(TileHit {
+                point,
+                z_order,
+                normal,
+                distance,
+            });
+        }
+
+        // The ray leaves the cell through the first edge it crosses outwards.
+        let polygon = cell(point);
+        let mut exit = f32::MAX;
+        let mut exit_normal = Vec2::new(0.0, 0.0);
+        for (i, start) in polygon.iter().enumerate() {
+            let edge = polygon[(i + 1) % polygon.len()] - *start;
+            if edge.x == 0.0 && edge.y == 0.0 {
+                continue;
+            }
+            let outward = Vec2::new(edge.y, -edge.x).normalize();
+            let speed = direction.dot(outward);
+            if speed <= 0.0 {
+                continue;
+            }
+            let along = (outward.dot(*start) - outward.dot(origin)) / speed;
+            if along < exit {
+                exit = along;
+                exit_normal = outward;
+            }
+        }
+        // The cells are rounded to whole pixels, so the ray always moves on
+        // in case it did not quite reach the next cell.
+        let exit = exit.max(distance + EDGE_NUDGE);
+        if exit > max_distance {
+            return None;
+        }
+        distance = exit;
+        normal = -exit_normal;
+        let mut nudge = EDGE_NUDGE;
+        let mut next = locate(origin + direction * (exit + nudge));
+        while next == point {
+            nudge *= 2.0;
+            if exit + nudge > max_distance {
+                return None;
+            }
+            next = locate(origin + direction * (exit + nudge));
+        }
+        point = next;
+    }
+}
+
+impl Tilemap {
+    /// Returns the polygon of the grid cell of a tile, in pixels relative to
+    /// the tilemap.
+    fn cell_polygon(&self, point: Point2) -> Vec<Vec2> {
+        let center = self.tile_to_world(point);
+        self.topology()
+            .tile_polygon(self.tile_dimensions())
+            .into_iter()
+            .map(|corner| center + corner)
+            .collect()
+    }
+
+    /// Returns the z orders of the layers with a tile at a point for which
+    /// the predicate is true.
+    fn matching_layers<F>(&self, point: Point2, predicate: &F) -> Vec<usize>
+    where
+        F: Fn(Point2, usize, &RawTile) -> bool,
+    {
+        (0..self.layers().len())
+            .filter(|z_order| {
+                self.raw_tile(point, *z_order)
+                    .map_or(false, |tile| predicate(point, *z_order, tile))
+            })
+            .collect()
+    }
+
+    /// Casts a ray and returns the first tile it hits, on the lowest layer
+    /// for which the predicate is true.
+    ///
+    /// The ray starts at a position in pixels relative to the tilemap, like
+    /// [`tile_to_world`], and the cells of the tiles are walked along it up
+    /// to the maximum distance in pixels, which limits the search even where
+    /// there are no tiles. The distance of the hit is along the direction,
+    /// which does not need to be normalized, and a ray which starts in a
+    /// tile hits it at once with a zero normal.
+    ///
+    /// [`tile_to_world`]: Tilemap::tile_to_world
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_math::Vec2;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    /// use bevy_tilemap_types::point::Point2;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_tile(Tile { point: (3, 0), ..Default::default() }).unwrap();
+    ///
+    /// let origin = Vec2::new(16.0, 16.0);
+    /// let hit = tilemap.raycast(origin, Vec2::new(1.0, 0.0), 500.0, |_, _, _| true).unwrap();
+    ///
+    /// assert_eq!(hit.point, Point2::new(3, 0));
+    /// assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+    /// assert_eq!(hit.distance, 80.0);
+    /// ```
+    pub fn raycast<F>(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        is_solid: F,
+    ) -> Option<TileHit>
+    where
+        F: Fn(Point2, usize, &RawTile) -> bool,
+    {
+        raycast(
+            self.topology(),
+            origin,
+            direction,
+            max_distance,
+            |position| self.position_to_tile(position),
+            |point| self.cell_polygon(point),
+            |point| self.matching_layers(point, &is_solid).into_iter().next(),
+        )
+    }
+
+    /// Moves an axis aligned box and returns the first tile it hits, on the
+    /// lowest layer for which the predicate is true.
+    ///
+    /// The box is given by its minimum and maximum corners in pixels relative
+    /// to the tilemap, like [`tile_to_world`], and is moved by the motion in
+    /// pixels. The distance of the hit is how far the box moves until it
+    /// touches the tile, so it can be moved that far and then slide along the
+    /// normal.
+    ///
+    /// Touching a tile while moving along it is not a hit, and neither are
+    /// the tiles the box already overlaps, so that it can move out of them.
+    /// Those are found with [`overlap_aabb`].
+    ///
+    /// [`tile_to_world`]: Tilemap::tile_to_world
+    /// [`overlap_aabb`]: Tilemap::overlap_aabb
+    pub fn sweep_aabb<F>(&self, min: Vec2, max: Vec2, motion: Vec2, is_solid: F) -> Option<TileHit>
+    where
+        F: Fn(Point2, usize, &RawTile) -> bool,
+    {
+        let length = motion.length();
+        let area_min = min.min(min + motion);
+        let area_max = max.max(max + motion);
+        let mut first: Option<TileHit> = None;
+        for point in self.points_in_area(area_min, area_max) {
+            let z_order = match self.matching_layers(point, &is_solid).first() {
+                Some(z_order) => *z_order,
+                None => continue,
+            };
+            let polygon = self.cell_polygon(point);
+            if let Some((fraction, normal)) = sweep(min, max, motion, &polygon) {
+                let distance = fraction * length;
+                if first.map_or(true, |hit| distance < hit.distance) {
+                    first = Some(TileHit {
+                        point,
+                        z_order,
+                        normal,
+                        distance,
+                    });
+                }
+            }
+        }
+        first
+    }
+
+    /// Returns the tiles at a position in pixels relative to the tilemap, on
+    /// every layer for which the predicate is true.
+    ///
+    /// The normal of each hit points to the closest edge of the cell and the
+    /// distance is how far that edge is. Positions on the edge between two
+    /// cells are in neither of them.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_math::Vec2;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_tile(Tile { point: (0, 0), ..Default::default() }).unwrap();
+    ///
+    /// let hits = tilemap.overlap_point(Vec2::new(20.0, 10.0), |_, _, _| true);
+    ///
+    /// assert_eq!(hits.len(), 1);
+    /// assert_eq!(hits[0].normal, Vec2::new(0.0, -1.0));
+    /// assert_eq!(hits[0].distance, 10.0);
+    /// ```
+    pub fn overlap_point<F>(&self, position: Vec2, is_solid: F) -> Vec<TileHit>
+    where
+        F: Fn(Point2, usize, &RawTile) -> bool,
+    {
+        let point = self.position_to_tile(position);
+        let (distance, normal) = match penetration(position, position, &self.cell_polygon(point)) {
+            Some(penetration) => penetration,
+            None => return Vec::new(),
+        };
+        self.matching_layers(point, &is_solid)
+            .into_iter()
+            .map(|z_order| TileHit {
+                point,
+                z_order,
+                normal,
+                distance,
+            })
+            .collect()
+    }
+
+    /// Returns the tiles which overlap an axis aligned box, on every layer
+    /// for which the predicate is true.
+    ///
+    /// The box is given by its minimum and maximum corners in pixels relative
+    /// to the tilemap, like [`tile_to_world`]. The normal and distance of
+    /// each hit are the shortest way to push the box out of the tile, and
+    /// tiles which only touch the box do not overlap it.
+    ///
+    /// [`tile_to_world`]: Tilemap::tile_to_world
+    pub fn overlap_aabb<F>(&self, min: Vec2, max: Vec2, is_solid: F) -> Vec<TileHit>
+    where
+        F: Fn(Point2, usize, &RawTile) -> bool,
+    {
+        let mut hits = Vec::new();
+        for point in self.points_in_area(min, max) {
+            let layers = self.matching_layers(point, &is_solid);
+            if layers.is_empty() {
+                continue;
+            }
+            if let Some((distance, normal)) = penetration(min, max, &self.cell_polygon(point)) {
+                hits.extend(layers.into_iter().map(|z_order| TileHit {
+                    point,
+                    z_order,
+                    normal,
+                    distance,
+                }));
+            }
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tile::Tile, tilemap::test_builder};
+
+    /// Returns a tilemap with tiles at the points, on the z orders given with
+    /// them.
+    fn query_tilemap(topology: GridTopology, points: &[(i32, i32, usize)]) -> Tilemap {
+        let mut tilemap = test_builder().topology(topology).finish().unwrap();
+        let tiles = points.iter().map(|(x, y, z_order)| Tile {
+            point: (*x, *y),
+            z_order: *z_order,
+            ..Default::default()
+        });
+        tilemap.insert_tiles(tiles).unwrap();
+        tilemap
+    }
+
+    #[test]
+    fn raycast_square() {
+        let tilemap = query_tilemap(GridTopology::Square, &[(3, 0, 0), (0, 2, 1), (-2, -2, 0)]);
+        let any = |_, _, _: &RawTile| true;
+        let origin = Vec2::new(16.0, 16.0);
+
+        let hit = tilemap
+            .raycast(origin, Vec2::new(0.0, 1.0), 200.0, any)
+            .unwrap();
+        let expected = TileHit {
+            point: Point2::new(0, 2),
+            z_order: 1,
+            normal: Vec2::new(0.0, -1.0),
+            distance: 48.0,
+        };
+        assert_eq!(hit, expected);
+
+        // Diagonally through the corners of the cells.
+        let hit = tilemap
+            .raycast(origin, Vec2::new(-1.0, -1.0), 200.0, any)
+            .unwrap();
+        assert_eq!(hit.point, Point2::new(-2, -2));
+
+        assert!(tilemap
+            .raycast(origin, Vec2::new(1.0, 0.0), 60.0, any)
+            .is_none());
+        let only_top = |_, z_order, _: &RawTile| z_order == 1;
+        assert!(tilemap
+            .raycast(origin, Vec2::new(1.0, 0.0), 200.0, only_top)
+            .is_none());
+
+        let inside = tilemap
+            .raycast(Vec2::new(100.0, 16.0), Vec2::new(1.0, 0.0), 200.0, any)
+            .unwrap();
+        let expected = TileHit {
+            point: Point2::new(3, 0),
+            z_order: 0,
+            normal: Vec2::new(0.0, 0.0),
+            distance: 0.0,
+        };
+        assert_eq!(inside, expected);
+    }
+
+    #[test]
+    fn raycast_hex() {
+        for topology in [GridTopology::HexY, GridTopology::HexOddRows].iter() {
+            let tilemap = query_tilemap(*topology, &[(3, 0, 0)]);
+            let origin = tilemap.tile_to_world((0, 0));
+            let direction = tilemap.tile_to_world((3, 0)) - origin;
+            let hit = tilemap
+                .raycast(origin, direction, 200.0, |_, _, _| true)
+                .unwrap();
+            assert_eq!(hit.point, Point2::new(3, 0), "{:?}", topology);
+            assert!(
+                (hit.normal - Vec2::new(-1.0, 0.0)).length() < 0.01,
+                "{:?}",
+                topology
+            );
+            assert!((hit.distance - 80.0).abs() < 0.1, "{:?}", topology);
+        }
+    }
+
+    #[test]
+    fn sweep_aabb_floor() {
+        let floor: Vec<(i32, i32, usize)> = (0..4).map(|x| (x, 0, 0)).collect();
+        let tilemap = query_tilemap(GridTopology::Square, &floor);
+        let any = |_, _, _: &RawTile| true;
+
+        // Falling onto the floor.
+        let (min, max) = (Vec2::new(40.0, 50.0), Vec2::new(60.0, 80.0));
+        let hit = tilemap
+            .sweep_aabb(min, max, Vec2::new(30.0, -60.0), any)
+            .unwrap();
+        assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+        assert!((hit.distance - 0.3 * Vec2::new(30.0, -60.0).length()).abs() < 0.01);
+
+        // Walking along the floor and standing on it.
+        let (min, max) = (Vec2::new(40.0, 32.0), Vec2::new(60.0, 62.0));
+        assert!(tilemap
+            .sweep_aabb(min, max, Vec2::new(50.0, 0.0), any)
+            .is_none());
+        let hit = tilemap
+            .sweep_aabb(min, max, Vec2::new(0.0, -10.0), any)
+            .unwrap();
+        assert!(hit.distance.abs() < 0.01);
+
+        // Already inside of the floor.
+        let (min, max) = (Vec2::new(40.0, 20.0), Vec2::new(60.0, 50.0));
+        assert!(tilemap
+            .sweep_aabb(min, max, Vec2::new(0.0, -10.0), any)
+            .is_none());
+    }
+
+    #[test]
+    fn overlap_aabb_depth() {
+        let tilemap = query_tilemap(GridTopology::Square, &[(0, 0, 0), (1, 0, 0), (1, 0, 1)]);
+        let hits =
+            tilemap.overlap_aabb(Vec2::new(24.0, 26.0), Vec2::new(40.0, 40.0), |_, _, _| true);
+        assert_eq!(hits.len(), 3);
+        for hit in hits {
+            assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+            assert!((hit.distance - 6.0).abs() < 0.01);
+        }
+        assert!(tilemap
+            .overlap_aabb(Vec2::new(0.0, 32.0), Vec2::new(64.0, 40.0), |_, _, _| true)
+            .is_empty());
+    }
+}
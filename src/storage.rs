@@ -0,0 +1,1290 @@
+//! A compact and versioned binary format to save and load tilemaps.
+//!
+//! The serde support of the `serialize` feature is great for small maps, but
+//! it always needs the whole tilemap in memory. The native format is built so
+//! that single chunks can be read from disk on demand, which allows large
+//! worlds to only keep the chunks that are needed in [`Tilemap`].
+//!
+//! # Layout
+//!
+//! All numbers are stored in little endian.
+//!
+//! * **Preamble**: the magic bytes `BTLM`, the format version as a `u16`, the
+//!   flags as a `u16` and the length of the header as a `u32`.
+//! * **Header**: the configuration of the tilemap, followed by the layer table
+//!   with the kind and anchor of each layer, and the chunk index. The chunk
+//!   index contains the point of every chunk and the offset and length of its
+//!   blob.
+//! * **Blobs**: one blob per chunk, with its user data and the tiles of each
+//!   layer, or the fog state of every tile for fog layers. Each tile has its
+//!   sprite index, color and flip flags. Dense layers have every tile, removed
+//!   ones included, sparse layers only the tiles they have. Blobs are deflate
+//!   compressed if the compressed flag is set.
+//!
+//! Chunks are at most [`MAX_CHUNK_SIDE`] tiles wide and high, larger ones are
+//! rejected when loading.
+//!
+//! The texture atlas handles, custom pipelines, fog style, tile data, entities
+//! and meshes are not saved. The texture atlas is given again when loading,
+//! and those of layers are set again with [`Tilemap::set_layer_texture_atlas`].
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::{prelude::*, storage::Compression};
+//! use std::io::Cursor;
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = TilemapBuilder::new()
+//!     .texture_atlas(texture_atlas_handle.clone())
+//!     .tile_dimensions(32, 32)
+//!     .add_layer(TilemapLayer { kind: LayerKind::Sparse, ..Default::default() }, 0)
+//!     .auto_chunk()
+//!     .finish()
+//!     .unwrap();
+//!
+//! tilemap.insert_tile(Tile { point: (100, 100), sprite_index: 3, ..Default::default() }).unwrap();
+//!
+//! let mut bytes = Vec::new();
+//! tilemap.save_to(&mut bytes, Compression::None).unwrap();
+//!
+//! // Only load the structure of the map and the chunk index, without any chunks.
+//! let (mut loaded, index) =
+//!     Tilemap::load_header_from(Cursor::new(&bytes), texture_atlas_handle).unwrap();
+//! assert!(loaded.get_tile((100, 100), 0).is_none());
+//!
+//! // Then stream in the chunk that is needed.
+//! let chunk_point = loaded.point_to_chunk_point((100, 100));
+//! assert!(index.contains(chunk_point));
+//! loaded.load_chunk_from(Cursor::new(&bytes), &index, chunk_point).unwrap();
+//! assert_eq!(loaded.get_tile((100, 100), 0).unwrap().index, 3);
+//! ```
+
+use crate::{
+    chunk::{Chunk, LayerKind, RawTile},
+    fog::FogState,
+    lib::*,
+    prelude::GridTopology,
+    tile::{TileAnchor, TileFlip},
+    tilemap::{ErrorKind as TilemapErrorKind, Tilemap, TilemapError, TilemapLayer, TilemapResult},
+};
+
+/// The magic bytes at the start of every saved tilemap.
+const MAGIC: [u8; 4] = *b"BTLM";
+/// The flag which is set if the chunk blobs are compressed.
+const FLAG_COMPRESSED: u16 = 0b0000_0000_0000_0001;
+/// The length of the preamble in bytes.
+const PREAMBLE_LEN: u64 = 12;
+
+/// The version of the format which is written.
+///
+/// Data saved with a newer version of the format is rejected.
+pub const FORMAT_VERSION: u16 = 1;
+/// The largest width and height of a chunk in tiles which is loaded.
+///
+/// The chunk dimensions are read from the data, and every dense layer of a
+/// chunk is allocated for its whole area.
+pub const MAX_CHUNK_SIDE: u32 = 1024;
+/// The length of a layer in the layer table in bytes.
+const LAYER_LEN: usize = 2;
+/// The length of a chunk in the chunk index in bytes.
+const CHUNK_INDEX_LEN: usize = 24;
+/// The length of a tile in a chunk blob in bytes.
+const TILE_LEN: usize = 25;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+/// The kinds of errors that can occur while saving or loading a tilemap.
+pub enum ErrorKind {
+    /// An I/O error occurred.
+    Io(io::ErrorKind),
+    /// The data does not start with the magic bytes.
+    InvalidMagic,
+    /// The data was saved with a newer version of the format.
+    UnsupportedVersion(u16),
+    /// The data is compressed but the `compression` feature is not enabled.
+    UnsupportedCompression,
+    /// The data is malformed.
+    InvalidData(&'static str),
+    /// The chunk is not in the saved data.
+    MissingChunk(Point2),
+    /// The tilemap could not be constructed.
+    Tilemap(TilemapError),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use ErrorKind::*;
+        match self {
+            Io(kind) => write!(f, "an I/O error occurred: {:?}", kind),
+            InvalidMagic => write!(f, "the data is not a saved tilemap"),
+            UnsupportedVersion(version) => write!(
+                f,
+                "the format version {} is newer than the supported version {}",
+                version, FORMAT_VERSION
+            ),
+            UnsupportedCompression => write!(
+                f,
+                "the data is compressed, the `compression` feature is required"
+            ),
+            InvalidData(reason) => write!(f, "the data is malformed: {}", reason),
+            MissingChunk(point) => write!(f, "the chunk {} is not in the saved data", point),
+            Tilemap(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for ErrorKind {}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+/// The error type for operations when saving or loading a tilemap.
+pub struct StorageError(pub Box<ErrorKind>);
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl From<ErrorKind> for StorageError {
+    fn from(kind: ErrorKind) -> StorageError {
+        StorageError(Box::new(kind))
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> StorageError {
+        StorageError(Box::new(ErrorKind::Io(err.kind())))
+    }
+}
+
+impl From<TilemapError> for StorageError {
+    fn from(err: TilemapError) -> StorageError {
+        StorageError(Box::new(ErrorKind::Tilemap(err)))
+    }
+}
+
+/// A storage result.
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// The compression used for chunk blobs.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Compression {
+    /// The chunks are stored as they are.
+    None,
+    /// The chunks are deflate compressed.
+    #[cfg(feature = "compression")]
+    Deflate,
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::None
+    }
+}
+
+/// Appends little endian values to a buffer.
+#[derive(Default)]
+struct Encoder {
+    /// The encoded bytes.
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    /// Appends a `u8`.
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Appends a `u16`.
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends a `u32`.
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends a `u64`.
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends a `u128`.
+    fn u128(&mut self, value: u128) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends an `i32`.
+    fn i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends an `f32`.
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Appends an optional dimension.
+    fn dimension(&mut self, dimension: Option<Dimension2>) {
+        let dimension = dimension.map_or((0, 0, 0), |d| (1, d.width, d.height));
+        self.u8(dimension.0);
+        self.u32(dimension.1);
+        self.u32(dimension.2);
+    }
+}
+
+/// Reads little endian values from a slice.
+struct Decoder<'a> {
+    /// The bytes which have not been read yet.
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// Takes the next bytes of the slice.
+    fn take(&mut self, len: usize) -> StorageResult<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(ErrorKind::InvalidData("unexpected end of data").into());
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    /// Reads a `u8`.
+    fn u8(&mut self) -> StorageResult<u8> {
+        let mut buf = [0; 1];
+        buf.copy_from_slice(self.take(1)?);
+        Ok(u8::from_le_bytes(buf))
+    }
+
+    /// Reads a `u16`.
+    fn u16(&mut self) -> StorageResult<u16> {
+        let mut buf = [0; 2];
+        buf.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// Reads a `u32`.
+    fn u32(&mut self) -> StorageResult<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Reads a `u64`.
+    fn u64(&mut self) -> StorageResult<u64> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads a `u128`.
+    fn u128(&mut self) -> StorageResult<u128> {
+        let mut buf = [0; 16];
+        buf.copy_from_slice(self.take(16)?);
+        Ok(u128::from_le_bytes(buf))
+    }
+
+    /// Reads an `i32`.
+    fn i32(&mut self) -> StorageResult<i32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(i32::from_le_bytes(buf))
+    }
+
+    /// Reads an `f32`.
+    fn f32(&mut self) -> StorageResult<f32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(f32::from_le_bytes(buf))
+    }
+
+    /// Reads the amount of entries which follow, each of a length in bytes,
+    /// which can not be more than the bytes that are left.
+    fn count(&mut self, entry_len: usize) -> StorageResult<usize> {
+        let count = self.u32()? as usize;
+        match count.checked_mul(entry_len) {
+            Some(len) if len <= self.bytes.len() => Ok(count),
+            _ => Err(ErrorKind::InvalidData("more entries than data").into()),
+        }
+    }
+
+    /// Reads an optional dimension.
+    fn dimension(&mut self) -> StorageResult<Option<Dimension2>> {
+        let is_some = self.u8()? != 0;
+        let dimension = Dimension2::new(self.u32()?, self.u32()?);
+        Ok(if is_some { Some(dimension) } else { None })
+    }
+}
+
+/// Encodes a topology as a byte.
+fn topology_to_u8(topology: GridTopology) -> u8 {
+    use GridTopology::*;
+    match topology {
+        Square => 0,
+        HexY => 1,
+        HexX => 2,
+        HexEvenRows => 3,
+        HexOddRows => 4,
+        HexEvenCols => 5,
+        HexOddCols => 6,
+        Isometric => 7,
+        StaggeredIsometric => 8,
+    }
+}
+
+/// Decodes a topology from a byte.
+fn topology_from_u8(value: u8) -> StorageResult<GridTopology> {
+    use GridTopology::*;
+    Ok(match value {
+        0 => Square,
+        1 => HexY,
+        2 => HexX,
+        3 => HexEvenRows,
+        4 => HexOddRows,
+        5 => HexEvenCols,
+        6 => HexOddCols,
+        7 => Isometric,
+        8 => StaggeredIsometric,
+        _ => return Err(ErrorKind::InvalidData("unknown topology").into()),
+    })
+}
+
+/// Encodes an optional layer kind as a byte.
+fn layer_kind_to_u8(kind: Option<LayerKind>) -> u8 {
+    match kind {
+        None => 0,
+        Some(LayerKind::Dense) => 1,
+        Some(LayerKind::Sparse) => 2,
+        Some(LayerKind::Fog) => 3,
+    }
+}
+
+/// Decodes an optional layer kind from a byte.
+fn layer_kind_from_u8(value: u8) -> StorageResult<Option<LayerKind>> {
+    match value {
+        0 => Ok(None),
+        1 => Ok(Some(LayerKind::Dense)),
+        2 => Ok(Some(LayerKind::Sparse)),
+        3 => Ok(Some(LayerKind::Fog)),
+        _ => Err(ErrorKind::InvalidData("unknown layer kind").into()),
+    }
+}
+
+/// Encodes a tile anchor as a byte.
+fn tile_anchor_to_u8(anchor: TileAnchor) -> u8 {
+    match anchor {
+        TileAnchor::Center => 0,
+        TileAnchor::BottomLeft => 1,
+        TileAnchor::Bottom => 2,
+        TileAnchor::BottomRight => 3,
+        TileAnchor::Left => 4,
+        TileAnchor::Right => 5,
+        TileAnchor::TopLeft => 6,
+        TileAnchor::Top => 7,
+        TileAnchor::TopRight => 8,
+    }
+}
+
+/// Decodes a tile anchor from a byte.
+fn tile_anchor_from_u8(value: u8) -> StorageResult<TileAnchor> {
+    match value {
+        0 => Ok(TileAnchor::Center),
+        1 => Ok(TileAnchor::BottomLeft),
+        2 => Ok(TileAnchor::Bottom),
+        3 => Ok(TileAnchor::BottomRight),
+        4 => Ok(TileAnchor::Left),
+        5 => Ok(TileAnchor::Right),
+        6 => Ok(TileAnchor::TopLeft),
+        7 => Ok(TileAnchor::Top),
+        8 => Ok(TileAnchor::TopRight),
+        _ => Err(ErrorKind::InvalidData("unknown tile anchor").into()),
+    }
+}
+
+/// Encodes a fog state as a byte.
+fn fog_state_to_u8(state: FogState) -> u8 {
+    match state {
+        FogState::Unexplored => 0,
+        FogState::Explored => 1,
+        FogState::Visible => 2,
+    }
+}
+
+/// Decodes a fog state from a byte.
+fn fog_state_from_u8(value: u8) -> StorageResult<FogState> {
+    match value {
+        0 => Ok(FogState::Unexplored),
+        1 => Ok(FogState::Explored),
+        2 => Ok(FogState::Visible),
+        _ => Err(ErrorKind::InvalidData("unknown fog state").into()),
+    }
+}
+
+/// The decoded header of a saved tilemap.
+struct Header {
+    /// The length of the preamble and header in bytes, where the chunk blobs
+    /// start.
+    len: u64,
+    /// If the chunk blobs are compressed.
+    compressed: bool,
+    /// The grid topology.
+    topology: GridTopology,
+    /// The dimensions of the tilemap in chunks.
+    dimensions: Option<Dimension2>,
+    /// The dimensions of a chunk in tiles.
+    chunk_dimensions: Dimension2,
+    /// The dimensions of a tile in pixels.
+    tile_dimensions: Dimension2,
+    /// The raw auto flags.
+    auto_flags: u16,
+    /// The auto spawn dimensions.
+    auto_spawn: Option<Dimension2>,
+    /// The physics scale.
+    #[cfg_attr(not(feature = "bevy_rapier2d"), allow(dead_code))]
+    physics_scale: f32,
+    /// The kind of each layer.
+    layers: Vec<Option<LayerKind>>,
+    /// The anchor of each layer.
+    anchors: Vec<TileAnchor>,
+    /// The point, offset from the end of the header and length of each chunk.
+    chunks: Vec<(Point2, u64, u64)>,
+}
+
+impl Header {
+    /// Reads the preamble and header from a reader.
+    ///
+    /// Afterwards the reader is positioned at the start of the chunk blobs.
+    fn read_from<R: Read>(reader: &mut R) -> StorageResult<Header> {
+        let mut preamble = [0; PREAMBLE_LEN as usize];
+        reader.read_exact(&mut preamble)?;
+        let mut decoder = Decoder { bytes: &preamble };
+        if decoder.take(MAGIC.len())? != MAGIC {
+            return Err(ErrorKind::InvalidMagic.into());
+        }
+        let version = decoder.u16()?;
+        if version > FORMAT_VERSION {
+            return Err(ErrorKind::UnsupportedVersion(version).into());
+        }
+        let flags = decoder.u16()?;
+        let len = decoder.u32()?;
+
+        // The length is not trusted, so only as much is read as there is.
+        let mut bytes = Vec::new();
+        reader
+            .by_ref()
+            .take(u64::from(len))
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != len as usize {
+            return Err(ErrorKind::InvalidData("unexpected end of data").into());
+        }
+        let mut decoder = Decoder { bytes: &bytes };
+        let topology = topology_from_u8(decoder.u8()?)?;
+        let dimensions = decoder.dimension()?;
+        let chunk_dimensions = Dimension2::new(decoder.u32()?, decoder.u32()?);
+        let sides = [chunk_dimensions.width, chunk_dimensions.height];
+        if sides
+            .iter()
+            .any(|side| *side == 0 || *side > MAX_CHUNK_SIDE)
+        {
+            return Err(ErrorKind::InvalidData("invalid chunk dimensions").into());
+        }
+        let tile_dimensions = Dimension2::new(decoder.u32()?, decoder.u32()?);
+        let auto_flags = decoder.u16()?;
+        let auto_spawn = decoder.dimension()?;
+        let physics_scale = decoder.f32()?;
+
+        let layer_count = decoder.count(LAYER_LEN)?;
+        let mut layers = Vec::with_capacity(layer_count);
+        let mut anchors = Vec::with_capacity(layer_count);
+        for _ in 0..layer_count {
+            layers.push(layer_kind_from_u8(decoder.u8()?)?);
+            anchors.push(tile_anchor_from_u8(decoder.u8()?)?);
+        }
+
+        let chunk_count = decoder.count(CHUNK_INDEX_LEN)?;
+        let mut chunks = Vec::with_capacity(chunk_count);
+        for _ in 0..chunk_count {
+            let point = Point2::new(decoder.i32()?, decoder.i32()?);
+            let offset = decoder.u64()?;
+            let len = decoder.u64()?;
+            if offset.checked_add(len).is_none() {
+                return Err(ErrorKind::InvalidData("chunk blob out of bounds").into());
+            }
+            chunks.push((point, offset, len));
+        }
+
+        Ok(Header {
+            len: PREAMBLE_LEN + u64::from(len),
+            compressed: flags & FLAG_COMPRESSED != 0,
+            topology,
+            dimensions,
+            chunk_dimensions,
+            tile_dimensions,
+            auto_flags,
+            auto_spawn,
+            physics_scale,
+            layers,
+            anchors,
+            chunks,
+        })
+    }
+
+    /// Returns the index of the chunks in the header.
+    fn chunk_index(&self) -> ChunkIndex {
+        ChunkIndex {
+            start: self.len,
+            compressed: self.compressed,
+            chunk_dimensions: self.chunk_dimensions,
+            chunks: self
+                .chunks
+                .iter()
+                .map(|(point, offset, len)| (*point, (*offset, *len)))
+                .collect(),
+        }
+    }
+
+    /// Constructs an empty tilemap from the header.
+    fn to_tilemap(&self, texture_atlas: Handle<TextureAtlas>) -> StorageResult<Tilemap> {
+        let mut builder = Tilemap::builder()
+            .topology(self.topology)
+            .chunk_dimensions(self.chunk_dimensions.width, self.chunk_dimensions.height)
+            .tile_dimensions(self.tile_dimensions.width, self.tile_dimensions.height)
+            .z_layers(self.layers.len())
+            .texture_atlas(texture_atlas);
+        if let Some(dimensions) = self.dimensions {
+            builder = builder.dimensions(dimensions.width, dimensions.height);
+        }
+        if let Some(auto_spawn) = self.auto_spawn {
+            builder = builder.auto_spawn(auto_spawn.width, auto_spawn.height);
+        }
+        #[cfg(feature = "bevy_rapier2d")]
+        {
+            builder = builder.physics_scale(self.physics_scale);
+        }
+        for (z_order, (kind, anchor)) in self.layers.iter().zip(&self.anchors).enumerate() {
+            if let Some(kind) = kind {
+                let layer = TilemapLayer {
+                    kind: *kind,
+                    anchor: *anchor,
+                    ..Default::default()
+                };
+                builder = builder.add_layer(layer, z_order);
+            }
+        }
+
+        let mut tilemap = builder.finish()?;
+        tilemap.set_auto_flags(self.auto_flags);
+        Ok(tilemap)
+    }
+}
+
+/// The index of the chunks of a saved tilemap, to load them on demand with
+/// [`Tilemap::load_chunk_from`].
+///
+/// It is read together with the header by [`Tilemap::load_header_from`], so
+/// that loading a chunk only needs to read its blob.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChunkIndex {
+    /// Where the chunk blobs start, in bytes.
+    start: u64,
+    /// If the chunk blobs are compressed.
+    compressed: bool,
+    /// The dimensions of a chunk in tiles.
+    chunk_dimensions: Dimension2,
+    /// The offset from the start of the blobs and the length of each chunk.
+    chunks: HashMap<Point2, (u64, u64)>,
+}
+
+impl ChunkIndex {
+    /// Returns `true` if the chunk is in the saved data.
+    pub fn contains<P: Into<Point2>>(&self, point: P) -> bool {
+        self.chunks.contains_key(&point.into())
+    }
+
+    /// Returns the points of every chunk in the saved data.
+    pub fn points(&self) -> Vec<Point2> {
+        self.chunks.keys().copied().collect()
+    }
+}
+
+/// Returns the most bytes a chunk blob can have once it is decompressed.
+fn max_blob_len(layer_count: usize, dimensions: Dimension2) -> u64 {
+    let layer_len = 5 + dimensions.area() as u64 * TILE_LEN as u64;
+    20 + layer_count as u64 * layer_len
+}
+
+/// Encodes the user data and tiles of a chunk.
+fn encode_chunk(chunk: &Chunk, dimensions: Dimension2) -> Encoder {
+    // Removed tiles of dense layers are transparent.
+    let removed = RawTile {
+        color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+        ..Default::default()
+    };
+    let mut encoder = Encoder::default();
+    encoder.u128(chunk.user_data());
+    encoder.u32(chunk.z_layers() as u32);
+    for z_order in 0..chunk.z_layers() {
+        let kind = chunk.layer_kind(z_order);
+        encoder.u8(layer_kind_to_u8(kind));
+        if kind.is_none() {
+            continue;
+        }
+        if kind == Some(LayerKind::Fog) {
+            let area = dimensions.area() as usize;
+            encoder.u32(area as u32);
+            for index in 0..area {
+                let state = chunk.get_fog_state(z_order, index).unwrap_or_default();
+                encoder.u8(fog_state_to_u8(state));
+            }
+            continue;
+        }
+        // Dense layers keep every tile, so that the data is at least as large
+        // as the layer which is allocated for it.
+        let indices: Vec<usize> = if kind == Some(LayerKind::Dense) {
+            (0..dimensions.area() as usize).collect()
+        } else {
+            let mut indices = chunk.get_tile_indices(z_order).unwrap_or_default();
+            indices.sort_unstable();
+            indices
+        };
+        encoder.u32(indices.len() as u32);
+        for index in indices {
+            let tile = chunk.get_tile(z_order, index).copied().unwrap_or(removed);
+            encoder.u32(index as u32);
+            encoder.u32(tile.index as u32);
+            encoder.f32(tile.color.r());
+            encoder.f32(tile.color.g());
+            encoder.f32(tile.color.b());
+            encoder.f32(tile.color.a());
+            encoder.u8(tile.flip.bits());
+        }
+    }
+    encoder
+}
+
+/// Decodes the user data and tiles of a chunk into a new chunk.
+fn decode_chunk(
+    bytes: &[u8],
+    point: Point2,
+    layers: &[Option<LayerKind>],
+    dimensions: Dimension2,
+) -> StorageResult<Chunk> {
+    // Dense and fog layers are allocated for the whole chunk, which the data
+    // must cover before anything is allocated.
+    let area = dimensions.area() as usize;
+    let min_len = layers.iter().fold(0, |len, kind| match kind {
+        Some(LayerKind::Dense) => len + area * TILE_LEN,
+        Some(LayerKind::Fog) => len + area,
+        _ => len,
+    });
+    if min_len > bytes.len() {
+        return Err(ErrorKind::InvalidData("chunk blob is smaller than its layers").into());
+    }
+    let mut chunk = Chunk::new(point, layers, dimensions);
+    let mut decoder = Decoder { bytes };
+    *chunk.user_data_mut() = decoder.u128()?;
+    let layer_count = decoder.u32()? as usize;
+    for z_order in 0..layer_count {
+        let kind = layer_kind_from_u8(decoder.u8()?)?;
+        if kind.is_none() {
+            continue;
+        }
+        if chunk.layer_kind(z_order).is_none() {
+            return Err(TilemapError::from(TilemapErrorKind::LayerDoesNotExist(z_order)).into());
+        }
+        if kind == Some(LayerKind::Fog) {
+            if chunk.layer_kind(z_order) != Some(LayerKind::Fog) {
+                return Err(ErrorKind::InvalidData("fog states on a layer without fog").into());
+            }
+            let fog_count = decoder.count(1)?;
+            if fog_count != area {
+                return Err(ErrorKind::InvalidData("fog states do not match the chunk").into());
+            }
+            for index in 0..area {
+                let state = fog_state_from_u8(decoder.u8()?)?;
+                chunk.set_fog_state(z_order, index, state);
+            }
+            continue;
+        }
+        let tile_count = decoder.count(TILE_LEN)?;
+        if kind == Some(LayerKind::Dense) && tile_count != area {
+            return Err(ErrorKind::InvalidData("dense tiles do not match the chunk").into());
+        }
+        for _ in 0..tile_count {
+            let index = decoder.u32()? as usize;
+            let sprite_index = decoder.u32()? as usize;
+            let color = Color::rgba(
+                decoder.f32()?,
+                decoder.f32()?,
+                decoder.f32()?,
+                decoder.f32()?,
+            );
+            let flip = TileFlip::from_bits(decoder.u8()?)
+                .ok_or(ErrorKind::InvalidData("unknown tile flip flags"))?;
+            if index >= area {
+                return Err(ErrorKind::InvalidData("tile index out of bounds").into());
+            }
+            chunk.set_raw_tile(
+                z_order,
+                index,
+                RawTile {
+                    index: sprite_index,
+                    color,
+                    flip,
+                },
+            );
+        }
+    }
+
+    Ok(chunk)
+}
+
+/// Compresses a chunk blob.
+fn compress(bytes: Vec<u8>, compression: Compression) -> StorageResult<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(bytes),
+        #[cfg(feature = "compression")]
+        Compression::Deflate => {
+            let mut encoder =
+                ::flate2::write::DeflateEncoder::new(Vec::new(), ::flate2::Compression::default());
+            encoder.write_all(&bytes)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+/// Decompresses a chunk blob if needed, up to a maximum length.
+#[cfg(feature = "compression")]
+fn decompress(bytes: Vec<u8>, compressed: bool, max_len: u64) -> StorageResult<Vec<u8>> {
+    if !compressed {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    ::flate2::read::DeflateDecoder::new(bytes.as_slice())
+        .take(max_len)
+        .read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Returns the chunk blob, compressed blobs are unsupported without the
+/// `compression` feature.
+#[cfg(not(feature = "compression"))]
+fn decompress(bytes: Vec<u8>, compressed: bool, _max_len: u64) -> StorageResult<Vec<u8>> {
+    if compressed {
+        return Err(ErrorKind::UnsupportedCompression.into());
+    }
+    Ok(bytes)
+}
+
+impl Tilemap {
+    /// Saves the tilemap with all of its chunks to a writer.
+    ///
+    /// The texture atlas handles, the tile data, the entities and meshes are
+    /// not saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{prelude::*, storage::Compression};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// assert!(tilemap.save_to(&mut bytes, Compression::None).is_ok());
+    /// ```
+    pub fn save_to<W: Write>(&self, mut writer: W, compression: Compression) -> StorageResult<()> {
+        let mut points: Vec<&Point2> = self.chunks().keys().collect();
+        points.sort();
+
+        let mut blobs = Vec::with_capacity(points.len());
+        for point in &points {
+            if let Some(chunk) = self.get_chunk(point) {
+                blobs.push((
+                    **point,
+                    compress(
+                        encode_chunk(chunk, self.chunk_dimensions()).bytes,
+                        compression,
+                    )?,
+                ));
+            }
+        }
+
+        let mut header = Encoder::default();
+        header.u8(topology_to_u8(self.topology()));
+        header.dimension(self.dimensions());
+        header.u32(self.chunk_dimensions().width);
+        header.u32(self.chunk_dimensions().height);
+        header.u32(self.tile_dimensions().width);
+        header.u32(self.tile_dimensions().height);
+        header.u16(self.auto_flags());
+        header.dimension(self.auto_spawn());
+        #[cfg(feature = "bevy_rapier2d")]
+        header.f32(self.physics_scale());
+        #[cfg(not(feature = "bevy_rapier2d"))]
+        header.f32(1.0);
+        let layers = self.layers();
+        header.u32(layers.len() as u32);
+        for layer in layers {
+            header.u8(layer_kind_to_u8(layer.as_ref().map(|layer| layer.kind)));
+            header.u8(tile_anchor_to_u8(
+                layer.as_ref().map(|layer| layer.anchor).unwrap_or_default(),
+            ));
+        }
+        header.u32(blobs.len() as u32);
+        let mut offset = 0;
+        for (point, blob) in &blobs {
+            header.i32(point.x);
+            header.i32(point.y);
+            header.u64(offset);
+            header.u64(blob.len() as u64);
+            offset += blob.len() as u64;
+        }
+
+        let flags = match compression {
+            Compression::None => 0,
+            #[cfg(feature = "compression")]
+            Compression::Deflate => FLAG_COMPRESSED,
+        };
+        let mut preamble = Encoder::default();
+        preamble.bytes.extend_from_slice(&MAGIC);
+        preamble.u16(FORMAT_VERSION);
+        preamble.u16(flags);
+        preamble.u32(header.bytes.len() as u32);
+
+        writer.write_all(&preamble.bytes)?;
+        writer.write_all(&header.bytes)?;
+        for (_, blob) in &blobs {
+            writer.write_all(blob)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads a tilemap with all of its chunks from a reader.
+    ///
+    /// As the texture atlas handle is not saved, it needs to be given again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails or the data is not a valid saved
+    /// tilemap.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{prelude::*, storage::Compression};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle.clone(), 32, 32);
+    /// tilemap.add_layer(TilemapLayer { kind: LayerKind::Dense, ..Default::default() }, 0).unwrap();
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    /// tilemap.insert_tile(Tile { point: (1, 1), sprite_index: 2, ..Default::default() }).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// tilemap.save_to(&mut bytes, Compression::None).unwrap();
+    ///
+    /// let mut loaded = Tilemap::load_from(bytes.as_slice(), texture_atlas_handle).unwrap();
+    /// assert_eq!(loaded.get_tile((1, 1), 0).unwrap().index, 2);
+    /// ```
+    pub fn load_from<R: Read>(
+        mut reader: R,
+        texture_atlas: Handle<TextureAtlas>,
+    ) -> StorageResult<Tilemap> {
+        let header = Header::read_from(&mut reader)?;
+        let mut tilemap = header.to_tilemap(texture_atlas)?;
+        let mut blobs = Vec::new();
+        reader.read_to_end(&mut blobs)?;
+
+        let layers = tilemap.layer_kinds();
+        let max_len = max_blob_len(layers.len(), header.chunk_dimensions);
+        for (point, offset, len) in &header.chunks {
+            // The header checked that the end does not overflow.
+            let end = offset + len;
+            if end > blobs.len() as u64 {
+                return Err(ErrorKind::InvalidData("chunk blob out of bounds").into());
+            }
+            let blob = blobs[*offset as usize..end as usize].to_vec();
+            let blob = decompress(blob, header.compressed, max_len)?;
+            let chunk = decode_chunk(&blob, *point, &layers, header.chunk_dimensions)?;
+            tilemap.chunks_mut().insert(*point, chunk);
+        }
+
+        Ok(tilemap)
+    }
+
+    /// Loads a tilemap from a reader without any of its chunks, together
+    /// with the index of its chunks.
+    ///
+    /// Chunks can then be loaded on demand with [`load_chunk_from`].
+    ///
+    /// [`load_chunk_from`]: Tilemap::load_chunk_from
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails or the data is not a valid saved
+    /// tilemap.
+    pub fn load_header_from<R: Read>(
+        mut reader: R,
+        texture_atlas: Handle<TextureAtlas>,
+    ) -> StorageResult<(Tilemap, ChunkIndex)> {
+        let header = Header::read_from(&mut reader)?;
+        let tilemap = header.to_tilemap(texture_atlas)?;
+        Ok((tilemap, header.chunk_index()))
+    }
+
+    /// Loads a single chunk from a saved tilemap, using the index which was
+    /// loaded with the header.
+    ///
+    /// Only the chunk itself is read. The chunk is not spawned, which can be
+    /// done with [`spawn_chunk`] or by auto spawning.
+    ///
+    /// [`spawn_chunk`]: Tilemap::spawn_chunk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, if the chunk is not in the saved
+    /// data, if the chunk is already loaded or if the saved chunk dimensions
+    /// differ from the tilemap's.
+    pub fn load_chunk_from<R, P>(
+        &mut self,
+        mut reader: R,
+        index: &ChunkIndex,
+        point: P,
+    ) -> StorageResult<()>
+    where
+        R: Read + Seek,
+        P: Into<Point2>,
+    {
+        let point: Point2 = point.into();
+        if self.chunks().contains_key(&point) {
+            return Err(TilemapError::from(TilemapErrorKind::ChunkAlreadyExists(point)).into());
+        }
+        if index.chunk_dimensions != self.chunk_dimensions() {
+            return Err(ErrorKind::InvalidData("chunk dimensions differ").into());
+        }
+        let (offset, len) = *index
+            .chunks
+            .get(&point)
+            .ok_or(ErrorKind::MissingChunk(point))?;
+
+        let start = index.start.checked_add(offset);
+        let end = start.and_then(|start| start.checked_add(len));
+        let data_len = reader.seek(SeekFrom::End(0))?;
+        let start = match (start, end) {
+            (Some(start), Some(end)) if end <= data_len => start,
+            _ => return Err(ErrorKind::InvalidData("chunk blob out of bounds").into()),
+        };
+        reader.seek(SeekFrom::Start(start))?;
+        let mut blob = vec![0; len as usize];
+        reader.read_exact(&mut blob)?;
+
+        let layers = self.layer_kinds();
+        let max_len = max_blob_len(layers.len(), index.chunk_dimensions);
+        let blob = decompress(blob, index.compressed, max_len)?;
+        let chunk = decode_chunk(&blob, point, &layers, index.chunk_dimensions)?;
+        self.chunks_mut().insert(point, chunk);
+
+        Ok(())
+    }
+
+    /// Unloads a chunk from memory, despawning it if needed.
+    ///
+    /// This is meant to be used together with [`load_chunk_from`] to only keep
+    /// the chunks that are needed in memory. Any changes to the chunk are lost
+    /// unless the tilemap was saved first.
+    ///
+    /// [`load_chunk_from`]: Tilemap::load_chunk_from
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk is not loaded.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// assert!(tilemap.unload_chunk((0, 0)).is_ok());
+    /// assert!(tilemap.unload_chunk((0, 0)).is_err());
+    /// ```
+    pub fn unload_chunk<P: Into<Point2>>(&mut self, point: P) -> TilemapResult<()> {
+        let point: Point2 = point.into();
+        if !self.chunks().contains_key(&point) {
+            return Err(TilemapErrorKind::MissingChunk.into());
+        }
+        self.remove_chunk(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tile::Tile, tilemap::test_builder};
+    use ::std::io::Cursor;
+
+    /// The position of the layer count in the saved data.
+    const LAYER_COUNT_POS: usize = 53;
+
+    fn tilemap() -> Tilemap {
+        let mut tilemap = test_builder()
+            .topology(GridTopology::HexOddRows)
+            .add_layer(
+                TilemapLayer {
+                    anchor: TileAnchor::Bottom,
+                    ..Default::default()
+                },
+                0,
+            )
+            .add_layer(
+                TilemapLayer {
+                    kind: LayerKind::Sparse,
+                    ..Default::default()
+                },
+                1,
+            )
+            .add_layer(
+                TilemapLayer {
+                    kind: LayerKind::Fog,
+                    ..Default::default()
+                },
+                2,
+            )
+            .finish()
+            .unwrap();
+        let tiles = vec![
+            Tile {
+                point: (1, 1),
+                sprite_index: 3,
+                tint: Color::RED,
+                flip: TileFlip::ROTATE_90,
+                ..Default::default()
+            },
+            Tile {
+                point: (9, -3),
+                z_order: 1,
+                sprite_index: 7,
+                ..Default::default()
+            },
+        ];
+        tilemap.insert_tiles(tiles).unwrap();
+        let states = vec![((1, 1), FogState::Visible), ((2, 1), FogState::Explored)];
+        tilemap.set_fog_states(2, states).unwrap();
+        *tilemap
+            .chunks_mut()
+            .get_mut(&Point2::new(0, 0))
+            .unwrap()
+            .user_data_mut() = 42;
+        tilemap
+    }
+
+    fn assert_chunk_0_0(tilemap: &mut Tilemap) {
+        let expected = RawTile {
+            index: 3,
+            color: Color::RED,
+            flip: TileFlip::ROTATE_90,
+        };
+        assert_eq!(tilemap.get_tile((1, 1), 0), Some(&expected));
+        assert_eq!(tilemap.fog_state((1, 1), 2), Some(FogState::Visible));
+        assert_eq!(tilemap.fog_state((2, 1), 2), Some(FogState::Explored));
+        assert_eq!(tilemap.fog_state((3, 1), 2), Some(FogState::Unexplored));
+        let chunk = tilemap.get_chunk(&Point2::new(0, 0)).unwrap();
+        assert_eq!(chunk.user_data(), 42);
+    }
+
+    fn assert_chunk_1_0(tilemap: &mut Tilemap) {
+        assert_eq!(tilemap.get_tile((9, -3), 1).unwrap().index, 7);
+        assert_eq!(tilemap.get_tile((9, -2), 1), None);
+    }
+
+    fn assert_layers(tilemap: &Tilemap) {
+        assert_eq!(tilemap.topology(), GridTopology::HexOddRows);
+        let layers = tilemap.layers();
+        assert_eq!(layers.len(), 5);
+        assert_eq!(layers[0].as_ref().unwrap().anchor, TileAnchor::Bottom);
+        assert_eq!(layers[1].as_ref().unwrap().kind, LayerKind::Sparse);
+        assert_eq!(layers[2].as_ref().unwrap().kind, LayerKind::Fog);
+        assert!(layers[3].is_none());
+    }
+
+    fn saved(compression: Compression) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        tilemap().save_to(&mut bytes, compression).unwrap();
+        bytes
+    }
+
+    fn load(bytes: &[u8]) -> StorageResult<Tilemap> {
+        Tilemap::load_from(bytes, Handle::default())
+    }
+
+    fn assert_round_trip(compression: Compression) {
+        let bytes = saved(compression);
+        let mut loaded = load(&bytes).unwrap();
+        assert_layers(&loaded);
+        assert_eq!(loaded.chunks().len(), 2);
+        assert_chunk_0_0(&mut loaded);
+        assert_chunk_1_0(&mut loaded);
+
+        let (mut streamed, index) =
+            Tilemap::load_header_from(bytes.as_slice(), Handle::default()).unwrap();
+        assert_layers(&streamed);
+        assert!(streamed.chunks().is_empty());
+        assert_eq!(index.points().len(), 2);
+        streamed
+            .load_chunk_from(Cursor::new(&bytes), &index, (1, 0))
+            .unwrap();
+        assert_chunk_1_0(&mut streamed);
+        assert!(streamed.get_tile((1, 1), 0).is_none());
+        streamed
+            .load_chunk_from(Cursor::new(&bytes), &index, (0, 0))
+            .unwrap();
+        assert_chunk_0_0(&mut streamed);
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(Compression::None);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn round_trip_compressed() {
+        assert_eq!(saved(Compression::Deflate)[6], FLAG_COMPRESSED as u8);
+        assert_round_trip(Compression::Deflate);
+    }
+
+    #[test]
+    fn stream_errors() {
+        let bytes = saved(Compression::None);
+        let (mut tilemap, index) =
+            Tilemap::load_header_from(bytes.as_slice(), Handle::default()).unwrap();
+        assert!(!index.contains((5, 5)));
+        assert_eq!(
+            tilemap.load_chunk_from(Cursor::new(&bytes), &index, (5, 5)),
+            Err(ErrorKind::MissingChunk(Point2::new(5, 5)).into())
+        );
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(tilemap
+            .load_chunk_from(Cursor::new(truncated), &index, (1, 0))
+            .is_err());
+        tilemap
+            .load_chunk_from(Cursor::new(&bytes), &index, (1, 0))
+            .unwrap();
+        assert_eq!(
+            tilemap.load_chunk_from(Cursor::new(&bytes), &index, (1, 0)),
+            Err(TilemapError::from(TilemapErrorKind::ChunkAlreadyExists(Point2::new(1, 0))).into())
+        );
+    }
+
+    #[test]
+    fn truncated_data() {
+        let bytes = saved(Compression::None);
+        for len in 0..bytes.len() {
+            assert!(load(&bytes[..len]).is_err(), "truncated to {} bytes", len);
+        }
+    }
+
+    /// Loads saved data with some of its bytes replaced.
+    fn corrupt(bytes: &[u8], pos: usize, value: &[u8]) -> StorageError {
+        let mut bytes = bytes.to_vec();
+        bytes[pos..pos + value.len()].copy_from_slice(value);
+        load(&bytes).unwrap_err()
+    }
+
+    #[test]
+    fn corrupt_data() {
+        let bytes = saved(Compression::None);
+        let invalid = |reason| StorageError::from(ErrorKind::InvalidData(reason));
+
+        assert_eq!(corrupt(&bytes, 0, b"X"), ErrorKind::InvalidMagic.into());
+        assert_eq!(
+            corrupt(&bytes, 4, &2u16.to_le_bytes()),
+            ErrorKind::UnsupportedVersion(2).into()
+        );
+        // Lengths and counts which are larger than the data.
+        assert_eq!(
+            corrupt(&bytes, 8, &u32::MAX.to_le_bytes()),
+            invalid("unexpected end of data")
+        );
+        assert_eq!(
+            corrupt(&bytes, LAYER_COUNT_POS, &u32::MAX.to_le_bytes()),
+            invalid("more entries than data")
+        );
+        let chunk_count_pos = LAYER_COUNT_POS + 4 + 5 * LAYER_LEN;
+        assert_eq!(
+            corrupt(&bytes, chunk_count_pos, &u32::MAX.to_le_bytes()),
+            invalid("more entries than data")
+        );
+        // Blobs which end after the data, or past the largest offset.
+        let offset_pos = chunk_count_pos + 4 + 8;
+        assert_eq!(
+            corrupt(&bytes, offset_pos, &(1u64 << 40).to_le_bytes()),
+            invalid("chunk blob out of bounds")
+        );
+        assert_eq!(
+            corrupt(&bytes, offset_pos, &u64::MAX.to_le_bytes()),
+            invalid("chunk blob out of bounds")
+        );
+        // Chunks without any tiles, or too large to allocate.
+        let chunk_width_pos = 12 + 10;
+        assert_eq!(
+            corrupt(&bytes, chunk_width_pos, &0u32.to_le_bytes()),
+            invalid("invalid chunk dimensions")
+        );
+        let mut huge = 60_000u32.to_le_bytes().to_vec();
+        huge.extend_from_slice(&60_000u32.to_le_bytes());
+        assert_eq!(
+            corrupt(&bytes, chunk_width_pos, &huge),
+            invalid("invalid chunk dimensions")
+        );
+        // Dense layers larger than the blobs of their chunks.
+        let mut large = MAX_CHUNK_SIDE.to_le_bytes().to_vec();
+        large.extend_from_slice(&MAX_CHUNK_SIDE.to_le_bytes());
+        assert_eq!(
+            corrupt(&bytes, chunk_width_pos, &large),
+            invalid("chunk blob is smaller than its layers")
+        );
+    }
+
+    #[test]
+    fn dense_layers_keep_removed_tiles() {
+        let mut tilemap = tilemap();
+        tilemap.clear_tile((1, 1), 0).unwrap();
+        let mut bytes = Vec::new();
+        tilemap.save_to(&mut bytes, Compression::None).unwrap();
+
+        let mut loaded = load(&bytes).unwrap();
+        assert_eq!(loaded.get_tile((1, 1), 0), None);
+        assert_eq!(loaded.get_tile((2, 1), 0), None);
+        assert_chunk_1_0(&mut loaded);
+    }
+}
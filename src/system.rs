@@ -1,16 +1,43 @@
 //! The tilemap systems.
 
-#[cfg(feature = "bevy_rapier2d")]
-use crate::{chunk::Chunk, TilemapLayer};
 use crate::{
+    animation::apply_sprite_frames,
     chunk::{
-        entity::{ChunkBundle, ModifiedLayer, ZOrder},
+        entity::{ChunkBundle, ChunkPoint, ChunkTilemap, ZOrder},
         mesh::ChunkMesh,
-        render::GridTopology,
+        render::GridTopologyExt,
     },
+    collision::{ColliderKind, TileCollider, TileSensors},
+    event::TilemapChunkLayerEvent,
     lib::*,
+    tile::TileAnchor,
     Tilemap,
 };
+#[cfg(feature = "bevy_rapier2d")]
+use crate::{
+    collision::{ChunkCollider, ColliderBackend, ColliderMerge, RapierBackend},
+    TilemapLayer,
+};
+
+/// How the tiles of a layer are turned into colliders.
+#[cfg(feature = "bevy_rapier2d")]
+#[derive(Copy, Clone)]
+struct LayerColliders {
+    /// How the tiles are merged.
+    merge: ColliderMerge,
+    /// The interaction group and its mask.
+    interaction_groups: InteractionGroups,
+}
+
+#[cfg(feature = "bevy_rapier2d")]
+impl From<&TilemapLayer> for LayerColliders {
+    fn from(layer: &TilemapLayer) -> LayerColliders {
+        LayerColliders {
+            merge: layer.collider_merge,
+            interaction_groups: layer.interaction_groups,
+        }
+    }
+}
 
 /// The event handling system for the tilemap.
 ///
@@ -19,26 +46,33 @@ use crate::{
 /// at the time of creation.
 ///
 /// 1. Spawn chunks
-/// 1. Modify chunks
 /// 1. Despawn chunks
+///
+/// A [`TilemapChunkLayerEvent`] is sent for every chunk layer entity spawned
+/// or despawned.
+///
+/// Modified chunks are updated by the chunk update system instead, from the
+/// tiles which changed.
 pub(crate) fn tilemap_events(
     commands: &mut Commands,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut layer_events: ResMut<Events<TilemapChunkLayerEvent>>,
     mut tilemap_query: Query<(Entity, &mut Tilemap)>,
-    mut layer_query: Query<&mut ModifiedLayer>,
+    z_order_query: Query<&ZOrder>,
 ) {
     for (map_entity, mut tilemap) in tilemap_query.iter_mut() {
         tilemap.chunk_events_update();
-        let mut modified_chunks = Vec::new();
+        // Otherwise the collision system updates them as it reads them.
+        #[cfg(not(feature = "bevy_rapier2d"))]
+        tilemap.collision_events_update();
         let mut spawned_chunks = Vec::new();
         let mut despawned_chunks = Vec::new();
+        let mut despawned_layers = Vec::new();
         let mut reader = tilemap.chunk_events().get_reader();
         for event in reader.iter(&tilemap.chunk_events()) {
             use crate::TilemapChunkEvent::*;
             match event {
-                Modified { ref layers } => {
-                    modified_chunks.push(layers.clone());
-                }
+                Modified { .. } => {}
                 Spawned { ref point } => {
                     spawned_chunks.push(*point);
                 }
@@ -48,9 +82,30 @@ pub(crate) fn tilemap_events(
                 } => {
                     despawned_chunks.push((entities.clone(), *point));
                 }
+                LayerDespawned {
+                    ref entity,
+                    ref point,
+                    ref z_order,
+                } => {
+                    despawned_layers.push((*entity, *point, *z_order));
+                }
             }
         }
 
+        // How each layer is rendered, with the texture atlas of the tilemap
+        // for the layers without one of their own.
+        let texture_atlas = tilemap.texture_atlas().clone_weak();
+        let layers: Vec<Option<(TileAnchor, Option<HandleId>, Handle<TextureAtlas>)>> = tilemap
+            .layers()
+            .iter()
+            .map(|layer| {
+                layer.as_ref().map(|layer| {
+                    let layer_atlas = layer.texture_atlas.as_ref().unwrap_or(&texture_atlas);
+                    (layer.anchor, layer.pipeline, layer_atlas.clone_weak())
+                })
+            })
+            .collect();
+
         let capacity = spawned_chunks.len();
         for point in spawned_chunks.into_iter() {
             if tilemap.spawned_chunks().contains(&(point.x, point.y)) {
@@ -59,13 +114,11 @@ pub(crate) fn tilemap_events(
                 tilemap.spawned_chunks_mut().insert((point.x, point.y));
             }
 
-            let layers = tilemap.layers();
-            let layers_len = tilemap.layers().len();
             let chunk_dimensions = tilemap.chunk_dimensions();
             let tile_dimensions = tilemap.tile_dimensions();
-            let texture_atlas = tilemap.texture_atlas().clone_weak();
-            let pipeline_handle = tilemap.topology().to_pipeline_handle();
             let topology = tilemap.topology();
+            let sprite_frames = tilemap.sprite_animation_frames();
+            let fog_style = tilemap.fog_style();
             let chunk = if let Some(chunk) = tilemap.chunks_mut().get_mut(&point) {
                 chunk
             } else {
@@ -73,67 +126,48 @@ pub(crate) fn tilemap_events(
                 continue;
             };
             let mut entities = Vec::with_capacity(capacity);
-            for z_order in 0..layers_len {
-                if layers.get(z_order).is_none() {
+            for (z_order, layer) in layers.iter().enumerate() {
+                let (anchor, pipeline, layer_atlas) = match layer {
+                    Some((anchor, pipeline, layer_atlas)) => (*anchor, *pipeline, layer_atlas),
+                    None => continue,
+                };
+                let mut mesh = Mesh::from(&ChunkMesh::new(
+                    chunk_dimensions,
+                    tile_dimensions,
+                    anchor,
+                    topology,
+                ));
+                let (mut indexes, colors, flips) = if let Some(parts) =
+                    chunk.tiles_to_renderer_parts(z_order, chunk_dimensions, &fog_style)
+                {
+                    parts
+                } else {
+                    warn!("Can not split tiles to data for the renderer");
                     continue;
-                }
-                let mut mesh = Mesh::from(&ChunkMesh::new(chunk_dimensions));
-                let (indexes, colors) =
-                    if let Some(parts) = chunk.tiles_to_renderer_parts(z_order, chunk_dimensions) {
-                        parts
-                    } else {
-                        warn!("Can not split tiles to data for the renderer");
-                        continue;
-                    };
+                };
+                apply_sprite_frames(&sprite_frames, &mut indexes);
+                // The whole mesh is built here, so earlier changes are done.
+                chunk.take_dirty_ranges(z_order, chunk_dimensions.area() as usize);
                 mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_INDEX, indexes);
                 mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_COLOR, colors);
+                mesh.set_attribute(ChunkMesh::ATTRIBUTE_TILE_FLIP, flips);
                 let mesh_handle = meshes.add(mesh);
                 chunk.set_mesh(z_order, mesh_handle.clone());
 
-                use GridTopology::*;
-                let translation_x = match topology {
-                    HexX | HexEvenCols | HexOddCols => {
-                        (((chunk.point().x * tile_dimensions.width as i32) as f32 * 0.75) as i32
-                            * chunk_dimensions.width as i32) as f32
-                    }
-                    HexY => {
-                        (chunk.point().x
-                            * tile_dimensions.width as i32
-                            * chunk_dimensions.width as i32) as f32
-                            + (chunk.point().y as f32 * chunk_dimensions.height as f32 * 0.5)
-                                * tile_dimensions.width as f32
-                    }
-                    Square | HexEvenRows | HexOddRows => {
-                        (chunk.point().x
-                            * tile_dimensions.width as i32
-                            * chunk_dimensions.width as i32) as f32
-                    }
+                let translation = topology
+                    .chunk_translation(chunk.point(), chunk_dimensions, tile_dimensions)
+                    .extend(z_order as f32);
+                let pipeline_handle = match pipeline {
+                    Some(id) => HandleUntyped::weak(id),
+                    None => topology.to_pipeline_handle(),
                 };
-                let translation_y = match topology {
-                    HexX => {
-                        (chunk.point().y
-                            * tile_dimensions.height as i32
-                            * chunk_dimensions.height as i32) as f32
-                            + (chunk.point().x as f32 * chunk_dimensions.width as f32 * 0.5)
-                                * tile_dimensions.height as f32
-                    }
-                    HexY | HexEvenRows | HexOddRows => {
-                        (((chunk.point().y * tile_dimensions.height as i32) as f32 * 0.75) as i32
-                            * chunk_dimensions.height as i32) as f32
-                    }
-                    Square | HexEvenCols | HexOddCols => {
-                        (chunk.point().y
-                            * tile_dimensions.height as i32
-                            * chunk_dimensions.height as i32) as f32
-                    }
-                };
-                let translation = Vec3::new(translation_x, translation_y, z_order as f32);
-                let pipeline = RenderPipeline::new(pipeline_handle.clone_weak().typed());
+                let pipeline = RenderPipeline::new(pipeline_handle.typed());
                 let entity = if let Some(entity) = commands
                     .spawn(ChunkBundle {
-                        point,
+                        point: ChunkPoint(point),
                         z_order: ZOrder(z_order),
-                        texture_atlas: texture_atlas.clone_weak(),
+                        tilemap: ChunkTilemap(map_entity),
+                        texture_atlas: layer_atlas.clone_weak(),
                         mesh: mesh_handle.clone_weak(),
                         transform: Transform::from_translation(translation),
                         render_pipelines: RenderPipelines::from_pipelines(vec![pipeline]),
@@ -146,7 +180,6 @@ pub(crate) fn tilemap_events(
                         },
                         main_pass: MainPass,
                         global_transform: Default::default(),
-                        modified_layer: Default::default(),
                     })
                     .current_entity()
                 {
@@ -162,151 +195,182 @@ pub(crate) fn tilemap_events(
 
                 chunk.add_entity(z_order, entity);
                 entities.push(entity);
+                layer_events.send(TilemapChunkLayerEvent::Spawned {
+                    entity,
+                    tilemap: map_entity,
+                    point,
+                    z_order,
+                });
             }
             commands.push_children(map_entity, &entities);
         }
 
-        for layers in modified_chunks.into_iter() {
-            for (_layer, entity) in layers.into_iter() {
-                let mut modified_layer = if let Ok(layer) = layer_query.get_mut(entity) {
-                    layer
-                } else {
-                    warn!("Chunk layer does not exist, skipping");
-                    continue;
-                };
-                modified_layer.0 += 1;
-            }
-        }
-
         for (entities, point) in despawned_chunks.into_iter() {
             for entity in entities.into_iter() {
+                if let Ok(z_order) = z_order_query.get(entity) {
+                    layer_events.send(TilemapChunkLayerEvent::Despawned {
+                        entity,
+                        tilemap: map_entity,
+                        point,
+                        z_order: z_order.0,
+                    });
+                }
                 commands.despawn_recursive(entity);
             }
             info!("Chunk {} despawned", point);
         }
+
+        for (entity, point, z_order) in despawned_layers.into_iter() {
+            layer_events.send(TilemapChunkLayerEvent::Despawned {
+                entity,
+                tilemap: map_entity,
+                point,
+                z_order,
+            });
+            commands.despawn_recursive(entity);
+            info!("Layer {} of chunk {} despawned", z_order, point);
+        }
     }
 }
 
-/// Spawns collisions based on given arguments.
+/// Spawns a static collider in pixels relative to the tilemap.
 ///
-/// This is a bit messy and has quite a few inputs but, quite a few parts had
-/// to be cloned. There very likely is a better way to clean this up.
+/// The translation is the one of the tilemap in physics units.
 #[cfg(feature = "bevy_rapier2d")]
-fn spawn_collisions(
+fn spawn_collider(
     commands: &mut Commands,
-    layers: &[Option<TilemapLayer>],
-    point: Point2,
+    collider: &ChunkCollider,
+    interaction_groups: InteractionGroups,
+    translation: Vec2,
+    backend: &RapierBackend,
+) -> Option<Entity> {
+    let collider = if let Some(collider) = backend.build_collider(collider) {
+        collider.collision_groups(interaction_groups)
+    } else {
+        error!("Collider shape is degenerate, can not spawn it");
+        return None;
+    };
+    let entity = commands
+        .spawn((
+            RigidBodyBuilder::new_static().translation(translation.x, translation.y),
+            collider,
+        ))
+        .current_entity();
+    if entity.is_none() {
+        error!("Collider entity does not exist unexpectedly, can not run the tilemap system");
+    }
+    entity
+}
+
+/// Updates the colliders of a chunk layer to match its tiles.
+///
+/// Layers which merge their tiles are rebuilt as a whole. Otherwise only the
+/// colliders of the tiles at the indices are spawned again or despawned, or
+/// the ones of every tile without indices.
+#[cfg(feature = "bevy_rapier2d")]
+fn update_collisions(
+    commands: &mut Commands,
+    tilemap: &mut Tilemap,
+    layer: LayerColliders,
+    translation: Vec2,
+    chunk_point: Point2,
     z_order: usize,
-    chunk: &mut Chunk,
-    chunk_dimensions: Dimension2,
-    tile_dimensions: Dimension2,
-    transform: &Transform,
-    physics_tile_width: f32,
-    physics_tile_height: f32,
+    indices: Option<Vec<usize>>,
 ) {
-    // Don't continue if there is no layer.
-    if let Some(layer_opt) = layers.get(z_order) {
-        match layer_opt {
-            Some(layer) => {
-                if layer.interaction_groups.0 == 0 {
-                    return;
-                }
-            }
-            None => return,
-        }
+    if layer.interaction_groups.0 == 0 {
+        return;
     }
-    // Don't continue if there is no entity.
-    let entity = match chunk.get_entity(z_order) {
-        Some(e) => e,
+    let backend = RapierBackend {
+        physics_scale: tilemap.physics_scale(),
+    };
+    let grid = tilemap.chunk_grid(chunk_point);
+    let tiles: Vec<(usize, Option<TileCollider>)> = match (layer.merge, indices) {
+        (ColliderMerge::None, Some(indices)) => indices
+            .into_iter()
+            .map(|index| {
+                let collider = tilemap.chunk_tile_collider(chunk_point, z_order, index);
+                (index, collider)
+            })
+            .collect(),
+        _ => tilemap
+            .chunk_tile_colliders(chunk_point, z_order)
+            .into_iter()
+            .map(|(index, collider)| (index, Some(collider)))
+            .collect(),
+    };
+    let chunk = if let Some(chunk) = tilemap.chunks_mut().get_mut(&chunk_point) {
+        chunk
+    } else {
+        warn!("Can not get chunk at {}, skipping", &chunk_point);
+        return;
+    };
+    // Colliders are only spawned along with their chunk layer.
+    let parent = match chunk.get_entity(z_order) {
+        Some(entity) => entity,
         None => return,
     };
-    // Don't continue if there already is a collision there.
-    let index = chunk_dimensions.encode_point_unchecked(point);
-    if chunk.get_collision_entity(index).is_some() {
+
+    if layer.merge != ColliderMerge::None {
+        for entity in chunk.take_collision_entities(z_order) {
+            commands.despawn(entity);
+        }
+        let tiles: Vec<(usize, TileCollider)> = tiles
+            .into_iter()
+            .filter_map(|(index, collider)| collider.map(|collider| (index, collider)))
+            .collect();
+        let entities: Vec<Entity> = grid
+            .merged_colliders(layer.merge, &tiles)
+            .iter()
+            .filter_map(|collider| {
+                spawn_collider(
+                    commands,
+                    collider,
+                    layer.interaction_groups,
+                    translation,
+                    &backend,
+                )
+            })
+            .collect();
+        commands.push_children(parent, &entities);
+        chunk.set_merged_collision_entities(z_order, entities);
         return;
     }
-    let mut collision_entities = Vec::new();
-    if let Some(indices) = chunk.get_tile_indices(z_order) {
-        for index in &indices {
-            let point = match chunk_dimensions.decode_point(*index) {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("{}", e);
-                    continue;
-                }
-            };
-            // Adjust half a width and height back.
-            let mut x = (point.x - chunk_dimensions.width as i32 / 2) as f32;
-            let mut y = (point.y - chunk_dimensions.height as i32 / 2) as f32;
-            // Adjust by chunk position
-            x += chunk.point().x as f32
-                * chunk_dimensions.width as f32
-                * tile_dimensions.width as f32;
-            y += chunk.point().y as f32
-                * chunk_dimensions.height as f32
-                * tile_dimensions.height as f32;
-            // Add tilemap's translation
-            x += transform.translation.x;
-            y += transform.translation.y;
-
-            if chunk_dimensions.width % 2 == 0 {
-                x += 0.5;
-            }
-            if chunk_dimensions.height % 2 == 0 {
-                y += 0.5;
-            }
-
-            let collision_groups = layers
-                .get(z_order)
-                .and_then(|layer_opt| layer_opt.and_then(|layer| Some(layer.interaction_groups)));
-            if let Some(collision_groups) = collision_groups {
-                if collision_groups.with_mask(0).0 != 0 {
-                    let mut collider = ColliderBuilder::cuboid(
-                        physics_tile_width / 2.0,
-                        physics_tile_height / 2.0,
-                    );
-
-                    collider = collider.collision_groups(collision_groups);
 
-                    let entity = if let Some(entity) = commands
-                        .spawn((
-                            RigidBodyBuilder::new_static()
-                                .translation(x * physics_tile_width, y * physics_tile_height),
-                            collider,
-                        ))
-                        .current_entity()
-                    {
-                        entity
-                    } else {
-                        error!("Collider entity does not exist unexpectedly, can not run the tilemap system");
-                        return;
-                    };
-
-                    collision_entities.push(entity);
-                }
-            }
+    let mut entities = Vec::new();
+    for (index, collider) in tiles {
+        // The collider of a tile may have changed along with its sprite.
+        if let Some(entity) = chunk.remove_collision_entity(z_order, index) {
+            commands.despawn(entity);
         }
-        for (index, entity) in indices.iter().zip(&collision_entities) {
-            chunk.insert_collision_entity(*index, *entity);
+        let collider = match collider.and_then(|collider| grid.tile_collider(index, &collider)) {
+            Some(collider) => collider,
+            None => continue,
+        };
+        if let Some(entity) = spawn_collider(
+            commands,
+            &collider,
+            layer.interaction_groups,
+            translation,
+            &backend,
+        ) {
+            chunk.insert_collision_entity(z_order, index, entity);
+            entities.push(entity);
         }
-        commands.push_children(entity, &collision_entities);
     }
+    commands.push_children(parent, &entities);
 }
 
 /// The event handling system for collisions. Namely spawning and despawning.
 ///
-/// Depending on if a collision needs to be created or not, given a variety of
-/// conditions, collisions are spawned or despawned accordingly.
+/// The colliders of a chunk are spawned with it. After that, the colliders of
+/// the tiles that changed are spawned or despawned, and the layers which merge
+/// their tiles are rebuilt.
 #[cfg(feature = "bevy_rapier2d")]
 pub(crate) fn tilemap_collision_events(
     commands: &mut Commands,
     mut tilemap_query: Query<(&mut Tilemap, &Transform)>,
 ) {
     for (mut tilemap, transform) in tilemap_query.iter_mut() {
-        if tilemap.topology() != GridTopology::Square {
-            error!("collision physics are not supported on hex tiles yet");
-            continue;
-        }
         tilemap.collision_events_update();
         let mut spawned_chunks = Vec::new();
         let mut reader = tilemap.chunk_events().get_reader();
@@ -320,103 +384,123 @@ pub(crate) fn tilemap_collision_events(
             };
         }
 
-        for point in spawned_chunks.into_iter() {
-            let layers = tilemap.layers();
-            let layers_len = tilemap.layers().len();
-            let chunk_dimensions = tilemap.chunk_dimensions();
-            let tile_dimensions = tilemap.tile_dimensions();
-            let physics_tile_width = tile_dimensions.width as f32 / tilemap.physics_scale();
-            let physics_tile_height = tile_dimensions.height as f32 / tilemap.physics_scale();
-            let chunk = if let Some(chunk) = tilemap.chunks_mut().get_mut(&point) {
-                chunk
-            } else {
-                warn!("Can not get chunk at {}, skipping", &point);
-                continue;
-            };
-            for z_order in 0..layers_len {
-                spawn_collisions(
-                    commands,
-                    &layers,
-                    point,
-                    z_order,
-                    chunk,
-                    chunk_dimensions,
-                    tile_dimensions,
-                    transform,
-                    physics_tile_width,
-                    physics_tile_height,
-                );
-            }
-        }
-
-        let mut spawned_collisions = Vec::new();
-        let mut despawned_collisions = Vec::new();
+        // The indices of the changed tiles by chunk point and z order.
+        let mut changed: HashMap<(Point2, usize), Vec<usize>> = HashMap::default();
+        let chunk_dimensions = tilemap.chunk_dimensions();
         let mut reader = tilemap.collision_events().get_reader();
         for event in reader.iter(&tilemap.collision_events()) {
             use crate::event::TilemapCollisionEvent::*;
-            match event {
+            let (chunk_point, tiles) = match event {
                 Spawned {
                     ref chunk_point,
                     ref tiles,
-                } => {
-                    spawned_collisions.push((*chunk_point, tiles.clone()));
                 }
-                Despawned {
+                | Despawned {
                     ref chunk_point,
                     ref tiles,
-                } => {
-                    despawned_collisions.push((*chunk_point, tiles.clone()));
-                }
+                } => (*chunk_point, tiles),
+                SensorEntered { .. } | SensorExited { .. } => continue,
             };
+            for tile in tiles {
+                let index = chunk_dimensions.encode_point_unchecked(tile.point);
+                changed
+                    .entry((chunk_point, tile.z_order))
+                    .or_insert_with(Vec::new)
+                    .push(index);
+            }
         }
 
-        for (chunk_point, tiles) in spawned_collisions.into_iter() {
-            let layers = tilemap.layers();
-            let chunk_dimensions = tilemap.chunk_dimensions();
-            let tile_dimensions = tilemap.tile_dimensions();
-            let physics_tile_width = tile_dimensions.width as f32 / tilemap.physics_scale();
-            let physics_tile_height = tile_dimensions.height as f32 / tilemap.physics_scale();
-            let chunk = if let Some(chunk) = tilemap.chunks_mut().get_mut(&chunk_point) {
-                chunk
-            } else {
-                warn!("Can not get chunk at {}, skipping", &chunk_point);
-                continue;
-            };
-            for tile in tiles {
-                spawn_collisions(
+        let layers: Vec<Option<LayerColliders>> = tilemap
+            .layers()
+            .iter()
+            .map(|layer| layer.as_ref().map(LayerColliders::from))
+            .collect();
+        let physics_scale = tilemap.physics_scale();
+        let translation = transform.translation.truncate() / physics_scale;
+        for point in spawned_chunks.into_iter() {
+            for (z_order, layer) in layers.iter().enumerate() {
+                if let Some(layer) = layer {
+                    update_collisions(
+                        commands,
+                        &mut tilemap,
+                        *layer,
+                        translation,
+                        point,
+                        z_order,
+                        None,
+                    );
+                }
+            }
+            // Every tile of the chunk is up to date.
+            changed.retain(|(chunk_point, _), _| *chunk_point != point);
+        }
+
+        for ((chunk_point, z_order), mut indices) in changed.into_iter() {
+            indices.sort_unstable();
+            indices.dedup();
+            if let Some(Some(layer)) = layers.get(z_order) {
+                update_collisions(
                     commands,
-                    &layers,
-                    tile.point,
-                    tile.z_order,
-                    chunk,
-                    chunk_dimensions,
-                    tile_dimensions,
-                    transform,
-                    physics_tile_width,
-                    physics_tile_height,
+                    &mut tilemap,
+                    *layer,
+                    translation,
+                    chunk_point,
+                    z_order,
+                    Some(indices),
                 );
             }
         }
+    }
+}
 
-        for (chunk_point, tiles) in despawned_collisions.into_iter() {
-            let chunk_dimensions = tilemap.chunk_dimensions();
-            let chunk = if let Some(chunk) = tilemap.chunks_mut().get_mut(&chunk_point) {
-                chunk
-            } else {
-                warn!("Can not get chunk at {}, skipping", &chunk_point);
-                continue;
-            };
-            for tile in tiles {
-                let index = chunk_dimensions.encode_point_unchecked(tile.point);
-                let collision_entity = chunk.get_collision_entity(index);
-                if let Some(entity) = collision_entity {
-                    commands.despawn(entity);
-                    info!(
-                        "Tile {} on z order {} collision entity despawned",
-                        tile.point, tile.z_order
-                    );
+/// Sends the events of the entities with [`TileSensors`] entering and exiting
+/// sensor tiles.
+pub(crate) fn tile_sensor_events(
+    mut tilemap_query: Query<(Entity, &mut Tilemap, &Transform)>,
+    mut sensor_query: Query<(Entity, &GlobalTransform, &mut TileSensors)>,
+) {
+    for (entity, transform, mut sensors) in sensor_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let mut tiles = Vec::new();
+        for (tilemap_entity, tilemap, tilemap_transform) in tilemap_query.iter_mut() {
+            let point = tilemap.world_to_tile(position, tilemap_transform);
+            for z_order in 0..tilemap.layer_count() {
+                if let Some(TileCollider {
+                    kind: ColliderKind::Sensor,
+                    ..
+                }) = tilemap.tile_collider(point, z_order)
+                {
+                    tiles.push((tilemap_entity, point, z_order));
+                }
+            }
+        }
+        if tiles == sensors.tiles {
+            continue;
+        }
+
+        use crate::event::TilemapCollisionEvent::*;
+        for (tilemap_entity, point, z_order) in sensors.tiles.iter() {
+            if !tiles.contains(&(*tilemap_entity, *point, *z_order)) {
+                if let Ok((_, mut tilemap, _)) = tilemap_query.get_mut(*tilemap_entity) {
+                    tilemap.send_collision_event(SensorExited {
+                        entity,
+                        point: *point,
+                        z_order: *z_order,
+                    });
+                }
+            }
+        }
+        for (tilemap_entity, point, z_order) in tiles.iter() {
+            if !sensors.tiles.contains(&(*tilemap_entity, *point, *z_order)) {
+                if let Ok((_, mut tilemap, _)) = tilemap_query.get_mut(*tilemap_entity) {
+                    tilemap.send_collision_event(SensorEntered {
+                        entity,
+                        point: *point,
+                        z_order: *z_order,
+                    });
                 }
             }
         }
+        sensors.tiles = tiles;
     }
 }
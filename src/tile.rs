@@ -2,6 +2,143 @@
 
 use crate::lib::*;
 
+bitflags! {
+    /// How the sprite of a tile is flipped, which allows reusing a sprite in
+    /// every orientation.
+    ///
+    /// The flags follow the convention of Tiled. The anti-diagonal flip swaps
+    /// the x and y axes of the sprite and is applied first, followed by the
+    /// horizontal and the vertical flip. Rotations are combinations of these
+    /// flags. Transposed sprites which are not square are stretched to the
+    /// dimensions of the sprite.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// let tile = Tile {
+    ///     point: (0, 0),
+    ///     sprite_index: 3,
+    ///     flip: TileFlip::ROTATE_90,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert!(tile.flip.contains(TileFlip::ANTI_DIAGONAL));
+    /// assert_eq!(TileFlip::ROTATE_90.rotate_cw(), TileFlip::ROTATE_180);
+    /// ```
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct TileFlip: u8 {
+        /// Mirrors the sprite along its vertical axis.
+        const HORIZONTAL = 0b0000_0001;
+        /// Mirrors the sprite along its horizontal axis.
+        const VERTICAL = 0b0000_0010;
+        /// Swaps the x and y axes of the sprite.
+        const ANTI_DIAGONAL = 0b0000_0100;
+        /// Rotates the sprite by 90 degrees clockwise.
+        const ROTATE_90 = Self::ANTI_DIAGONAL.bits | Self::HORIZONTAL.bits;
+        /// Rotates the sprite by 180 degrees.
+        const ROTATE_180 = Self::HORIZONTAL.bits | Self::VERTICAL.bits;
+        /// Rotates the sprite by 270 degrees clockwise.
+        const ROTATE_270 = Self::ANTI_DIAGONAL.bits | Self::VERTICAL.bits;
+    }
+}
+
+impl Default for TileFlip {
+    fn default() -> TileFlip {
+        TileFlip::empty()
+    }
+}
+
+impl TileFlip {
+    /// Returns the flags which rotate the flipped sprite by another 90
+    /// degrees clockwise.
+    pub fn rotate_cw(self) -> TileFlip {
+        let mut flip = TileFlip::empty();
+        flip.set(
+            TileFlip::ANTI_DIAGONAL,
+            !self.contains(TileFlip::ANTI_DIAGONAL),
+        );
+        flip.set(TileFlip::HORIZONTAL, !self.contains(TileFlip::VERTICAL));
+        flip.set(TileFlip::VERTICAL, self.contains(TileFlip::HORIZONTAL));
+        flip
+    }
+
+    /// Returns the flags which rotate the flipped sprite by another 90
+    /// degrees counter clockwise.
+    pub fn rotate_ccw(self) -> TileFlip {
+        self.rotate_cw().rotate_cw().rotate_cw()
+    }
+}
+
+/// The point of a sprite which is placed on the same point of its grid cell.
+///
+/// Sprites of another size than the grid cell are grown or shrunk around their
+/// anchor, so that for example a tall tree anchored at the bottom extends over
+/// the cells above it. Sprites which are the size of a cell are not affected,
+/// so sprites of any size can be mixed in one texture atlas.
+///
+/// The rows of a chunk are drawn from the top down, so overlapping sprites
+/// cover the rows behind them. Sprites which reach into a neighbouring chunk
+/// on the same layer may be drawn below its tiles.
+///
+/// # Examples
+/// ```
+/// use bevy_tilemap::prelude::*;
+///
+/// let layer = TilemapLayer {
+///     kind: LayerKind::Sparse,
+///     anchor: TileAnchor::Bottom,
+///     ..Default::default()
+/// };
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum TileAnchor {
+    /// The center of the sprite.
+    Center,
+    /// The bottom left corner of the sprite.
+    BottomLeft,
+    /// The center of the bottom edge of the sprite.
+    Bottom,
+    /// The bottom right corner of the sprite.
+    BottomRight,
+    /// The center of the left edge of the sprite.
+    Left,
+    /// The center of the right edge of the sprite.
+    Right,
+    /// The top left corner of the sprite.
+    TopLeft,
+    /// The center of the top edge of the sprite.
+    Top,
+    /// The top right corner of the sprite.
+    TopRight,
+}
+
+impl Default for TileAnchor {
+    fn default() -> TileAnchor {
+        TileAnchor::Center
+    }
+}
+
+impl TileAnchor {
+    /// Returns the anchor as a fraction of the size of the sprite, from its
+    /// bottom left corner.
+    pub(crate) fn to_fraction(self) -> [f32; 2] {
+        use TileAnchor::*;
+        match self {
+            Center => [0.5, 0.5],
+            BottomLeft => [0.0, 0.0],
+            Bottom => [0.5, 0.0],
+            BottomRight => [1.0, 0.0],
+            Left => [0.0, 0.5],
+            Right => [1.0, 0.5],
+            TopLeft => [0.0, 1.0],
+            Top => [0.5, 1.0],
+            TopRight => [1.0, 1.0],
+        }
+    }
+}
+
 /// A tile with an index value and color.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -14,6 +151,9 @@ pub struct Tile<P: Into<Point2>> {
     pub sprite_index: usize,
     /// The desired tint and alpha of the tile. White means no change.
     pub tint: Color,
+    /// How the sprite is flipped and rotated.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flip: TileFlip,
 }
 
 impl<P: Into<Point2> + Default> Default for Tile<P> {
@@ -23,6 +163,7 @@ impl<P: Into<Point2> + Default> Default for Tile<P> {
             z_order: 0,
             sprite_index: 0,
             tint: Color::WHITE,
+            flip: TileFlip::empty(),
         }
     }
 }
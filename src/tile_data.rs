@@ -0,0 +1,876 @@
+//! Typed gameplay data attached to the tiles of a layer.
+//!
+//! Any type which implements [`TileData`] can be stored per tile, such as a
+//! movement cost, an owner, health or flags. Each type has its own storage
+//! per layer and chunk, which is dense or sparse to match the [`LayerKind`]
+//! of the layer. Fog layers store data densely. The data is kept with the
+//! chunk but has no effect on rendering, so it does not need the tile to
+//! exist.
+//!
+//! With the `serialize` feature the data is serialized with the chunks, keyed
+//! by a name that is registered for its type with
+//! [`Tilemap::register_tile_data`], so that saved tilemaps do not depend on
+//! the path of the type. Serializing data of a type which was not registered
+//! is an error. After deserializing a tilemap, the data of a type is returned
+//! once its key is registered again, and the data of the other keys is kept
+//! as it was. The data of [`TileCollider`] is registered by the crate.
+//!
+//! # Examples
+//! ```
+//! use bevy_asset::{prelude::*, HandleId};
+//! use bevy_sprite::prelude::*;
+//! use bevy_tilemap::prelude::*;
+//!
+//! #[derive(Clone, PartialEq, Debug)]
+//! # #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+//! struct MovementCost(u32);
+//!
+//! // In production use a strong handle from an actual source.
+//! let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+//!
+//! let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+//! tilemap.insert_chunk((0, 0)).unwrap();
+//!
+//! tilemap.set_tile_data((1, 1), 0, MovementCost(3)).unwrap();
+//!
+//! assert_eq!(tilemap.get_tile_data::<MovementCost, _>((1, 1), 0), Some(&MovementCost(3)));
+//! assert_eq!(tilemap.get_tile_data::<MovementCost, _>((2, 1), 0), None);
+//! ```
+//!
+//! [`LayerKind`]: crate::chunk::LayerKind
+//! [`TileCollider`]: crate::collision::TileCollider
+//! [`Tilemap::get_tile_data`]: crate::tilemap::Tilemap::get_tile_data
+//! [`Tilemap::register_tile_data`]: crate::tilemap::Tilemap::register_tile_data
+
+use crate::{
+    chunk::LayerKind,
+    collision::TileCollider,
+    lib::*,
+    tilemap::{ErrorKind, TilemapResult},
+    Tilemap,
+};
+
+/// Data which can be attached to tiles.
+///
+/// This is implemented for every type which is `Clone`, `PartialEq`, `Debug`,
+/// `Send` and `Sync`, and with the `serialize` feature also `Serialize` and
+/// `Deserialize`.
+#[cfg(not(feature = "serde"))]
+pub trait TileData: Any + Clone + PartialEq + Debug + Send + Sync {}
+
+#[cfg(not(feature = "serde"))]
+impl<T: Any + Clone + PartialEq + Debug + Send + Sync> TileData for T {}
+
+/// Data which can be attached to tiles.
+///
+/// This is implemented for every type which is `Clone`, `PartialEq`, `Debug`,
+/// `Send` and `Sync`, and with the `serialize` feature also `Serialize` and
+/// `Deserialize`.
+#[cfg(feature = "serde")]
+pub trait TileData:
+    Any + Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned
+{
+}
+
+#[cfg(feature = "serde")]
+impl<T> TileData for T where
+    T: Any + Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned
+{
+}
+
+/// The data of one type on one layer of a chunk.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, PartialEq, Debug)]
+enum DataLayer<T> {
+    /// Data for every tile of the chunk.
+    Dense(Vec<Option<T>>),
+    /// Data for some tiles of the chunk.
+    Sparse(HashMap<usize, T>),
+}
+
+impl<T: TileData> DataLayer<T> {
+    /// Constructs an empty data layer for a layer kind.
+    fn new(kind: LayerKind, area: usize) -> DataLayer<T> {
+        match kind {
+            LayerKind::Dense | LayerKind::Fog => DataLayer::Dense(vec![None; area]),
+            LayerKind::Sparse => DataLayer::Sparse(HashMap::default()),
+        }
+    }
+
+    /// Gets the data of a tile.
+    fn get(&self, index: usize) -> Option<&T> {
+        match self {
+            DataLayer::Dense(data) => data.get(index).and_then(|data| data.as_ref()),
+            DataLayer::Sparse(data) => data.get(&index),
+        }
+    }
+
+    /// Gets the data of a tile mutably.
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match self {
+            DataLayer::Dense(data) => data.get_mut(index).and_then(|data| data.as_mut()),
+            DataLayer::Sparse(data) => data.get_mut(&index),
+        }
+    }
+
+    /// Sets the data of a tile, returning the previous data.
+    fn insert(&mut self, index: usize, value: T) -> Option<T> {
+        match self {
+            DataLayer::Dense(data) => {
+                if let Some(data) = data.get_mut(index) {
+                    data.replace(value)
+                } else {
+                    warn!(
+                        "tile data is out of bounds at index {} and can not be set",
+                        index
+                    );
+                    None
+                }
+            }
+            DataLayer::Sparse(data) => data.insert(index, value),
+        }
+    }
+
+    /// Removes the data of a tile.
+    fn remove(&mut self, index: usize) -> Option<T> {
+        match self {
+            DataLayer::Dense(data) => data.get_mut(index).and_then(|data| data.take()),
+            DataLayer::Sparse(data) => data.remove(&index),
+        }
+    }
+
+    /// Sets the data of the tiles of another data layer which have no data in
+    /// this one.
+    #[cfg(feature = "serde")]
+    fn merge(&mut self, other: DataLayer<T>) {
+        let data: Vec<(usize, T)> = match other {
+            DataLayer::Dense(data) => data
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, value)| Some((index, value?)))
+                .collect(),
+            DataLayer::Sparse(data) => data.into_iter().collect(),
+        };
+        for (index, value) in data.into_iter() {
+            if self.get(index).is_none() {
+                self.insert(index, value);
+            }
+        }
+    }
+}
+
+/// A data layer of which the type is erased.
+trait ErasedDataLayer: Any + Send + Sync {
+    /// Returns the data layer as `Any`.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the data layer as mutable `Any`.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Clones the data layer into a box.
+    fn clone_box(&self) -> Box<dyn ErasedDataLayer>;
+
+    /// Returns `true` if both data layers are of the same type and equal.
+    fn eq_box(&self, other: &dyn ErasedDataLayer) -> bool;
+
+    /// Formats the data layer for debugging.
+    fn fmt_box(&self, f: &mut Formatter<'_>) -> FmtResult;
+
+    /// Serializes the data layer into a value.
+    #[cfg(feature = "serde")]
+    fn to_value(&self) -> ::serde_json::Result<::serde_json::Value>;
+}
+
+impl<T: TileData> ErasedDataLayer for DataLayer<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedDataLayer> {
+        Box::new(self.clone())
+    }
+
+    fn eq_box(&self, other: &dyn ErasedDataLayer) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<DataLayer<T>>()
+            .map_or(false, |other| self == other)
+    }
+
+    fn fmt_box(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Debug::fmt(self, f)
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_value(&self) -> ::serde_json::Result<::serde_json::Value> {
+        ::serde_json::to_value(self)
+    }
+}
+
+impl Clone for Box<dyn ErasedDataLayer> {
+    fn clone(&self) -> Box<dyn ErasedDataLayer> {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for dyn ErasedDataLayer {
+    fn eq(&self, other: &dyn ErasedDataLayer) -> bool {
+        self.eq_box(other)
+    }
+}
+
+impl Debug for dyn ErasedDataLayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.fmt_box(f)
+    }
+}
+
+/// The key the data of [`TileCollider`] is serialized by.
+#[cfg(feature = "serde")]
+const TILE_COLLIDER_KEY: &str = "bevy_tilemap::TileCollider";
+
+/// Returns the key the data of a type of this crate is serialized by, as it
+/// does not need to be registered.
+#[cfg(feature = "serde")]
+fn builtin_key<T: TileData>() -> Option<&'static str> {
+    if TypeId::of::<T>() == TypeId::of::<TileCollider>() {
+        Some(TILE_COLLIDER_KEY)
+    } else {
+        None
+    }
+}
+
+/// The data layers of one type, by z order.
+#[derive(Clone, PartialEq, Debug)]
+struct TypedLayers {
+    /// The name of the type, to tell which type has no key.
+    #[cfg(feature = "serde")]
+    type_name: &'static str,
+    /// The key the data is serialized by, if its type was registered.
+    #[cfg(feature = "serde")]
+    key: Option<&'static str>,
+    /// The data layers by z order.
+    layers: HashMap<usize, Box<dyn ErasedDataLayer>>,
+}
+
+impl TypedLayers {
+    /// Constructs the empty data layers of a type.
+    fn new<T: TileData>() -> TypedLayers {
+        TypedLayers {
+            #[cfg(feature = "serde")]
+            type_name: type_name::<T>(),
+            #[cfg(feature = "serde")]
+            key: builtin_key::<T>(),
+            layers: HashMap::default(),
+        }
+    }
+
+    /// Returns the data layer of a z order.
+    fn get<T: TileData>(&self, z_order: usize) -> Option<&DataLayer<T>> {
+        self.layers.get(&z_order)?.as_any().downcast_ref()
+    }
+
+    /// Returns the data layer of a z order mutably.
+    fn get_mut<T: TileData>(&mut self, z_order: usize) -> Option<&mut DataLayer<T>> {
+        self.layers.get_mut(&z_order)?.as_any_mut().downcast_mut()
+    }
+}
+
+impl Tilemap {
+    /// Gets the typed data of a tile from a given point and z order.
+    ///
+    /// Tile data is independent of the sprite of the tile, which does not need
+    /// to exist. With the `serialize` feature, data that was deserialized is
+    /// only returned after the key of its type was registered with
+    /// [`register_tile_data`].
+    ///
+    /// [`register_tile_data`]: Tilemap::register_tile_data
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// tilemap.set_tile_data((3, 1), 0, 25u32).unwrap();
+    ///
+    /// assert_eq!(tilemap.get_tile_data::<u32, _>((3, 1), 0), Some(&25));
+    /// assert_eq!(tilemap.get_tile_data::<u64, _>((3, 1), 0), None);
+    /// ```
+    pub fn get_tile_data<T, P>(&self, point: P, z_order: usize) -> Option<&T>
+    where
+        T: TileData,
+        P: Into<Point2>,
+    {
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+        self.chunks()
+            .get(&chunk_point)?
+            .tile_data()
+            .get(z_order, index)
+    }
+
+    /// Gets the typed data of a tile mutably from a given point and z order.
+    ///
+    /// Getting a [`TileCollider`] mutably sends a collision event for the
+    /// tile, so that its collider is updated with any changes. Like
+    /// [`get_tile_data`], data that was deserialized is only returned after
+    /// the key of its type was registered.
+    ///
+    /// [`TileCollider`]: crate::collision::TileCollider
+    /// [`get_tile_data`]: Tilemap::get_tile_data
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// tilemap.set_tile_data((3, 1), 0, 25u32).unwrap();
+    /// if let Some(health) = tilemap.get_tile_data_mut::<u32, _>((3, 1), 0) {
+    ///     *health -= 5;
+    /// }
+    ///
+    /// assert_eq!(tilemap.get_tile_data::<u32, _>((3, 1), 0), Some(&20));
+    /// ```
+    pub fn get_tile_data_mut<T, P>(&mut self, point: P, z_order: usize) -> Option<&mut T>
+    where
+        T: TileData,
+        P: Into<Point2>,
+    {
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+        if TypeId::of::<T>() == TypeId::of::<TileCollider>()
+            && self.get_tile_data::<T, _>(point, z_order).is_some()
+        {
+            self.send_tile_collision_event(chunk_point, tile_point, z_order);
+        }
+        self.chunks_mut()
+            .get_mut(&chunk_point)?
+            .tile_data_mut()
+            .get_mut(z_order, index)
+    }
+
+    /// Sets the typed data of a tile at a given point and z order, returning
+    /// the data of the same type that was there before.
+    ///
+    /// The data is stored densely or sparsely to match the kind of the layer.
+    /// Setting data does not send any event, as it has no effect on rendering.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or the chunk of the point does not
+    /// exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// assert_eq!(tilemap.set_tile_data((3, 1), 0, 25u32), Ok(None));
+    /// assert_eq!(tilemap.set_tile_data((3, 1), 0, 30u32), Ok(Some(25)));
+    /// assert!(tilemap.set_tile_data((3, 1), 4, 30u32).is_err());
+    /// assert!(tilemap.set_tile_data((100, 1), 0, 30u32).is_err());
+    /// ```
+    pub fn set_tile_data<T, P>(
+        &mut self,
+        point: P,
+        z_order: usize,
+        data: T,
+    ) -> TilemapResult<Option<T>>
+    where
+        T: TileData,
+        P: Into<Point2>,
+    {
+        let kind = match self.layers().get(z_order).and_then(|layer| layer.as_ref()) {
+            Some(layer) => layer.kind,
+            None => return Err(ErrorKind::LayerDoesNotExist(z_order).into()),
+        };
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+        let area = self.chunk_dimensions().area() as usize;
+        #[cfg(feature = "serde")]
+        let key = self.tile_data_keys().get(&TypeId::of::<T>()).copied();
+        let chunk = match self.chunks_mut().get_mut(&chunk_point) {
+            Some(chunk) => chunk,
+            None => return Err(ErrorKind::MissingChunk.into()),
+        };
+        let tile_data = chunk.tile_data_mut();
+        let previous = tile_data.insert(kind, area, z_order, index, data);
+        #[cfg(feature = "serde")]
+        {
+            if let Some(key) = key {
+                tile_data.set_key::<T>(key);
+            }
+        }
+        if TypeId::of::<T>() == TypeId::of::<TileCollider>() {
+            self.send_tile_collision_event(chunk_point, tile_point, z_order);
+        }
+        Ok(previous)
+    }
+
+    /// Removes the typed data of a tile at a given point and z order,
+    /// returning it if there was any.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// tilemap.set_tile_data((3, 1), 0, 25u32).unwrap();
+    ///
+    /// assert_eq!(tilemap.remove_tile_data::<u32, _>((3, 1), 0), Some(25));
+    /// assert_eq!(tilemap.get_tile_data::<u32, _>((3, 1), 0), None);
+    /// ```
+    pub fn remove_tile_data<T, P>(&mut self, point: P, z_order: usize) -> Option<T>
+    where
+        T: TileData,
+        P: Into<Point2>,
+    {
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions().encode_point_unchecked(tile_point);
+        let data = self
+            .chunks_mut()
+            .get_mut(&chunk_point)?
+            .tile_data_mut()
+            .remove(z_order, index)?;
+        if TypeId::of::<T>() == TypeId::of::<TileCollider>() {
+            self.send_tile_collision_event(chunk_point, tile_point, z_order);
+        }
+        Some(data)
+    }
+
+    /// Registers the key the tile data of a type is serialized by.
+    ///
+    /// The key should stay the same across versions, as it is what the data
+    /// of a saved tilemap is read by. A type needs a key before its data can
+    /// be serialized, and after a tilemap was deserialized its data is
+    /// returned once its key was registered again. Registering a type again
+    /// replaces its key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is used by another type, or if the
+    /// deserialized data of the key can not be read as the type, in which
+    /// case the data is kept as it is.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// #[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+    /// struct MovementCost(u32);
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// tilemap.register_tile_data::<MovementCost>("movement_cost").unwrap();
+    /// tilemap.set_tile_data((1, 1), 0, MovementCost(3)).unwrap();
+    ///
+    /// assert!(tilemap.register_tile_data::<u32>("movement_cost").is_err());
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn register_tile_data<T: TileData>(&mut self, key: &'static str) -> TilemapResult<()> {
+        let type_id = TypeId::of::<T>();
+        let builtin = key == TILE_COLLIDER_KEY && type_id != TypeId::of::<TileCollider>();
+        let taken = self
+            .tile_data_keys()
+            .iter()
+            .any(|(other, other_key)| *other_key == key && *other != type_id);
+        if builtin || taken {
+            return Err(ErrorKind::TileDataKeyTaken(key.to_string()).into());
+        }
+        for chunk in self.chunks_mut().values_mut() {
+            if let Err(err) = chunk.tile_data_mut().register::<T>(key) {
+                return Err(ErrorKind::InvalidTileData(key.to_string(), err.to_string()).into());
+            }
+        }
+        self.tile_data_keys_mut().insert(type_id, key);
+        Ok(())
+    }
+}
+
+/// The tile data of a chunk, by the type of the data and the z order of its
+/// layer.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub(crate) struct TileDataMap {
+    /// The data layers by the type of the data.
+    layers: HashMap<TypeId, TypedLayers>,
+    /// The deserialized data layers of which the type was not registered yet,
+    /// by key and z order.
+    #[cfg(feature = "serde")]
+    serialized: HashMap<String, HashMap<usize, ::serde_json::Value>>,
+}
+
+impl TileDataMap {
+    /// Gets the data of a tile.
+    pub(crate) fn get<T: TileData>(&self, z_order: usize, index: usize) -> Option<&T> {
+        self.layers
+            .get(&TypeId::of::<T>())?
+            .get::<T>(z_order)?
+            .get(index)
+    }
+
+    /// Gets the data of a tile mutably.
+    pub(crate) fn get_mut<T: TileData>(&mut self, z_order: usize, index: usize) -> Option<&mut T> {
+        self.layers
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut::<T>(z_order)?
+            .get_mut(index)
+    }
+
+    /// Sets the data of a tile, creating the data layer for the layer kind if
+    /// needed. Returns the previous data.
+    pub(crate) fn insert<T: TileData>(
+        &mut self,
+        kind: LayerKind,
+        area: usize,
+        z_order: usize,
+        index: usize,
+        value: T,
+    ) -> Option<T> {
+        self.layers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(TypedLayers::new::<T>)
+            .layers
+            .entry(z_order)
+            .or_insert_with(|| Box::new(DataLayer::<T>::new(kind, area)))
+            .as_any_mut()
+            .downcast_mut::<DataLayer<T>>()?
+            .insert(index, value)
+    }
+
+    /// Removes the data of a tile.
+    pub(crate) fn remove<T: TileData>(&mut self, z_order: usize, index: usize) -> Option<T> {
+        self.layers
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut::<T>(z_order)?
+            .remove(index)
+    }
+
+    /// Sets the key the data of a type is serialized by, if it has any data.
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_key<T: TileData>(&mut self, key: &'static str) {
+        if let Some(layers) = self.layers.get_mut(&TypeId::of::<T>()) {
+            layers.key = Some(key);
+        }
+    }
+
+    /// Sets the key the data of a type is serialized by and deserializes the
+    /// data of the key on every layer, if there is any.
+    ///
+    /// The data which was set before takes precedence over the deserialized
+    /// data of the same tile. If the data of any layer can not be read as the
+    /// type, an error is returned and the serialized data is kept as it is.
+    #[cfg(feature = "serde")]
+    pub(crate) fn register<T: TileData>(&mut self, key: &'static str) -> ::serde_json::Result<()> {
+        let mut deserialized = Vec::new();
+        if let Some(values) = self.serialized.get(key) {
+            for (z_order, value) in values.iter() {
+                let layer: DataLayer<T> = ::serde_json::from_value(value.clone())?;
+                deserialized.push((*z_order, layer));
+            }
+            self.serialized.remove(key);
+        }
+        if deserialized.is_empty() {
+            self.set_key::<T>(key);
+            return Ok(());
+        }
+
+        let layers = self
+            .layers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(TypedLayers::new::<T>);
+        layers.key = Some(key);
+        for (z_order, layer) in deserialized.into_iter() {
+            match layers.get_mut::<T>(z_order) {
+                Some(existing) => existing.merge(layer),
+                None => {
+                    layers.layers.insert(z_order, Box::new(layer));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the data of every type from a layer to another.
+    pub(crate) fn move_layer(&mut self, from_z: usize, to_z: usize) {
+        for layers in self.layers.values_mut() {
+            if let Some(layer) = layers.layers.remove(&from_z) {
+                layers.layers.insert(to_z, layer);
+            }
+        }
+        #[cfg(feature = "serde")]
+        for values in self.serialized.values_mut() {
+            if let Some(value) = values.remove(&from_z) {
+                values.insert(to_z, value);
+            }
+        }
+    }
+
+    /// Removes the data of every type from a layer.
+    pub(crate) fn remove_layer(&mut self, z_order: usize) {
+        for layers in self.layers.values_mut() {
+            layers.layers.remove(&z_order);
+        }
+        #[cfg(feature = "serde")]
+        for values in self.serialized.values_mut() {
+            values.remove(&z_order);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for TileDataMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map: BTreeMap<&str, BTreeMap<usize, ::serde_json::Value>> = BTreeMap::new();
+        for (key, values) in self.serialized.iter() {
+            let entry = map.entry(key.as_str()).or_insert_with(BTreeMap::new);
+            for (z_order, value) in values.iter() {
+                entry.insert(*z_order, value.clone());
+            }
+        }
+        for layers in self.layers.values() {
+            if layers.layers.is_empty() {
+                continue;
+            }
+            let key = match layers.key {
+                Some(key) => key,
+                None => {
+                    return Err(S::Error::custom(format!(
+                    "tile data of type `{}` has no key, try `Tilemap::register_tile_data` first",
+                    layers.type_name
+                )))
+                }
+            };
+            let entry = map.entry(key).or_insert_with(BTreeMap::new);
+            for (z_order, layer) in layers.layers.iter() {
+                entry.insert(*z_order, layer.to_value().map_err(S::Error::custom)?);
+            }
+        }
+        map.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for TileDataMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TileDataMap, D::Error> {
+        let map: BTreeMap<String, BTreeMap<usize, ::serde_json::Value>> =
+            BTreeMap::deserialize(deserializer)?;
+        let serialized = map
+            .into_iter()
+            .map(|(key, values)| (key, values.into_iter().collect()))
+            .collect();
+        let mut tile_data = TileDataMap {
+            layers: HashMap::default(),
+            serialized,
+        };
+        // The data of the types of this crate is read right away.
+        tile_data
+            .register::<TileCollider>(TILE_COLLIDER_KEY)
+            .map_err(D::Error::custom)?;
+        Ok(tile_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{test_builder, TilemapLayer};
+
+    fn tilemap() -> Tilemap {
+        let mut tilemap = test_builder()
+            .add_layer(TilemapLayer::default(), 0)
+            .add_layer(
+                TilemapLayer {
+                    kind: LayerKind::Sparse,
+                    ..Default::default()
+                },
+                1,
+            )
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        tilemap
+    }
+
+    #[test]
+    fn data_follows_layer_kind() {
+        let mut tilemap = tilemap();
+        assert_eq!(tilemap.set_tile_data((1, 1), 0, 3u32), Ok(None));
+        assert_eq!(tilemap.set_tile_data((1, 1), 1, 5u32), Ok(None));
+        assert_eq!(tilemap.set_tile_data((1, 1), 1, 7u32), Ok(Some(5)));
+        assert_eq!(tilemap.get_tile_data::<u32, _>((1, 1), 0), Some(&3));
+        assert_eq!(tilemap.get_tile_data::<u32, _>((1, 1), 1), Some(&7));
+        assert_eq!(tilemap.get_tile_data::<u32, _>((2, 1), 0), None);
+        assert_eq!(tilemap.get_tile_data::<i32, _>((1, 1), 0), None);
+
+        let layers = &tilemap.chunks()[&Point2::new(0, 0)].tile_data().layers[&TypeId::of::<u32>()];
+        if let Some(DataLayer::Dense(data)) = layers.get::<u32>(0) {
+            assert_eq!(data.len(), 64);
+            assert_eq!(data[45], Some(3));
+        } else {
+            panic!("dense layers store dense data");
+        }
+        if let Some(DataLayer::Sparse(data)) = layers.get::<u32>(1) {
+            assert_eq!(data.len(), 1);
+            assert_eq!(data.get(&45), Some(&7));
+        } else {
+            panic!("sparse layers store sparse data");
+        }
+    }
+
+    #[test]
+    fn remove_returns_data() {
+        let mut tilemap = tilemap();
+        tilemap.set_tile_data((1, 1), 0, 3u32).unwrap();
+        tilemap.set_tile_data((1, 1), 1, 5u32).unwrap();
+        if let Some(data) = tilemap.get_tile_data_mut::<u32, _>((1, 1), 1) {
+            *data += 1;
+        }
+
+        assert_eq!(tilemap.remove_tile_data::<u32, _>((1, 1), 0), Some(3));
+        assert_eq!(tilemap.remove_tile_data::<u32, _>((1, 1), 0), None);
+        assert_eq!(tilemap.remove_tile_data::<u32, _>((1, 1), 1), Some(6));
+        assert_eq!(tilemap.get_tile_data::<u32, _>((1, 1), 1), None);
+        assert_eq!(tilemap.remove_tile_data::<u32, _>((100, 1), 0), None);
+    }
+
+    #[test]
+    fn set_needs_layer_and_chunk() {
+        let mut tilemap = tilemap();
+        assert_eq!(
+            tilemap.set_tile_data((1, 1), 2, 3u32),
+            Err(ErrorKind::LayerDoesNotExist(2).into())
+        );
+        assert_eq!(
+            tilemap.set_tile_data((100, 1), 0, 3u32),
+            Err(ErrorKind::MissingChunk.into())
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn data_round_trips_once_registered() {
+        let mut tilemap = tilemap();
+        tilemap.register_tile_data::<u32>("health").unwrap();
+        tilemap.set_tile_data((1, 1), 0, 3u32).unwrap();
+        tilemap
+            .set_tile_data((1, 1), 1, TileCollider::default())
+            .unwrap();
+        let chunk_point = Point2::new(0, 0);
+        let data = tilemap.chunks()[&chunk_point].tile_data().clone();
+
+        let json = ::serde_json::to_string(&data).unwrap();
+        let mut tilemap = self::tilemap();
+        *tilemap
+            .chunks_mut()
+            .get_mut(&chunk_point)
+            .unwrap()
+            .tile_data_mut() = ::serde_json::from_str(&json).unwrap();
+
+        // Colliders are read right away, other types once they are registered.
+        assert_eq!(
+            tilemap.get_tile_data::<TileCollider, _>((1, 1), 1),
+            Some(&TileCollider::default())
+        );
+        assert_eq!(tilemap.get_tile_data_mut::<u32, _>((1, 1), 0), None);
+        tilemap.register_tile_data::<u32>("health").unwrap();
+        assert_eq!(tilemap.get_tile_data::<u32, _>((1, 1), 0), Some(&3));
+        assert_eq!(tilemap.chunks()[&chunk_point].tile_data(), &data);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn data_needs_a_key() {
+        let mut tilemap = tilemap();
+        tilemap.set_tile_data((1, 1), 0, 3u32).unwrap();
+        let data = tilemap.chunks()[&Point2::new(0, 0)].tile_data();
+        assert!(::serde_json::to_string(data).is_err());
+
+        assert_eq!(
+            tilemap.register_tile_data::<i32>(TILE_COLLIDER_KEY),
+            Err(ErrorKind::TileDataKeyTaken(TILE_COLLIDER_KEY.to_string()).into())
+        );
+        tilemap.register_tile_data::<u32>("health").unwrap();
+        assert_eq!(
+            tilemap.register_tile_data::<i32>("health"),
+            Err(ErrorKind::TileDataKeyTaken(String::from("health")).into())
+        );
+        let data = tilemap.chunks()[&Point2::new(0, 0)].tile_data();
+        assert!(::serde_json::to_string(data).is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_data_is_kept() {
+        let mut tilemap = tilemap();
+        tilemap.register_tile_data::<String>("name").unwrap();
+        tilemap
+            .set_tile_data((1, 1), 1, String::from("dwarf"))
+            .unwrap();
+        let chunk_point = Point2::new(0, 0);
+        let data = tilemap.chunks()[&chunk_point].tile_data().clone();
+
+        let json = ::serde_json::to_string(&data).unwrap();
+        let mut tilemap = self::tilemap();
+        *tilemap
+            .chunks_mut()
+            .get_mut(&chunk_point)
+            .unwrap()
+            .tile_data_mut() = ::serde_json::from_str(&json).unwrap();
+
+        assert!(tilemap.register_tile_data::<u32>("name").is_err());
+        assert_eq!(tilemap.get_tile_data::<u32, _>((1, 1), 1), None);
+        // The data of the key is still there for the right type.
+        tilemap.register_tile_data::<String>("name").unwrap();
+        assert_eq!(
+            tilemap.get_tile_data::<String, _>((1, 1), 1),
+            Some(&String::from("dwarf"))
+        );
+        assert_eq!(tilemap.chunks()[&chunk_point].tile_data(), &data);
+    }
+}
@@ -93,14 +93,16 @@
 //! }
 //! ```
 
-#[cfg(feature = "bevy_rapier2d")]
-use crate::event::TilemapCollisionEvent;
 use crate::{
-    chunk::{Chunk, LayerKind, RawTile},
-    event::TilemapChunkEvent,
+    animation::{apply_sprite_frames, Animations},
+    auto_tile::{self, TerrainSet},
+    chunk::{mesh::ChunkMesh, render::GridTopologyExt, Chunk, ChunkGeometry, LayerKind, RawTile},
+    collision::{ColliderMerge, TileCollider},
+    event::{TilemapChunkEvent, TilemapCollisionEvent},
+    fog::FogStyle,
     lib::*,
     prelude::GridTopology,
-    tile::Tile,
+    tile::{Tile, TileAnchor, TileFlip},
 };
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -120,6 +122,14 @@ pub enum ErrorKind {
     MissingChunk,
     /// The chunk already exists.
     ChunkAlreadyExists(Point2),
+    /// The layer is not a fog layer.
+    NotFogLayer(usize),
+    /// The chunk height is odd with a staggered isometric grid.
+    OddStaggeredChunkHeight(u32),
+    /// The key of a type of tile data is already used by another type.
+    TileDataKeyTaken(String),
+    /// The tile data of a key can not be deserialized as the registered type.
+    InvalidTileData(String, String),
 }
 
 impl Display for ErrorKind {
@@ -146,6 +156,26 @@ impl Display for ErrorKind {
                 "the chunk {} already exists, if this was intentional run `remove_chunk` first",
                 p
             ),
+            NotFogLayer(n) => write!(
+                f,
+                "layer {} is not a fog layer, try `add_layer` with `LayerKind::Fog` first",
+                n
+            ),
+            OddStaggeredChunkHeight(n) => write!(
+                f,
+                "chunk height {} is odd, staggered isometric chunks must have an even height",
+                n
+            ),
+            TileDataKeyTaken(key) => write!(
+                f,
+                "the tile data key `{}` is already registered for another type",
+                key
+            ),
+            InvalidTileData(key, err) => write!(
+                f,
+                "the tile data of key `{}` can not be deserialized: {}",
+                key, err
+            ),
         }
     }
 }
@@ -190,6 +220,7 @@ bitflags! {
         const AUTO_CONFIGURE = 0b0000_0000_0000_0001;
         const AUTO_CHUNK = 0b0000_0000_0000_0010;
         const AUTO_SPAWN = 0b0000_0000_0000_0100;
+        const AUTO_CULL = 0b0000_0000_0000_1000;
     }
 }
 
@@ -208,10 +239,38 @@ impl Default for AutoFlags {
 
 /// A layer configuration for a tilemap.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TilemapLayer {
     /// The kind of layer to create.
     pub kind: LayerKind,
+    /// Where the sprites of the layer are anchored to their grid cells, which
+    /// matters for sprites of another size than a cell.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anchor: TileAnchor,
+    /// The id of a custom `PipelineDescriptor` to draw the layer with, instead
+    /// of the one of the grid topology.
+    ///
+    /// A pipeline with the vertex shader of a topology and a custom fragment
+    /// shader is built by [`TilemapRenderGraphBuilder::add_tilemap_pipeline`].
+    ///
+    /// [`TilemapRenderGraphBuilder::add_tilemap_pipeline`]:
+    /// crate::chunk::TilemapRenderGraphBuilder::add_tilemap_pipeline
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub pipeline: Option<HandleId>,
+    /// The texture atlas the sprites of the layer are from, instead of the
+    /// one of the tilemap.
+    ///
+    /// This allows layers to use tilesets of their own, such as one for the
+    /// terrain and another for decorations.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub texture_atlas: Option<Handle<TextureAtlas>>,
+    /// How the tiles of the layer are merged into colliders.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub collider_merge: ColliderMerge,
+    /// The colliders of the tiles by sprite index, for the sprites which are
+    /// not solid in the shape of their grid cell.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tile_colliders: HashMap<usize, TileCollider>,
     /// The interaction group and its mask.
     #[cfg_attr(feature = "serde", serde(skip))]
     #[cfg(feature = "bevy_rapier2d")]
@@ -222,6 +281,11 @@ impl Default for TilemapLayer {
     fn default() -> TilemapLayer {
         TilemapLayer {
             kind: LayerKind::Dense,
+            anchor: TileAnchor::default(),
+            pipeline: None,
+            texture_atlas: None,
+            collider_merge: ColliderMerge::default(),
+            tile_colliders: HashMap::default(),
             #[cfg(feature = "bevy_rapier2d")]
             interaction_groups: InteractionGroups::none(),
         }
@@ -264,12 +328,26 @@ pub struct Tilemap {
     #[cfg_attr(feature = "serde", serde(skip))]
     /// The events of the tilemap.
     chunk_events: Events<TilemapChunkEvent>,
-    #[cfg(feature = "bevy_rapier2d")]
     #[cfg_attr(feature = "serde", serde(skip))]
     /// The collision events of the tilemap.
     collision_events: Events<TilemapCollisionEvent>,
     /// A set of all spawned chunks.
     spawned: HashSet<(i32, i32)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    /// The chunks with spawned layers which changed since their meshes were
+    /// last updated.
+    dirty_chunks: HashSet<Point2>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    /// The tile and sprite animations.
+    animations: Animations,
+    /// The terrain sets used for auto-tiling.
+    terrains: Vec<TerrainSet>,
+    /// How fog layers are rendered.
+    fog_style: FogStyle,
+    #[cfg(feature = "serde")]
+    #[serde(skip)]
+    /// The keys tile data is serialized by, by the type of the data.
+    tile_data_keys: HashMap<TypeId, &'static str>,
 }
 
 /// Tilemap factory, which can be used to construct and configure new tilemaps.
@@ -352,6 +430,10 @@ pub struct TilemapBuilder {
     /// for layers with colliders.
     #[cfg(feature = "bevy_rapier2d")]
     physics_scale: f32,
+    /// The terrain sets used for auto-tiling.
+    terrains: Vec<TerrainSet>,
+    /// How fog layers are rendered.
+    fog_style: FogStyle,
 }
 
 impl Default for TilemapBuilder {
@@ -368,6 +450,8 @@ impl Default for TilemapBuilder {
             auto_spawn: None,
             #[cfg(feature = "bevy_rapier2d")]
             physics_scale: 1.0,
+            terrains: Vec::new(),
+            fog_style: FogStyle::default(),
         }
     }
 }
@@ -563,6 +647,28 @@ impl TilemapBuilder {
         self
     }
 
+    /// Sets the tilemap to hide the chunks which are outside of the view of
+    /// every orthographic camera.
+    ///
+    /// Hidden chunks stay spawned with their meshes, so this works with or
+    /// without [`auto_spawn`], which can then spawn chunks further out than
+    /// what is on screen.
+    ///
+    /// By default this is not enabled.
+    ///
+    /// [`auto_spawn`]: TilemapBuilder::auto_spawn
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// let builder = TilemapBuilder::new().auto_cull();
+    /// ```
+    pub fn auto_cull(mut self) -> Self {
+        self.auto_flags.insert(AutoFlags::AUTO_CULL);
+        self
+    }
+
     /// Sets the Rapier physics scale for colliders and rigid bodies created
     /// for layers with colliders.
     #[cfg(feature = "bevy_rapier2d")]
@@ -571,14 +677,55 @@ impl TilemapBuilder {
         self
     }
 
+    /// Adds a terrain set used for auto-tiling.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::{auto_tile::{TerrainMask, TerrainSet}, prelude::*};
+    ///
+    /// let grass = TerrainSet::sequential(TerrainMask::Blob, GridTopology::Square, 0);
+    ///
+    /// let builder = TilemapBuilder::new().add_terrain(grass);
+    /// ```
+    pub fn add_terrain(mut self, terrain: TerrainSet) -> Self {
+        self.terrains.push(terrain);
+        self
+    }
+
+    /// Sets how fog layers are rendered.
+    ///
+    /// If this is not set then unexplored tiles are covered by sprite 0
+    /// tinted black and explored tiles by sprite 0 tinted half transparent
+    /// black.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_render::prelude::*;
+    /// use bevy_tilemap::{chunk::RawTile, fog::FogStyle, prelude::*};
+    ///
+    /// // Sprites 30 and 31 are dedicated fog sprites.
+    /// let style = FogStyle {
+    ///     unexplored: RawTile { index: 30, color: Color::WHITE, ..Default::default() },
+    ///     explored: RawTile { index: 31, color: Color::WHITE, ..Default::default() },
+    /// };
+    ///
+    /// let builder = TilemapBuilder::new().fog_style(style);
+    /// ```
+    pub fn fog_style(mut self, style: FogStyle) -> Self {
+        self.fog_style = style;
+        self
+    }
+
     /// Consumes the builder and returns a result.
     ///
     /// If successful a [`TilemapResult`] is return with [tilemap] on
     /// succes or a [`TilemapError`] if there is an issue.
     ///
     /// # Errors
-    /// If a texture atlas is not set this is the only way that an error can
-    /// occur. If this happens, be sure to use [`texture_atlas`].
+    /// If a texture atlas or the tile dimensions are not set, be sure to use
+    /// [`texture_atlas`] and [`tile_dimensions`]. With a
+    /// [`GridTopology::StaggeredIsometric`] grid the chunk height must be even
+    /// as the rows of every chunk need to be staggered the same way.
     ///
     /// # Examples
     /// ```
@@ -595,6 +742,7 @@ impl TilemapBuilder {
     /// ```
     ///
     /// [`texture_atlas`]: TilemapBuilder::texture_atlas
+    /// [`tile_dimensions`]: TilemapBuilder::tile_dimensions
     /// [tilemap]: Tilemap
     /// [`TilemapError`]: TilemapError
     /// [`TilemapResult`]: TilemapResult
@@ -609,6 +757,11 @@ impl TilemapBuilder {
         } else {
             return Err(ErrorKind::MissingTileDimensions.into());
         };
+        if self.topology == GridTopology::StaggeredIsometric
+            && self.chunk_dimensions.height % 2 != 0
+        {
+            return Err(ErrorKind::OddStaggeredChunkHeight(self.chunk_dimensions.height).into());
+        }
 
         let z_layers = if let Some(layers) = &self.layers {
             if self.z_layers > layers.len() {
@@ -635,9 +788,14 @@ impl TilemapBuilder {
             chunks: Default::default(),
             entities: Default::default(),
             chunk_events: Default::default(),
-            #[cfg(feature = "bevy_rapier2d")]
             collision_events: Default::default(),
             spawned: Default::default(),
+            dirty_chunks: Default::default(),
+            animations: Default::default(),
+            terrains: self.terrains,
+            fog_style: self.fog_style,
+            #[cfg(feature = "serde")]
+            tile_data_keys: Default::default(),
         };
 
         if let Some(mut layers) = self.layers {
@@ -671,9 +829,14 @@ impl Default for Tilemap {
             chunks: Default::default(),
             entities: Default::default(),
             chunk_events: Default::default(),
-            #[cfg(feature = "bevy_rapier2d")]
             collision_events: Default::default(),
             spawned: Default::default(),
+            dirty_chunks: Default::default(),
+            animations: Default::default(),
+            terrains: Vec::new(),
+            fog_style: FogStyle::default(),
+            #[cfg(feature = "serde")]
+            tile_data_keys: Default::default(),
         }
     }
 }
@@ -776,6 +939,82 @@ impl Tilemap {
         &self.texture_atlas
     }
 
+    /// Returns a reference of the handle of the texture atlas of a layer.
+    ///
+    /// This is the texture atlas of the [`TilemapLayer`] if it has one, or
+    /// else the one of the tilemap. Returns `None` if the layer does not
+    /// exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use strong handles from an actual source.
+    /// let terrain_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    /// let decoration_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(terrain_handle.clone(), 32, 32);
+    /// tilemap.add_layer(TilemapLayer::default(), 0).unwrap();
+    /// let layer = TilemapLayer {
+    ///     texture_atlas: Some(decoration_handle.clone()),
+    ///     ..Default::default()
+    /// };
+    /// tilemap.add_layer(layer, 1).unwrap();
+    ///
+    /// assert_eq!(tilemap.layer_texture_atlas(0), Some(&terrain_handle));
+    /// assert_eq!(tilemap.layer_texture_atlas(1), Some(&decoration_handle));
+    /// assert_eq!(tilemap.layer_texture_atlas(2), None);
+    /// ```
+    pub fn layer_texture_atlas(&self, z_order: usize) -> Option<&Handle<TextureAtlas>> {
+        match self.layers.get(z_order) {
+            Some(Some(layer)) => Some(layer.texture_atlas.as_ref().unwrap_or(&self.texture_atlas)),
+            _ => None,
+        }
+    }
+
+    /// Sets the texture atlas of a layer, or clears it with `None` so the
+    /// layer uses the texture atlas of the tilemap.
+    ///
+    /// Chunks which are already spawned keep their texture atlas until they
+    /// are spawned again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer does not exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use strong handles from an actual source.
+    /// let terrain_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    /// let decoration_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(terrain_handle, 32, 32);
+    /// tilemap.add_layer(TilemapLayer::default(), 0).unwrap();
+    ///
+    /// assert!(tilemap.set_layer_texture_atlas(0, Some(decoration_handle.clone())).is_ok());
+    /// assert_eq!(tilemap.layer_texture_atlas(0), Some(&decoration_handle));
+    /// assert!(tilemap.set_layer_texture_atlas(1, None).is_err());
+    /// ```
+    pub fn set_layer_texture_atlas(
+        &mut self,
+        z_order: usize,
+        handle: Option<Handle<TextureAtlas>>,
+    ) -> TilemapResult<()> {
+        match self.layers.get_mut(z_order) {
+            Some(Some(layer)) => {
+                layer.texture_atlas = handle;
+                Ok(())
+            }
+            _ => Err(ErrorKind::LayerDoesNotExist(z_order).into()),
+        }
+    }
+
     /// Constructs a new chunk and stores it at a coordinate position.
     ///
     /// It requires that you give it either a point. It then automatically sets
@@ -821,12 +1060,7 @@ impl Tilemap {
         if let Some(dimensions) = &self.dimensions {
             dimensions.check_point(point)?;
         }
-        let layer_kinds = self
-            .layers
-            .iter()
-            .map(|x| x.and_then(|y| Some(y.kind)))
-            .collect::<Vec<Option<LayerKind>>>();
-        let chunk = Chunk::new(point, &layer_kinds, self.chunk_dimensions);
+        let chunk = Chunk::new(point, &self.layer_kinds(), self.chunk_dimensions);
         match self.chunks.insert(point, chunk) {
             Some(_) => Err(ErrorKind::ChunkAlreadyExists(point).into()),
             None => Ok(()),
@@ -863,6 +1097,11 @@ impl Tilemap {
     pub fn add_layer_with_kind(&mut self, kind: LayerKind, z_order: usize) -> TilemapResult<()> {
         let layer = TilemapLayer {
             kind,
+            anchor: TileAnchor::default(),
+            pipeline: None,
+            texture_atlas: None,
+            collider_merge: ColliderMerge::default(),
+            tile_colliders: HashMap::default(),
             #[cfg(feature = "bevy_rapier2d")]
             interaction_groups: InteractionGroups::default(),
         };
@@ -913,7 +1152,7 @@ impl Tilemap {
     /// };
     /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
     ///
-    /// assert!(tilemap.add_layer(layer, 1).is_ok());
+    /// assert!(tilemap.add_layer(layer.clone(), 1).is_ok());
     /// assert!(tilemap.add_layer(layer, 1).is_err());
     /// ```
     ///
@@ -921,6 +1160,7 @@ impl Tilemap {
     /// [`LayerKind`]: crate::chunk::LayerKind
     /// [`LayerKind::Sparse`]: crate::chunk::LayerKind::Sparse
     pub fn add_layer(&mut self, layer: TilemapLayer, z_order: usize) -> TilemapResult<()> {
+        let kind = layer.kind;
         if let Some(inner_layer) = self.layers.get_mut(z_order) {
             if inner_layer.is_some() {
                 return Err(ErrorKind::LayerExists(z_order).into());
@@ -929,7 +1169,7 @@ impl Tilemap {
         }
 
         for chunk in self.chunks.values_mut() {
-            chunk.add_layer(&layer.kind, z_order, self.chunk_dimensions)
+            chunk.add_layer(&kind, z_order, self.chunk_dimensions)
         }
 
         Ok(())
@@ -971,7 +1211,7 @@ impl Tilemap {
             }
         };
         if let Some(layer) = self.layers.get(from_z) {
-            if Some(layer).is_none() {
+            if layer.is_none() {
                 return Err(ErrorKind::LayerDoesNotExist(from_z).into());
             }
         }
@@ -991,7 +1231,8 @@ impl Tilemap {
     /// method instead.
     ///
     /// This method takes in a Z layer which is then flagged for deletion. If
-    /// the layer already does not exist, it does nothing.
+    /// the layer already does not exist, it does nothing. The entities of the
+    /// layer in spawned chunks are despawned, along with their colliders.
     ///
     /// # Examples
     /// ```
@@ -1017,7 +1258,16 @@ impl Tilemap {
             return;
         }
 
-        for chunk in self.chunks.values_mut() {
+        for (point, chunk) in self.chunks.iter_mut() {
+            if let Some(entity) = chunk.get_entity(z) {
+                self.chunk_events.send(TilemapChunkEvent::LayerDespawned {
+                    entity,
+                    point: *point,
+                    z_order: z,
+                });
+            }
+            #[cfg(feature = "bevy_rapier2d")]
+            chunk.take_collision_entities(z);
             chunk.remove_layer(z);
         }
     }
@@ -1154,7 +1404,12 @@ impl Tilemap {
         self.spawned.remove(&(point.x, point.y));
 
         if let Some(chunk) = self.chunks.get_mut(&point) {
-            let entities = chunk.get_entities();
+            let entities = chunk.take_entities();
+            // The colliders are despawned with the entities of the chunk.
+            #[cfg(feature = "bevy_rapier2d")]
+            for z_order in 0..chunk.z_layers() {
+                chunk.take_collision_entities(z_order);
+            }
             self.chunk_events
                 .send(TilemapChunkEvent::Despawned { entities, point })
         }
@@ -1246,6 +1501,192 @@ impl Tilemap {
         (x, y)
     }
 
+    /// Returns the center of a tile in pixels, relative to the tilemap.
+    ///
+    /// The position is where the tile is rendered, which depends on the
+    /// [`GridTopology`] of the tilemap. To get the position in the world, apply
+    /// the transform of the tilemap entity.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_math::Vec2;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    ///
+    /// assert_eq!(tilemap.tile_to_world((0, 0)), Vec2::new(16.0, 16.0));
+    /// assert_eq!(tilemap.tile_to_world((-2, 1)), Vec2::new(-48.0, 48.0));
+    /// ```
+    pub fn tile_to_world<P: Into<Point2>>(&self, point: P) -> Vec2 {
+        let point: Point2 = point.into();
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        self.topology
+            .chunk_translation(chunk_point, self.chunk_dimensions, self.tile_dimensions)
+            + self
+                .topology
+                .tile_center(tile_point, self.chunk_dimensions, self.tile_dimensions)
+    }
+
+    /// Returns the point of the tile at a position in the world.
+    ///
+    /// The transform is the one of the tilemap entity. This is useful for
+    /// picking the tile under the cursor, once the cursor position is
+    /// translated into the world with the camera.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_math::{Vec2, Vec3};
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    /// use bevy_tilemap_types::point::Point2;
+    /// use bevy_transform::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let tilemap = TilemapBuilder::new()
+    ///     .texture_atlas(texture_atlas_handle)
+    ///     .topology(GridTopology::HexOddRows)
+    ///     .tile_dimensions(32, 32)
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// let transform = Transform::from_translation(Vec3::new(100.0, 100.0, 0.0));
+    ///
+    /// let position = tilemap.tile_to_world((3, -4)) + Vec2::new(100.0, 100.0);
+    /// assert_eq!(tilemap.world_to_tile(position, &transform), Point2::new(3, -4));
+    /// ```
+    pub fn world_to_tile(&self, position: Vec2, transform: &Transform) -> Point2 {
+        let position = transform
+            .compute_matrix()
+            .inverse()
+            .transform_point3(position.extend(0.0))
+            .truncate();
+        self.position_to_tile(position)
+    }
+
+    /// Returns the point of the tile at a position in pixels relative to the
+    /// tilemap.
+    pub(crate) fn position_to_tile(&self, position: Vec2) -> Point2 {
+        /// The maximum amount of steps taken towards the tile.
+        const MAX_STEPS: usize = 8;
+
+        // The chunks add some rounding to the positions, which is why the
+        // estimate is refined from the position of the tile it landed on.
+        let mut point = Point2::default();
+        for _ in 0..MAX_STEPS {
+            let offset = self
+                .topology
+                .tile_offset(position - self.tile_to_world(point), self.tile_dimensions);
+            if offset == Point2::default() {
+                break;
+            }
+            point += offset;
+        }
+
+        let mut closest = point;
+        let mut closest_distance = f32::MAX;
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let candidate = Point2::new(point.x + x, point.y + y);
+                let distance = self.topology.scaled_distance(
+                    position - self.tile_to_world(candidate),
+                    self.tile_dimensions,
+                );
+                if distance < closest_distance {
+                    closest = candidate;
+                    closest_distance = distance;
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Computes the geometry of a chunk layer as it is rendered, without a
+    /// GPU.
+    ///
+    /// The vertex positions are in pixels relative to the tilemap, like
+    /// [`tile_to_world`], and the texture coordinates are taken from the
+    /// texture atlas of the layer given by [`layer_texture_atlas`], which
+    /// must be passed in. Animated sprites are at their current frame and fog
+    /// layers are overlays, the same as on screen.
+    ///
+    /// [`tile_to_world`]: Tilemap::tile_to_world
+    /// [`layer_texture_atlas`]: Tilemap::layer_texture_atlas
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or the chunk does not exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_math::Vec2;
+    /// use bevy_render::prelude::*;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    /// let texture_atlas = TextureAtlas::from_grid(
+    ///     Handle::weak(HandleId::random::<Texture>()),
+    ///     Vec2::new(32.0, 32.0),
+    ///     2,
+    ///     2,
+    /// );
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    /// tilemap.insert_tile(Tile { point: (0, 0), sprite_index: 1, ..Default::default() }).unwrap();
+    ///
+    /// let geometry = tilemap.chunk_geometry((0, 0), 0, &texture_atlas).unwrap();
+    ///
+    /// assert!(geometry.positions.contains(&[0.0, 0.0, 0.0]));
+    /// assert!(geometry.positions.contains(&[32.0, 32.0, 0.0]));
+    /// assert!(geometry.uvs.contains(&[1.0, 0.5]));
+    /// assert!(tilemap.chunk_geometry((0, 0), 1, &texture_atlas).is_err());
+    /// ```
+    pub fn chunk_geometry<P: Into<Point2>>(
+        &self,
+        point: P,
+        z_order: usize,
+        texture_atlas: &TextureAtlas,
+    ) -> TilemapResult<ChunkGeometry> {
+        let anchor = match self.layers.get(z_order).and_then(|layer| layer.as_ref()) {
+            Some(layer) => layer.anchor,
+            None => return Err(ErrorKind::LayerDoesNotExist(z_order).into()),
+        };
+        let point: Point2 = point.into();
+        let chunk = match self.chunks.get(&point) {
+            Some(chunk) => chunk,
+            None => return Err(ErrorKind::MissingChunk.into()),
+        };
+        let (mut indexes, colors, flips) =
+            match chunk.tiles_to_renderer_parts(z_order, self.chunk_dimensions, &self.fog_style) {
+                Some(parts) => parts,
+                None => return Err(ErrorKind::LayerDoesNotExist(z_order).into()),
+            };
+        apply_sprite_frames(&self.sprite_animation_frames(), &mut indexes);
+
+        let translation =
+            self.topology
+                .chunk_translation(point, self.chunk_dimensions, self.tile_dimensions);
+        let mesh = ChunkMesh::new(
+            self.chunk_dimensions,
+            self.tile_dimensions,
+            anchor,
+            self.topology,
+        );
+        Ok(mesh.geometry(translation, &indexes, &colors, &flips, texture_atlas))
+    }
+
     /// Sorts tiles into the chunks they belong to.
     fn sort_tiles_to_chunks<P, I>(
         &mut self,
@@ -1281,6 +1722,7 @@ impl Tilemap {
                 z_order: tile.z_order,
                 sprite_index: tile.sprite_index,
                 tint: tile.tint,
+                flip: tile.flip,
             };
             if let Some(tiles) = chunk_map.get_mut(&chunk_point) {
                 tiles.push(chunk_tile);
@@ -1334,9 +1776,9 @@ impl Tilemap {
     /// // Set multiple tiles and unwrap the result
     /// tilemap.insert_tiles(tiles).unwrap();
     ///
-    /// assert_eq!(tilemap.get_tile((1, 1), 0), Some(&RawTile { index: 0, color: Color::WHITE }));
-    /// assert_eq!(tilemap.get_tile((2, 2), 0), Some(&RawTile { index: 1, color: Color::WHITE }));
-    /// assert_eq!(tilemap.get_tile((3, 3), 0), Some(&RawTile { index: 2, color: Color::WHITE }));
+    /// assert_eq!(tilemap.get_tile((1, 1), 0), Some(&RawTile { index: 0, color: Color::WHITE, ..Default::default() }));
+    /// assert_eq!(tilemap.get_tile((2, 2), 0), Some(&RawTile { index: 1, color: Color::WHITE, ..Default::default() }));
+    /// assert_eq!(tilemap.get_tile((3, 3), 0), Some(&RawTile { index: 2, color: Color::WHITE, ..Default::default() }));
     /// assert_eq!(tilemap.get_tile((4, 4), 0), None);
     /// ```
     ///
@@ -1346,22 +1788,29 @@ impl Tilemap {
         P: Into<Point2>,
         I: IntoIterator<Item = Tile<P>>,
     {
+        let tiles: Vec<Tile<Point2>> = tiles
+            .into_iter()
+            .map(|tile| Tile {
+                point: tile.point.into(),
+                z_order: tile.z_order,
+                sprite_index: tile.sprite_index,
+                tint: tile.tint,
+                flip: tile.flip,
+            })
+            .collect();
+        let points: Vec<(Point2, usize)> = tiles
+            .iter()
+            .map(|tile| (tile.point, tile.z_order))
+            .collect();
         let chunk_map = self.sort_tiles_to_chunks(tiles)?;
+        let mut modified = HashMap::default();
+        let layer_kinds = self.layer_kinds();
         for (chunk_point, tiles) in chunk_map.into_iter() {
-            // Is there a better way to do this? Clippy hates if I don't do it
-            // like this talking about constructing regardless yet, here it is,
-            // copying stuff regardless because it doesn't like self in the
-            // `FnOnce`.
-            let layers = self.layers.clone();
             let chunk_dimensions = self.chunk_dimensions;
             let chunk = if self.auto_flags.contains(AutoFlags::AUTO_CHUNK) {
-                self.chunks.entry(chunk_point).or_insert_with(|| {
-                    let layer_kinds = layers
-                        .iter()
-                        .map(|x| x.and_then(|y| Some(y.kind)))
-                        .collect::<Vec<Option<LayerKind>>>();
-                    Chunk::new(chunk_point, &layer_kinds, chunk_dimensions)
-                })
+                self.chunks
+                    .entry(chunk_point)
+                    .or_insert_with(|| Chunk::new(chunk_point, &layer_kinds, chunk_dimensions))
             } else {
                 match self.chunks.get_mut(&chunk_point) {
                     Some(c) => c,
@@ -1369,23 +1818,23 @@ impl Tilemap {
                 }
             };
 
-            let mut layers = HashMap::default();
+            let layers: &mut HashMap<usize, Entity> =
+                modified.entry(chunk_point).or_insert_with(HashMap::default);
             for tile in tiles.iter() {
                 let index = self.chunk_dimensions.encode_point_unchecked(tile.point);
-                // TODO: Tile collider must be added to the chunk.
                 chunk.set_tile(index, *tile);
                 if let Some(entity) = chunk.get_entity(tile.z_order) {
                     layers.entry(tile.z_order).or_insert(entity);
                 }
             }
 
-            self.chunk_events
-                .send(TilemapChunkEvent::Modified { layers });
-            #[cfg(feature = "bevy_rapier2d")]
             self.collision_events
                 .send(TilemapCollisionEvent::Spawned { chunk_point, tiles });
         }
 
+        self.resolve_terrain(points, &mut modified);
+        self.send_modified(modified);
+
         Ok(())
     }
 
@@ -1419,7 +1868,7 @@ impl Tilemap {
     /// let tile = Tile { point, sprite_index, ..Default::default() };
     ///
     /// assert!(tilemap.insert_tile(tile).is_ok());
-    /// assert_eq!(tilemap.get_tile((9, 3), 0), Some(&RawTile { index: 3, color: Color::WHITE }))
+    /// assert_eq!(tilemap.get_tile((9, 3), 0), Some(&RawTile { index: 3, color: Color::WHITE, ..Default::default() }))
     /// ```
     ///
     /// # Errors
@@ -1465,7 +1914,7 @@ impl Tilemap {
     /// tilemap.clear_tiles(to_remove).unwrap();
     /// assert_eq!(tilemap.get_tile((1, 1), 0), None);
     /// assert_eq!(tilemap.get_tile((2, 2), 0), None);
-    /// assert_eq!(tilemap.get_tile((3, 3), 0), Some(&RawTile { index: 0, color: Color::WHITE} ));
+    /// assert_eq!(tilemap.get_tile((3, 3), 0), Some(&RawTile { index: 0, color: Color::WHITE, ..Default::default() }));
     /// ```
     ///
     /// # Errors
@@ -1479,41 +1928,161 @@ impl Tilemap {
     {
         let mut tiles = Vec::new();
         for (point, z_order) in points {
+            let point = point.into();
+            // A tile inserted at the point afterwards must not inherit the
+            // animation.
+            self.animations.remove_tile(point, z_order);
             tiles.push(Tile {
-                point: point.into(),
+                point,
                 sprite_index: 0,
                 z_order,
                 tint: Color::rgba(0.0, 0.0, 0.0, 0.0),
+                flip: TileFlip::empty(),
             });
         }
+        let points: Vec<(Point2, usize)> = tiles
+            .iter()
+            .map(|tile| (tile.point, tile.z_order))
+            .collect();
         let chunk_map = self.sort_tiles_to_chunks(tiles)?;
-        let mut layers = HashMap::default();
-        for (chunk_point, tiles) in chunk_map.into_iter() {
+        let mut modified = HashMap::default();
+        for (chunk_point, mut tiles) in chunk_map.into_iter() {
             let chunk = match self.chunks.get_mut(&chunk_point) {
                 Some(c) => c,
                 None => return Err(ErrorKind::MissingChunk.into()),
             };
+            let chunk_dimensions = self.chunk_dimensions;
+            tiles.retain(|tile| {
+                let index = chunk_dimensions.encode_point_unchecked(tile.point);
+                chunk.get_tile(tile.z_order, index).is_some()
+            });
+            if tiles.is_empty() {
+                continue;
+            }
+
+            let layers: &mut HashMap<usize, Entity> =
+                modified.entry(chunk_point).or_insert_with(HashMap::default);
             for tile in tiles.iter() {
-                let index = self.chunk_dimensions.encode_point_unchecked(tile.point);
+                let index = chunk_dimensions.encode_point_unchecked(tile.point);
                 chunk.remove_tile(index, tile.z_order);
                 if let Some(entity) = chunk.get_entity(tile.z_order) {
                     layers.entry(tile.z_order).or_insert(entity);
                 }
             }
 
-            #[cfg(feature = "bevy_rapier2d")]
             self.collision_events
                 .send(TilemapCollisionEvent::Despawned { chunk_point, tiles });
         }
 
-        self.chunk_events
-            .send(TilemapChunkEvent::Modified { layers });
+        self.resolve_terrain(points, &mut modified);
+        self.send_modified(modified);
 
         Ok(())
     }
 
+    /// Re-resolves the sprites of terrain tiles at and around the points.
+    ///
+    /// The layers of the chunks which changed are added to `modified`.
+    fn resolve_terrain(
+        &mut self,
+        points: Vec<(Point2, usize)>,
+        modified: &mut HashMap<Point2, HashMap<usize, Entity>>,
+    ) {
+        if self.terrains.is_empty() {
+            return;
+        }
+
+        let mut affected = HashSet::default();
+        for (point, z_order) in points.into_iter() {
+            affected.insert((point, z_order));
+            for neighbour in auto_tile::surrounding(self.topology, point) {
+                affected.insert((neighbour, z_order));
+            }
+        }
+
+        let mut changes = Vec::new();
+        for (point, z_order) in affected.into_iter() {
+            let tile = match self.raw_tile(point, z_order) {
+                Some(tile) => tile,
+                None => continue,
+            };
+            let terrain = match self.terrains.iter().find(|t| t.contains(tile.index)) {
+                Some(terrain) => terrain,
+                None => continue,
+            };
+            let mask = terrain.mask().compute(self.topology, point, |neighbour| {
+                self.raw_tile(neighbour, z_order)
+                    .map_or(false, |tile| terrain.contains(tile.index))
+            });
+            let sprite_index = terrain.sprite_index(mask);
+            if sprite_index != tile.index {
+                changes.push((point, z_order, sprite_index));
+            }
+        }
+
+        for (point, z_order, sprite_index) in changes.into_iter() {
+            let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+            let tile_point = self.point_to_tile_point(point);
+            let index = self.chunk_dimensions.encode_point_unchecked(tile_point);
+            // The collider of the tile may depend on its sprite.
+            self.send_tile_collision_event(chunk_point, tile_point, z_order);
+            if let Some(chunk) = self.chunks.get_mut(&chunk_point) {
+                if let Some(tile) = chunk.get_tile_mut(z_order, index) {
+                    tile.index = sprite_index;
+                }
+                if let Some(entity) = chunk.get_entity(z_order) {
+                    modified
+                        .entry(chunk_point)
+                        .or_insert_with(HashMap::default)
+                        .insert(z_order, entity);
+                }
+            }
+        }
+    }
+
+    /// Sends a modified event for each chunk with the spawned layers that
+    /// changed, marking the chunk for a mesh update.
+    pub(crate) fn send_modified(&mut self, modified: HashMap<Point2, HashMap<usize, Entity>>) {
+        for (chunk_point, layers) in modified.into_iter() {
+            if layers.is_empty() {
+                continue;
+            }
+            self.dirty_chunks.insert(chunk_point);
+            self.chunk_events
+                .send(TilemapChunkEvent::Modified { layers });
+        }
+    }
+
+    /// Sends a collision event for a tile which changed, so that its collider
+    /// is updated.
+    pub(crate) fn send_tile_collision_event(
+        &mut self,
+        chunk_point: Point2,
+        tile_point: Point2,
+        z_order: usize,
+    ) {
+        let tile = Tile {
+            point: tile_point,
+            z_order,
+            ..Default::default()
+        };
+        self.collision_events.send(TilemapCollisionEvent::Spawned {
+            chunk_point,
+            tiles: vec![tile],
+        });
+    }
+
+    /// Gets a raw tile from a given point and z order without marking its
+    /// chunk as modified.
+    pub(crate) fn raw_tile(&self, point: Point2, z_order: usize) -> Option<&RawTile> {
+        let chunk_point: Point2 = self.point_to_chunk_point(point).into();
+        let tile_point = self.point_to_tile_point(point);
+        let index = self.chunk_dimensions.encode_point_unchecked(tile_point);
+        self.chunks.get(&chunk_point)?.get_tile(z_order, index)
+    }
+
     /// Takes a global tile point and returns a tile point in a chunk.
-    fn point_to_tile_point(&self, point: Point2) -> Point2 {
+    pub(crate) fn point_to_tile_point(&self, point: Point2) -> Point2 {
         let chunk_point: Point2 = self.point_to_chunk_point(point).into();
         let width = self.chunk_dimensions.width as i32;
         let height = self.chunk_dimensions.height as i32;
@@ -1588,7 +2157,7 @@ impl Tilemap {
     /// let tile = Tile { point, sprite_index, ..Default::default() };
     ///
     /// assert!(tilemap.insert_tile(tile).is_ok());
-    /// assert_eq!(tilemap.get_tile((9, 3), 0), Some(&RawTile { index: 3, color: Color::WHITE }));
+    /// assert_eq!(tilemap.get_tile((9, 3), 0), Some(&RawTile { index: 3, color: Color::WHITE, ..Default::default() }));
     /// assert_eq!(tilemap.get_tile((10, 4), 0), None);
     /// ```
     pub fn get_tile<P>(&mut self, point: P, z_order: usize) -> Option<&RawTile>
@@ -1608,7 +2177,12 @@ impl Tilemap {
     /// This is different thant he usual [`Tile`] struct in that it only
     /// contains the sprite index and the tint.
     ///
+    /// The tile is treated as changed whether it is written to or not: the
+    /// mesh of its chunk layer is updated and a collision event is sent for
+    /// it. Use [`get_tile`] to only read a tile.
+    ///
     /// [`Tile`]: crate::tile::Tile
+    /// [`get_tile`]: Tilemap::get_tile
     ///
     /// # Examples
     /// ```
@@ -1629,7 +2203,7 @@ impl Tilemap {
     /// let tile = Tile { point, sprite_index, ..Default::default() };
     ///
     /// assert!(tilemap.insert_tile(tile).is_ok());
-    /// assert_eq!(tilemap.get_tile_mut((2, 5), 0), Some(&mut RawTile { index: 2, color: Color::WHITE }));
+    /// assert_eq!(tilemap.get_tile_mut((2, 5), 0), Some(&mut RawTile { index: 2, color: Color::WHITE, ..Default::default() }));
     /// assert_eq!(tilemap.get_tile_mut((1, 4), 0), None);
     /// ```
     pub fn get_tile_mut<P>(&mut self, point: P, z_order: usize) -> Option<&mut RawTile>
@@ -1639,15 +2213,73 @@ impl Tilemap {
         let point: Point2 = point.into();
         let chunk_point: Point2 = self.point_to_chunk_point(point).into();
         let tile_point = self.point_to_tile_point(point);
-        let chunk = self.chunks.get_mut(&chunk_point)?;
         let index = self.chunk_dimensions.encode_point_unchecked(tile_point);
-        let mut layers = HashMap::default();
+        let chunk = self.chunks.get(&chunk_point)?;
+        chunk.get_tile(z_order, index)?;
         if let Some(entity) = chunk.get_entity(z_order) {
+            let mut layers = HashMap::default();
             layers.insert(z_order, entity);
-            self.chunk_events
-                .send(TilemapChunkEvent::Modified { layers });
+            let mut modified = HashMap::default();
+            modified.insert(chunk_point, layers);
+            self.send_modified(modified);
         }
-        chunk.get_tile_mut(z_order, index)
+        self.send_tile_collision_event(chunk_point, tile_point, z_order);
+        self.chunks
+            .get_mut(&chunk_point)
+            .and_then(|chunk| chunk.get_tile_mut(z_order, index))
+    }
+
+    /// Adds a terrain set used for auto-tiling.
+    ///
+    /// Tiles which are inserted or cleared afterwards are resolved together
+    /// with their neighbours, tiles which already exist are left as they are.
+    /// If sprite indices are in more than one terrain set, the set that was
+    /// added first is used.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_asset::{prelude::*, HandleId};
+    /// use bevy_render::prelude::*;
+    /// use bevy_sprite::prelude::*;
+    /// use bevy_tilemap::{auto_tile::{TerrainMask, TerrainSet}, chunk::RawTile, prelude::*};
+    ///
+    /// // In production use a strong handle from an actual source.
+    /// let texture_atlas_handle = Handle::weak(HandleId::random::<TextureAtlas>());
+    ///
+    /// let mut tilemap = Tilemap::new(texture_atlas_handle, 32, 32);
+    ///
+    /// tilemap.insert_chunk((0, 0)).unwrap();
+    ///
+    /// // A single wall is sprite 4, a wall with a neighbour to the north is 5.
+    /// tilemap.add_terrain(TerrainSet::new(TerrainMask::Edges, 4).with_sprite(0b0001, 5));
+    ///
+    /// let tiles = vec![
+    ///     Tile { point: (0, 0), sprite_index: 4, ..Default::default() },
+    ///     Tile { point: (0, 1), sprite_index: 4, ..Default::default() },
+    /// ];
+    /// tilemap.insert_tiles(tiles).unwrap();
+    /// assert_eq!(tilemap.get_tile((0, 0), 0), Some(&RawTile { index: 5, color: Color::WHITE, ..Default::default() }));
+    ///
+    /// tilemap.clear_tile((0, 1), 0).unwrap();
+    /// assert_eq!(tilemap.get_tile((0, 0), 0), Some(&RawTile { index: 4, color: Color::WHITE, ..Default::default() }));
+    /// ```
+    pub fn add_terrain(&mut self, terrain: TerrainSet) {
+        self.terrains.push(terrain);
+    }
+
+    /// Returns the terrain sets used for auto-tiling.
+    pub fn terrains(&self) -> &[TerrainSet] {
+        &self.terrains
+    }
+
+    /// Returns `true` if any chunk needs its meshes updated.
+    pub(crate) fn has_dirty_chunks(&self) -> bool {
+        !self.dirty_chunks.is_empty()
+    }
+
+    /// Takes the chunks which need their meshes updated.
+    pub(crate) fn take_dirty_chunks(&mut self) -> HashSet<Point2> {
+        mem::take(&mut self.dirty_chunks)
     }
 
     /// Returns the center tile, if the tilemap has dimensions.
@@ -1872,7 +2504,7 @@ impl Tilemap {
     /// is used for war games or world maps. It is easier to define structures
     /// with walls and floors with square but not impossible with hex.
     ///
-    /// [`GridTopology`]: crate::render::GridTopology
+    /// [`GridTopology`]: crate::GridTopology
     ///
     /// # Examples
     /// ```
@@ -1937,13 +2569,16 @@ impl Tilemap {
     /// [`chunk_events_update`]:
     ///
     ///
-    #[cfg(feature = "bevy_rapier2d")]
     pub fn collision_events(&self) -> &Events<TilemapCollisionEvent> {
         &self.collision_events
     }
 
+    /// Sends a collision event.
+    pub(crate) fn send_collision_event(&mut self, event: TilemapCollisionEvent) {
+        self.collision_events.send(event)
+    }
+
     /// Updates the collision events. This should only be done once per frame.
-    #[cfg(feature = "bevy_rapier2d")]
     pub(crate) fn collision_events_update(&mut self) {
         self.collision_events.update()
     }
@@ -1960,6 +2595,31 @@ impl Tilemap {
         self.physics_scale = scale;
     }
 
+    /// Returns `true` if the chunks outside of the view of every camera are
+    /// hidden.
+    ///
+    /// # Examples
+    /// ```
+    /// use bevy_tilemap::prelude::*;
+    ///
+    /// let mut tilemap = Tilemap::default();
+    /// assert!(!tilemap.auto_cull());
+    ///
+    /// tilemap.set_auto_cull(true);
+    /// assert!(tilemap.auto_cull());
+    /// ```
+    pub fn auto_cull(&self) -> bool {
+        self.auto_flags.contains(AutoFlags::AUTO_CULL)
+    }
+
+    /// Sets if the chunks outside of the view of every camera are hidden.
+    ///
+    /// When it is turned off, chunks keep the visibility they had until they
+    /// are spawned again.
+    pub fn set_auto_cull(&mut self, cull: bool) {
+        self.auto_flags.set(AutoFlags::AUTO_CULL, cull);
+    }
+
     /// Returns an option containing a Dimension2.
     pub(crate) fn auto_spawn(&self) -> Option<Dimension2> {
         self.auto_spawn
@@ -1970,6 +2630,21 @@ impl Tilemap {
         self.auto_spawn = Some(dimension);
     }
 
+    /// Returns a copy of the tilemap's dimensions in chunks, if any.
+    pub(crate) fn dimensions(&self) -> Option<Dimension2> {
+        self.dimensions
+    }
+
+    /// Returns the raw bits of the auto flags.
+    pub(crate) fn auto_flags(&self) -> u16 {
+        self.auto_flags.bits()
+    }
+
+    /// Sets the auto flags from raw bits, ignoring unknown flags.
+    pub(crate) fn set_auto_flags(&mut self, bits: u16) {
+        self.auto_flags = AutoFlags::from_bits_truncate(bits);
+    }
+
     /// Returns a copy of the chunk's dimensions.
     pub(crate) fn chunk_dimensions(&self) -> Dimension2 {
         self.chunk_dimensions
@@ -1991,19 +2666,523 @@ impl Tilemap {
     }
 
     /// Returns a reference to the layers in the tilemap.
-    pub(crate) fn layers(&self) -> Vec<Option<TilemapLayer>> {
-        self.layers.clone()
+    pub(crate) fn layers(&self) -> &[Option<TilemapLayer>] {
+        &self.layers
+    }
+
+    /// Returns the kind of each layer.
+    pub(crate) fn layer_kinds(&self) -> Vec<Option<LayerKind>> {
+        self.layers
+            .iter()
+            .map(|layer| layer.as_ref().map(|layer| layer.kind))
+            .collect()
+    }
+
+    /// Returns the number of z layers, including the empty ones.
+    pub(crate) fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns a reference to the inner chunks.
+    pub(crate) fn chunks(&self) -> &HashMap<Point2, Chunk> {
+        &self.chunks
     }
 
     /// Returns a mutable reference to the inner chunks.
     pub(crate) fn chunks_mut(&mut self) -> &mut HashMap<Point2, Chunk> {
         &mut self.chunks
     }
+
+    /// Returns how fog layers are rendered.
+    pub fn fog_style(&self) -> FogStyle {
+        self.fog_style
+    }
+
+    /// Returns a mutable reference to how fog layers are rendered.
+    pub(crate) fn fog_style_mut(&mut self) -> &mut FogStyle {
+        &mut self.fog_style
+    }
+
+    /// Returns a reference to the keys tile data is serialized by.
+    #[cfg(feature = "serde")]
+    pub(crate) fn tile_data_keys(&self) -> &HashMap<TypeId, &'static str> {
+        &self.tile_data_keys
+    }
+
+    /// Returns a mutable reference to the keys tile data is serialized by.
+    #[cfg(feature = "serde")]
+    pub(crate) fn tile_data_keys_mut(&mut self) -> &mut HashMap<TypeId, &'static str> {
+        &mut self.tile_data_keys
+    }
+
+    /// Returns a reference to the animations.
+    pub(crate) fn animations(&self) -> &Animations {
+        &self.animations
+    }
+
+    /// Returns a mutable reference to the animations.
+    pub(crate) fn animations_mut(&mut self) -> &mut Animations {
+        &mut self.animations
+    }
+}
+
+/// Returns a builder for the tilemaps of unit tests, with chunks of 8 by 8
+/// tiles of 32 by 32 pixels and a default texture atlas.
+#[cfg(test)]
+pub(crate) fn test_builder() -> TilemapBuilder {
+    Tilemap::builder()
+        .texture_atlas(Handle::default())
+        .chunk_dimensions(8, 8)
+        .tile_dimensions(32, 32)
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+
+    /// Checks that every tile in a few chunks round-trips through its world
+    /// position, as well as positions close to the edges of the tile.
+    fn assert_round_trip(topology: GridTopology, tile_width: u32, tile_height: u32) {
+        let tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .topology(topology)
+            .chunk_dimensions(8, 6)
+            .tile_dimensions(tile_width, tile_height)
+            .finish()
+            .unwrap();
+        let mut transform = Transform::from_translation(Vec3::new(-120.0, 64.0, 0.0));
+        transform.scale = Vec3::new(2.0, 0.5, 1.0);
+
+        for y in -20..20 {
+            for x in -20..20 {
+                let point = Point2::new(x, y);
+                let center = tilemap.tile_to_world(point);
+                assert_eq!(
+                    tilemap.world_to_tile(center, &Transform::default()),
+                    point,
+                    "{:?} at the tile center",
+                    topology
+                );
+                let nudge = Vec2::new(tile_width as f32 * 0.2, tile_height as f32 * 0.2);
+                assert_eq!(
+                    tilemap.world_to_tile(center + nudge, &Transform::default()),
+                    point,
+                    "{:?} next to the tile center",
+                    topology
+                );
+                let world = transform.mul_vec3(center.extend(0.0)).truncate();
+                assert_eq!(
+                    tilemap.world_to_tile(world, &transform),
+                    point,
+                    "{:?} with a transform",
+                    topology
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_square() {
+        assert_round_trip(GridTopology::Square, 32, 32);
+        assert_round_trip(GridTopology::Square, 30, 17);
+    }
+
+    #[test]
+    fn round_trip_hex_x() {
+        assert_round_trip(GridTopology::HexX, 32, 32);
+        assert_round_trip(GridTopology::HexX, 30, 26);
+    }
+
+    #[test]
+    fn round_trip_hex_y() {
+        assert_round_trip(GridTopology::HexY, 32, 32);
+        assert_round_trip(GridTopology::HexY, 26, 30);
+    }
+
+    #[test]
+    fn round_trip_hex_even_rows() {
+        assert_round_trip(GridTopology::HexEvenRows, 32, 32);
+        assert_round_trip(GridTopology::HexEvenRows, 26, 30);
+    }
+
+    #[test]
+    fn round_trip_hex_odd_rows() {
+        assert_round_trip(GridTopology::HexOddRows, 32, 32);
+        assert_round_trip(GridTopology::HexOddRows, 26, 30);
+    }
+
+    #[test]
+    fn round_trip_hex_even_cols() {
+        assert_round_trip(GridTopology::HexEvenCols, 32, 32);
+        assert_round_trip(GridTopology::HexEvenCols, 30, 26);
+    }
+
+    #[test]
+    fn round_trip_hex_odd_cols() {
+        assert_round_trip(GridTopology::HexOddCols, 32, 32);
+        assert_round_trip(GridTopology::HexOddCols, 30, 26);
+    }
+
+    /// Checks that the tiles of the computed geometry are drawn around the
+    /// tile centers.
+    fn assert_geometry(topology: GridTopology, tile_width: u32, tile_height: u32) {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .topology(topology)
+            .chunk_dimensions(8, 6)
+            .tile_dimensions(tile_width, tile_height)
+            .add_layer(TilemapLayer::default(), 0)
+            .finish()
+            .unwrap();
+        let texture_atlas = TextureAtlas::from_grid(
+            Handle::default(),
+            Vec2::new(tile_width as f32, tile_height as f32),
+            1,
+            1,
+        );
+
+        for chunk_point in [(0, 0), (1, -1)].iter() {
+            tilemap.insert_chunk(*chunk_point).unwrap();
+            let geometry = tilemap
+                .chunk_geometry(*chunk_point, 0, &texture_atlas)
+                .unwrap();
+            assert_eq!(geometry.positions.len(), 8 * 6 * 4);
+            assert_eq!(geometry.indices.len(), 8 * 6 * 6);
+            for y in 0..6 {
+                for x in 0..8 {
+                    let index = (y * 8 + x) as usize;
+                    let point = Point2::new(chunk_point.0 * 8 + x - 4, chunk_point.1 * 6 + y - 3);
+                    let center = tilemap.tile_to_world(point);
+                    let vertices = &geometry.positions[index * 4..index * 4 + 4];
+                    let drawn_x = vertices.iter().map(|vertex| vertex[0]).sum::<f32>() / 4.0;
+                    let drawn_y = vertices.iter().map(|vertex| vertex[1]).sum::<f32>() / 4.0;
+                    assert!(
+                        (drawn_x - center.x).abs() <= 1.0 && (drawn_y - center.y).abs() <= 1.0,
+                        "{:?} at {:?}",
+                        topology,
+                        point
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn geometry_square() {
+        assert_geometry(GridTopology::Square, 32, 32);
+    }
+
+    #[test]
+    fn geometry_hex() {
+        assert_geometry(GridTopology::HexX, 32, 32);
+        assert_geometry(GridTopology::HexY, 32, 32);
+        assert_geometry(GridTopology::HexEvenRows, 26, 30);
+        assert_geometry(GridTopology::HexOddRows, 26, 30);
+        assert_geometry(GridTopology::HexEvenCols, 30, 26);
+        assert_geometry(GridTopology::HexOddCols, 30, 26);
+    }
+
+    #[test]
+    fn geometry_isometric() {
+        assert_geometry(GridTopology::Isometric, 64, 32);
+        assert_geometry(GridTopology::StaggeredIsometric, 64, 32);
+    }
+
+    #[test]
+    fn geometry_flips_uvs() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .chunk_dimensions(8, 8)
+            .tile_dimensions(32, 32)
+            .finish()
+            .unwrap();
+        // Sprite 1 is the right half of the texture.
+        let texture_atlas = TextureAtlas::from_grid(Handle::default(), Vec2::new(32.0, 32.0), 2, 1);
+        let flips = [
+            TileFlip::empty(),
+            TileFlip::HORIZONTAL,
+            TileFlip::VERTICAL,
+            TileFlip::ROTATE_90,
+            TileFlip::ROTATE_180,
+        ];
+        let tiles = flips.iter().enumerate().map(|(x, flip)| Tile {
+            point: (x as i32 - 2, 0),
+            sprite_index: 1,
+            flip: *flip,
+            ..Default::default()
+        });
+        tilemap.insert_tiles(tiles).unwrap();
+        let geometry = tilemap.chunk_geometry((0, 0), 0, &texture_atlas).unwrap();
+
+        // The corners of a quad are bottom left, top left, top right and
+        // bottom right, while the texture coordinates grow downwards.
+        let expected = [
+            [[0.5, 1.0], [0.5, 0.0], [1.0, 0.0], [1.0, 1.0]],
+            [[1.0, 1.0], [1.0, 0.0], [0.5, 0.0], [0.5, 1.0]],
+            [[0.5, 0.0], [0.5, 1.0], [1.0, 1.0], [1.0, 0.0]],
+            [[1.0, 1.0], [0.5, 1.0], [0.5, 0.0], [1.0, 0.0]],
+            [[1.0, 0.0], [1.0, 1.0], [0.5, 1.0], [0.5, 0.0]],
+        ];
+        for (x, uvs) in expected.iter().enumerate() {
+            let index = 34 + x;
+            assert_eq!(
+                geometry.uvs[index * 4..index * 4 + 4].to_vec(),
+                uvs.to_vec(),
+                "{:?}",
+                flips[x]
+            );
+        }
+        // Flipping the sprite does not move it.
+        let center = tilemap.tile_to_world((1, 0));
+        let min = geometry.positions[37 * 4];
+        assert_eq!((min[0], min[1]), (center.x - 16.0, center.y - 16.0));
+    }
+
+    #[test]
+    fn geometry_anchors_oversized_sprites() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .chunk_dimensions(8, 8)
+            .tile_dimensions(32, 32)
+            .add_layer(
+                TilemapLayer {
+                    anchor: TileAnchor::Bottom,
+                    ..Default::default()
+                },
+                1,
+            )
+            .finish()
+            .unwrap();
+        let texture_atlas = TextureAtlas::from_grid(Handle::default(), Vec2::new(32.0, 96.0), 1, 1);
+        for z_order in 0..2 {
+            tilemap
+                .insert_tile(Tile {
+                    point: (0, 0),
+                    z_order,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let center = tilemap.tile_to_world((0, 0));
+
+        // Bottom left and top right corners of the tile at (0, 0).
+        let corners = |z_order| {
+            let geometry = tilemap
+                .chunk_geometry((0, 0), z_order, &texture_atlas)
+                .unwrap();
+            let bottom_left = geometry.positions[36 * 4];
+            let top_right = geometry.positions[36 * 4 + 2];
+            (
+                Vec2::new(bottom_left[0], bottom_left[1]) - center,
+                Vec2::new(top_right[0], top_right[1]) - center,
+            )
+        };
+        assert_eq!(corners(0), (Vec2::new(-16.0, -48.0), Vec2::new(16.0, 48.0)));
+        assert_eq!(corners(1), (Vec2::new(-16.0, -16.0), Vec2::new(16.0, 80.0)));
+    }
+
+    #[test]
+    fn oversized_sprites_are_not_culled() {
+        use crate::chunk::system::{chunk_bounds, is_in_view};
+
+        let tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .chunk_dimensions(4, 4)
+            .tile_dimensions(32, 32)
+            .add_layer(
+                TilemapLayer {
+                    anchor: TileAnchor::Bottom,
+                    ..Default::default()
+                },
+                0,
+            )
+            .finish()
+            .unwrap();
+        let texture_atlas = TextureAtlas::from_grid(Handle::default(), Vec2::new(32.0, 96.0), 1, 1);
+
+        let cell_bounds = chunk_bounds(&tilemap, 0, None);
+        assert_eq!(
+            cell_bounds,
+            (Vec2::new(-64.0, -64.0), Vec2::new(64.0, 64.0))
+        );
+        let sprite_bounds = chunk_bounds(&tilemap, 0, Some(&texture_atlas));
+        assert_eq!(
+            sprite_bounds,
+            (Vec2::new(-64.0, -64.0), Vec2::new(64.0, 128.0))
+        );
+
+        // A view above the chunk only sees the tops of its tallest sprites.
+        let views = [(Vec2::new(-10.0, 100.0), Vec2::new(10.0, 200.0))];
+        assert!(!is_in_view(cell_bounds.0, cell_bounds.1, &views));
+        assert!(is_in_view(sprite_bounds.0, sprite_bounds.1, &views));
+        let views = [(Vec2::new(-10.0, 130.0), Vec2::new(10.0, 200.0))];
+        assert!(!is_in_view(sprite_bounds.0, sprite_bounds.1, &views));
+    }
+
+    #[test]
+    fn dirty_tiles_update_in_ranges() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .chunk_dimensions(8, 4)
+            .tile_dimensions(32, 32)
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        let tiles = vec![(-4, -2), (-3, -2), (0, 0), (3, 1)]
+            .into_iter()
+            .map(|point| Tile {
+                point,
+                sprite_index: 3,
+                ..Default::default()
+            });
+        tilemap.insert_tiles(tiles).unwrap();
+
+        let chunk = tilemap.chunks_mut().get_mut(&Point2::new(0, 0)).unwrap();
+        assert_eq!(chunk.take_dirty_ranges(0, 32), vec![0..2, 20..21, 31..32]);
+        assert!(chunk.take_dirty_ranges(0, 32).is_empty());
+
+        let style = FogStyle::default();
+        let (indexes, colors, flips) = chunk
+            .tiles_to_renderer_parts(0, Dimension2::new(8, 4), &style)
+            .unwrap();
+        let (range_indexes, range_colors, range_flips) =
+            chunk.tiles_to_renderer_range(0, 18..22, &style).unwrap();
+        assert_eq!(range_indexes, indexes[18 * 4..22 * 4].to_vec());
+        assert_eq!(range_colors, colors[18 * 4..22 * 4].to_vec());
+        assert_eq!(range_flips, flips[18 * 4..22 * 4].to_vec());
+    }
+
+    #[test]
+    fn despawned_chunks_forget_their_entities() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .chunk_dimensions(8, 8)
+            .tile_dimensions(32, 32)
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        tilemap
+            .insert_tile(Tile {
+                point: (1, 1),
+                ..Default::default()
+            })
+            .unwrap();
+        // As the tilemap system does when it spawns the chunk.
+        tilemap.spawn_chunk((0, 0)).unwrap();
+        tilemap.spawned_chunks_mut().insert((0, 0));
+        let point = Point2::new(0, 0);
+        let entity = Entity::new(7);
+        tilemap
+            .chunks_mut()
+            .get_mut(&point)
+            .unwrap()
+            .add_entity(0, entity);
+
+        tilemap.despawn_chunk((0, 0)).unwrap();
+        assert_eq!(tilemap.get_chunk(&point).unwrap().get_entity(0), None);
+        for _ in 0..2 {
+            tilemap.chunk_events_update();
+            tilemap.collision_events_update();
+        }
+
+        // Edits of a despawned chunk do not modify the entity which is gone,
+        // and only tiles which exist are edited.
+        tilemap.get_tile_mut((1, 1), 0).unwrap().index = 3;
+        assert!(tilemap.get_tile_mut((2, 2), 0).is_none());
+        let mut reader = tilemap.chunk_events().get_reader();
+        assert_eq!(reader.iter(tilemap.chunk_events()).count(), 0);
+        let mut reader = tilemap.collision_events().get_reader();
+        assert_eq!(reader.iter(tilemap.collision_events()).count(), 1);
+
+        tilemap.spawn_chunk((0, 0)).unwrap();
+        let mut reader = tilemap.chunk_events().get_reader();
+        assert_eq!(reader.iter(tilemap.chunk_events()).count(), 1);
+        assert_eq!(tilemap.get_tile(Point2::new(1, 1), 0).unwrap().index, 3);
+    }
+
+    #[test]
+    fn cleared_tiles_mark_only_changed_chunks() {
+        let mut tilemap = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .chunk_dimensions(8, 8)
+            .tile_dimensions(32, 32)
+            .finish()
+            .unwrap();
+        tilemap.insert_chunk((0, 0)).unwrap();
+        tilemap.insert_chunk((1, 0)).unwrap();
+        tilemap
+            .insert_tiles(vec![
+                Tile {
+                    point: (1, 1),
+                    ..Default::default()
+                },
+                Tile {
+                    point: (9, 1),
+                    ..Default::default()
+                },
+            ])
+            .unwrap();
+        // As the tilemap system does when it spawns the chunks.
+        for (point, entity) in [(Point2::new(0, 0), 1), (Point2::new(1, 0), 2)].iter() {
+            let chunk = tilemap.chunks_mut().get_mut(point).unwrap();
+            chunk.add_entity(0, Entity::new(*entity));
+            chunk.take_dirty_ranges(0, 64);
+        }
+        for _ in 0..2 {
+            tilemap.chunk_events_update();
+        }
+        tilemap.take_dirty_chunks();
+
+        tilemap
+            .clear_tiles(vec![((2, 2), 0), ((10, 2), 0)])
+            .unwrap();
+        assert!(!tilemap.has_dirty_chunks());
+        let mut reader = tilemap.chunk_events().get_reader();
+        assert_eq!(reader.iter(tilemap.chunk_events()).count(), 0);
+
+        tilemap
+            .clear_tiles(vec![((1, 1), 0), ((10, 2), 0)])
+            .unwrap();
+        let mut dirty_chunks = HashSet::default();
+        dirty_chunks.insert(Point2::new(0, 0));
+        assert_eq!(tilemap.take_dirty_chunks(), dirty_chunks);
+        let mut reader = tilemap.chunk_events().get_reader();
+        assert_eq!(reader.iter(tilemap.chunk_events()).count(), 1);
+        let chunk = tilemap.chunks_mut().get_mut(&Point2::new(0, 0)).unwrap();
+        assert_eq!(chunk.take_dirty_ranges(0, 64), vec![45..46]);
+    }
+
+    #[test]
+    fn round_trip_isometric() {
+        assert_round_trip(GridTopology::Isometric, 64, 32);
+        assert_round_trip(GridTopology::Isometric, 30, 16);
+    }
+
+    #[test]
+    fn staggered_isometric_chunks_have_even_heights() {
+        let builder = Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .topology(GridTopology::StaggeredIsometric)
+            .tile_dimensions(64, 32);
+        assert_eq!(
+            builder.clone().chunk_dimensions(8, 5).finish().unwrap_err(),
+            TilemapError::from(ErrorKind::OddStaggeredChunkHeight(5))
+        );
+        assert!(builder.chunk_dimensions(5, 8).finish().is_ok());
+        assert!(Tilemap::builder()
+            .texture_atlas(Handle::default())
+            .topology(GridTopology::Isometric)
+            .chunk_dimensions(8, 5)
+            .tile_dimensions(64, 32)
+            .finish()
+            .is_ok());
+    }
+
+    #[test]
+    fn round_trip_staggered_isometric() {
+        assert_round_trip(GridTopology::StaggeredIsometric, 64, 32);
+        assert_round_trip(GridTopology::StaggeredIsometric, 30, 16);
+    }
 
     // fn new_tilemap_no_auto() -> Tilemap {
     //     let texture_atlas_handle = Handle::weak(Handllet modified_layer = layer_query.get()eId::random::<TextureAtlas>());